@@ -1,12 +1,94 @@
-use clap::Parser;
+use bytesize::ByteSize;
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
+use std::fmt;
 use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::delete::DeleteStrategy;
+use crate::profiles::PROFILES;
+use crate::scanner::{parse_age, parse_size, Query, ResultFilter};
 
 #[derive(Parser, Debug, Clone)]
 #[command(name = "cache-sweep")]
 #[command(about = "Find and delete dependency/cache folders to reclaim disk space")]
-#[command(version, disable_version_flag = true, arg(clap::Arg::new("version").short('v').long("version").action(clap::ArgAction::Version).global(true)))]
+#[command(version, propagate_version = true, disable_version_flag = true, arg(clap::Arg::new("version").short('v').long("version").action(clap::ArgAction::Version).global(true)))]
+#[command(args_conflicts_with_subcommands = true)]
 #[allow(clippy::struct_excessive_bools)] // CLI args naturally have many boolean flags
 pub struct Args {
+    #[command(flatten)]
+    pub scan: ScanArgs,
+
+    /// Sort by: size, path, or age
+    #[arg(short = 's', long, default_value = "size")]
+    pub sort: String,
+
+    /// Simulate deletion without actually deleting
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Stream each result as JSON (one object per line)
+    #[arg(long)]
+    pub json_stream: bool,
+
+    /// Output all results as single JSON object
+    #[arg(long)]
+    pub json: bool,
+
+    /// Print all results as a table for spreadsheets, or as metrics, instead of the TUI
+    #[arg(long, value_name = "FORMAT", conflicts_with_all = ["json", "json_stream"])]
+    pub format: Option<OutputFormat>,
+
+    /// Write `--format` output to this file (atomically) instead of stdout
+    #[arg(short = 'o', long, value_name = "FILE", requires = "format")]
+    pub output: Option<PathBuf>,
+
+    /// Write a self-contained HTML report (treemap, breakdowns, results) instead of the TUI
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["json", "json_stream", "format"])]
+    pub report: Option<PathBuf>,
+
+    /// Print only the matched paths, one per line
+    #[arg(long, conflicts_with_all = ["json", "json_stream", "format", "report", "print0"])]
+    pub paths: bool,
+
+    /// Print only the matched paths, each followed by a NUL byte (for `xargs -0`)
+    #[arg(long, conflicts_with_all = ["json", "json_stream", "format", "report"])]
+    pub print0: bool,
+
+    #[command(flatten)]
+    pub strategy: StrategyArgs,
+
+    /// Number of deletions `u` can undo in the TUI (0 deletes immediately)
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    pub undo_depth: usize,
+
+    /// Where `A` (archive) writes tarballs [default: `$XDG_DATA_HOME/cache-sweep/archives`]
+    #[arg(long, value_name = "DIR")]
+    pub archive_dir: Option<PathBuf>,
+
+    /// Once sizes are in, propose folders that free this much (e.g., 20GB) in the TUI
+    #[arg(long, value_name = "SIZE", value_parser = parse_size,
+          conflicts_with_all = ["json", "json_stream", "format", "report", "paths", "print0",
+                                "fail_over", "fail_count"])]
+    pub free: Option<u64>,
+
+    /// Exit with code 3 if the reclaimable total is over SIZE, or one profile's with
+    /// PROFILE=SIZE (comma-separated or repeated, e.g., 50GB,node=20GB)
+    #[arg(long, value_name = "[PROFILE=]SIZE", value_delimiter = ',', value_parser = parse_threshold)]
+    pub fail_over: Vec<SizeThreshold>,
+
+    /// Exit with code 3 if more than N reclaimable folders are found
+    #[arg(long, value_name = "N")]
+    pub fail_count: Option<usize>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// What to scan and which results to keep; shared by the TUI, the JSON modes and `clean`
+#[derive(clap::Args, Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)] // CLI args naturally have many boolean flags
+pub struct ScanArgs {
     /// Select profiles to search (comma-separated). Use without value to list.
     #[arg(short = 'p', long, value_delimiter = ',', conflicts_with = "targets")]
     pub profiles: Option<Vec<String>>,
@@ -23,10 +105,6 @@ pub struct Args {
     #[arg(short = 'f', long)]
     pub full: bool,
 
-    /// Sort by: size, path, or age
-    #[arg(short = 's', long, default_value = "size")]
-    pub sort: String,
-
     /// Search for specific folder names (disables profiles)
     #[arg(short = 't', long, value_delimiter = ',')]
     pub targets: Option<Vec<String>>,
@@ -35,28 +113,305 @@ pub struct Args {
     #[arg(short = 'X', long)]
     pub show_protected: bool,
 
-    /// Simulate deletion without actually deleting
+    /// Follow symbolic links (default: false for safety)
+    #[arg(long)]
+    pub follow_links: bool,
+
+    /// Respect .gitignore files (default: false - scan everything)
+    #[arg(long)]
+    pub respect_ignore: bool,
+
+    /// Treat folders with files changed within this many minutes as active builds (0 disables)
+    #[arg(long, value_name = "MINUTES", default_value_t = 10)]
+    pub active_window: u64,
+
+    /// Only folders at least this big (e.g., 100MB, 1.5G)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub min_size: Option<u64>,
+
+    /// Only folders at most this big
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub max_size: Option<u64>,
+
+    /// Only folders untouched for at least this long (e.g., 90d, 3mo, 1y)
+    #[arg(long, value_name = "AGE", value_parser = parse_age)]
+    pub older_than: Option<Duration>,
+
+    /// Only folders changed within this long
+    #[arg(long, value_name = "AGE", value_parser = parse_age)]
+    pub newer_than: Option<Duration>,
+
+    /// Only folders matching this expression, e.g. "size>1G age>90d profile:node !path:work/"
+    #[arg(long = "filter", value_name = "QUERY", value_parser = Query::parse)]
+    pub query: Option<Query>,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Comma-separated values
+    Csv,
+    /// Tab-separated values
+    Tsv,
+    /// Prometheus metrics, for the node exporter textfile collector
+    Prometheus,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+#[allow(clippy::large_enum_variant)] // Parsed once at startup; boxing would only complicate matching
+pub enum Command {
+    /// Manage folders cache-sweep moved to the trash
+    #[command(subcommand)]
+    Trash(TrashCommand),
+    /// List past deletions and the space they freed per month
+    History(HistoryArgs),
+    /// Delete matching folders without the TUI, for scripts and cron
+    Clean(CleanArgs),
+    /// Delete the folders listed in a saved `--json` result, re-checking each one first
+    Apply(ApplyArgs),
+    /// Unpack archived folders back to where they were (lists archives without arguments)
+    Restore {
+        /// Archive file or original path (as shown by `restore`)
+        items: Vec<String>,
+    },
+}
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct HistoryArgs {
+    /// Only deletions on or after this day (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE", value_parser = parse_date)]
+    pub since: Option<NaiveDate>,
+
+    /// Only deletions on or before this day (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE", value_parser = parse_date)]
+    pub until: Option<NaiveDate>,
+
+    /// Only folders under this directory
+    #[arg(long, value_name = "DIR")]
+    pub path: Option<PathBuf>,
+
+    /// Only this folder name (e.g., `node_modules`)
+    #[arg(long, value_name = "NAME")]
+    pub target: Option<String>,
+
+    /// Only deletions that failed or were left incomplete
+    #[arg(long)]
+    pub failed: bool,
+
+    /// Print the monthly totals only
+    #[arg(long)]
+    pub summary: bool,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)] // CLI args naturally have many boolean flags
+pub struct CleanArgs {
+    #[command(flatten)]
+    pub scan: ScanArgs,
+
+    /// Actually delete (without it, `clean` only runs with `--dry-run`)
+    #[arg(short = 'y', long)]
+    pub yes: bool,
+
+    /// List what would be deleted without deleting anything
     #[arg(long)]
     pub dry_run: bool,
 
-    /// Stream each result as JSON (one object per line)
+    /// Print a JSON report instead of a table
     #[arg(long)]
-    pub json_stream: bool,
+    pub json: bool,
 
-    /// Output all results as single JSON object
+    #[command(flatten)]
+    pub strategy: StrategyArgs,
+
+    /// Only folders whose path contains this text (case-insensitive)
+    #[arg(long = "match", value_name = "TEXT")]
+    pub pattern: Option<String>,
+
+    /// Also delete folders that look like a build is writing to them (see `--active-window`)
+    #[arg(long)]
+    pub include_active: bool,
+
+    /// Delete only enough to free this much, stalest and cheapest to rebuild first (e.g., 20GB)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub free: Option<u64>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)] // CLI args naturally have many boolean flags
+pub struct ApplyArgs {
+    /// Result file written by `cache-sweep --json` (and perhaps trimmed by hand)
+    #[arg(value_name = "PLAN")]
+    pub plan: PathBuf,
+
+    /// Actually delete (without it, `apply` only runs with `--dry-run`)
+    #[arg(short = 'y', long)]
+    pub yes: bool,
+
+    /// Check every folder in the plan without deleting anything
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Print a JSON report instead of a table
     #[arg(long)]
     pub json: bool,
 
-    /// Follow symbolic links (default: false for safety)
+    #[command(flatten)]
+    pub strategy: StrategyArgs,
+
+    /// How far a folder's size may drift from the plan, as a percentage or a size (e.g., 50MB)
+    #[arg(long, value_name = "AMOUNT", default_value = "10%", value_parser = parse_tolerance)]
+    pub tolerance: SizeTolerance,
+
+    /// Treat folders with files changed within this many minutes as active builds (0 disables)
+    #[arg(long, value_name = "MINUTES", default_value_t = 10)]
+    pub active_window: u64,
+
+    /// Also delete folders that look like a build is writing to them (see `--active-window`)
     #[arg(long)]
-    pub follow_links: bool,
+    pub include_active: bool,
 
-    /// Respect .gitignore files (default: false - scan everything)
+    /// Also accept folders with these names (for plans scanned with `--targets`)
+    #[arg(short = 't', long, value_delimiter = ',')]
+    pub targets: Vec<String>,
+}
+
+impl ApplyArgs {
+    /// Returns the active-build window, or `None` when the guard is disabled
+    pub const fn active_window(&self) -> Option<Duration> {
+        active_window(self.active_window)
+    }
+}
+
+/// How folders are removed; shared by the TUI, `clean` and `apply`
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct StrategyArgs {
+    /// Move folders to the trash instead of deleting them permanently
     #[arg(long)]
-    pub respect_ignore: bool,
+    pub trash: bool,
+}
+
+impl StrategyArgs {
+    pub const fn delete_strategy(&self) -> DeleteStrategy {
+        if self.trash {
+            DeleteStrategy::Trash
+        } else {
+            DeleteStrategy::Delete
+        }
+    }
+}
+
+/// `--active-window` minutes as a duration (0 disables the guard)
+const fn active_window(minutes: u64) -> Option<Duration> {
+    if minutes == 0 {
+        None
+    } else {
+        Some(Duration::from_secs(minutes.saturating_mul(60)))
+    }
+}
+
+/// How much a folder may have grown or shrunk since it was planned for deletion
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeTolerance {
+    /// Relative to the planned size
+    Percent(u64),
+    Bytes(u64),
+}
+
+impl SizeTolerance {
+    pub const fn allows(self, planned: u64, current: u64) -> bool {
+        let slack = match self {
+            Self::Percent(percent) => planned.saturating_mul(percent) / 100,
+            Self::Bytes(bytes) => bytes,
+        };
+        planned.abs_diff(current) <= slack
+    }
+}
+
+impl fmt::Display for SizeTolerance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Percent(percent) => write!(f, "{percent}%"),
+            Self::Bytes(bytes) => write!(f, "{}", ByteSize::b(*bytes)),
+        }
+    }
+}
+
+fn parse_tolerance(value: &str) -> Result<SizeTolerance, String> {
+    value.trim().strip_suffix('%').map_or_else(
+        || parse_size(value).map(SizeTolerance::Bytes),
+        |percent| {
+            percent
+                .trim()
+                .parse()
+                .map(SizeTolerance::Percent)
+                .map_err(|e| format!("invalid percentage: {e}"))
+        },
+    )
+}
+
+/// A `--fail-over` limit, on the whole scan or on one profile's folders
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeThreshold {
+    pub profile: Option<String>,
+    pub limit: u64,
+}
+
+fn parse_threshold(value: &str) -> Result<SizeThreshold, String> {
+    let Some((profile, size)) = value.split_once('=') else {
+        return parse_size(value).map(|limit| SizeThreshold {
+            profile: None,
+            limit,
+        });
+    };
+    let profile = profile.trim();
+    if !PROFILES.contains_key(profile) {
+        return Err(format!(
+            "unknown profile {profile:?} (run `cache-sweep -p` to list them)"
+        ));
+    }
+    Ok(SizeThreshold {
+        profile: Some(profile.to_string()),
+        limit: parse_size(size)?,
+    })
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|e| format!("expected YYYY-MM-DD: {e}"))
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum TrashCommand {
+    /// List trashed folders
+    List,
+    /// Move trashed folders back to where they were
+    Restore {
+        /// Original path (or name in the trash, as shown by `trash list`)
+        #[arg(required = true)]
+        items: Vec<String>,
+    },
+    /// Permanently delete everything cache-sweep moved to the trash
+    Empty,
 }
 
 impl Args {
+    /// Whether to print results instead of starting the TUI
+    pub const fn is_non_interactive(&self) -> bool {
+        self.json
+            || self.json_stream
+            || self.format.is_some()
+            || self.report.is_some()
+            || self.paths
+            || self.print0
+            || self.has_thresholds()
+    }
+
+    /// Whether `--fail-over` or `--fail-count` is set (alone, they check without printing results)
+    pub const fn has_thresholds(&self) -> bool {
+        !self.fail_over.is_empty() || self.fail_count.is_some()
+    }
+}
+
+impl ScanArgs {
     pub fn effective_directory(&self) -> PathBuf {
         if self.full {
             dirs::home_dir().unwrap_or_else(|| self.directory.clone())
@@ -65,6 +420,20 @@ impl Args {
         }
     }
 
+    /// Returns the active-build window, or `None` when the guard is disabled
+    pub const fn active_window(&self) -> Option<Duration> {
+        active_window(self.active_window)
+    }
+
+    pub const fn filter(&self) -> ResultFilter {
+        ResultFilter {
+            min_size: self.min_size,
+            max_size: self.max_size,
+            older_than: self.older_than,
+            newer_than: self.newer_than,
+        }
+    }
+
     pub fn effective_targets(&self) -> Vec<String> {
         self.targets.as_ref().map_or_else(
             || {
@@ -86,54 +455,259 @@ mod tests {
 
     fn default_args() -> Args {
         Args {
-            profiles: None,
-            directory: PathBuf::from("."),
-            exclude: None,
-            full: false,
+            scan: ScanArgs {
+                profiles: None,
+                directory: PathBuf::from("."),
+                exclude: None,
+                full: false,
+                targets: None,
+                show_protected: false,
+                follow_links: false,
+                respect_ignore: false,
+                active_window: 10,
+                min_size: None,
+                max_size: None,
+                older_than: None,
+                newer_than: None,
+                query: None,
+            },
             sort: "size".to_string(),
-            targets: None,
-            show_protected: false,
             dry_run: false,
             json_stream: false,
             json: false,
-            follow_links: false,
-            respect_ignore: false,
+            format: None,
+            output: None,
+            report: None,
+            paths: false,
+            print0: false,
+            strategy: StrategyArgs::default(),
+            undo_depth: 10,
+            archive_dir: None,
+            free: None,
+            fail_over: Vec::new(),
+            fail_count: None,
+            command: None,
         }
     }
 
     #[test]
     fn test_effective_directory_default() {
         let args = default_args();
-        assert_eq!(args.effective_directory(), PathBuf::from("."));
+        assert_eq!(args.scan.effective_directory(), PathBuf::from("."));
     }
 
     #[test]
     fn test_effective_directory_custom() {
         let mut args = default_args();
-        args.directory = PathBuf::from("/custom/path");
-        assert_eq!(args.effective_directory(), PathBuf::from("/custom/path"));
+        args.scan.directory = PathBuf::from("/custom/path");
+        assert_eq!(
+            args.scan.effective_directory(),
+            PathBuf::from("/custom/path")
+        );
     }
 
     #[test]
     fn test_effective_directory_full_flag() {
         let mut args = default_args();
-        args.full = true;
-        let result = args.effective_directory();
+        args.scan.full = true;
+        let result = args.scan.effective_directory();
         // Should be home dir if available, otherwise fallback to directory
         assert!(result != Path::new(".") || dirs::home_dir().is_none());
     }
 
+    #[test]
+    fn test_active_window_minutes() {
+        let args = default_args();
+        assert_eq!(args.scan.active_window(), Some(Duration::from_mins(10)));
+    }
+
+    #[test]
+    fn test_active_window_zero_disables() {
+        let mut args = default_args();
+        args.scan.active_window = 0;
+        assert_eq!(args.scan.active_window(), None);
+    }
+
+    #[test]
+    fn test_delete_strategy_from_trash_flag() {
+        let mut args = default_args();
+        assert_eq!(args.strategy.delete_strategy(), DeleteStrategy::Delete);
+        args.strategy.trash = true;
+        assert_eq!(args.strategy.delete_strategy(), DeleteStrategy::Trash);
+    }
+
+    #[test]
+    fn test_parse_trash_restore_subcommand() {
+        let args =
+            Args::try_parse_from(["cache-sweep", "trash", "restore", "/a/node_modules"]).unwrap();
+        let Some(Command::Trash(TrashCommand::Restore { items })) = args.command else {
+            panic!("expected trash restore");
+        };
+        assert_eq!(items, vec!["/a/node_modules"]);
+    }
+
+    #[test]
+    fn test_clean_takes_scan_flags() {
+        let args = Args::try_parse_from([
+            "cache-sweep",
+            "clean",
+            "-d",
+            "/code",
+            "-p",
+            "node",
+            "--match",
+            "old-",
+            "--yes",
+        ])
+        .unwrap();
+        let Some(Command::Clean(clean)) = args.command else {
+            panic!("expected clean");
+        };
+        assert_eq!(clean.scan.directory, PathBuf::from("/code"));
+        assert_eq!(clean.scan.profiles, Some(vec!["node".to_string()]));
+        assert_eq!(clean.pattern.as_deref(), Some("old-"));
+        assert!(clean.yes && !clean.dry_run);
+    }
+
+    #[test]
+    fn test_parse_apply_tolerance() {
+        let args = Args::try_parse_from(["cache-sweep", "apply", "plan.json"]).unwrap();
+        let Some(Command::Apply(apply)) = args.command else {
+            panic!("expected apply");
+        };
+        assert_eq!(apply.plan, PathBuf::from("plan.json"));
+        assert_eq!(apply.tolerance, SizeTolerance::Percent(10));
+
+        assert_eq!(parse_tolerance("5 %"), Ok(SizeTolerance::Percent(5)));
+        assert_eq!(parse_tolerance("1KB"), Ok(SizeTolerance::Bytes(1000)));
+        assert!(parse_tolerance("-5%").is_err());
+        assert!(parse_tolerance("lots").is_err());
+    }
+
+    #[test]
+    fn test_size_tolerance_bounds() {
+        let percent = SizeTolerance::Percent(10);
+        assert!(percent.allows(1000, 1100));
+        assert!(percent.allows(1000, 900));
+        assert!(!percent.allows(1000, 1101));
+        assert!(!percent.allows(0, 1));
+        assert_eq!(percent.to_string(), "10%");
+
+        let bytes = SizeTolerance::Bytes(4096);
+        assert!(bytes.allows(0, 4096));
+        assert!(!bytes.allows(10_000, 5000));
+    }
+
+    #[test]
+    fn test_format_conflicts_with_json() {
+        let args = Args::try_parse_from(["cache-sweep", "--format", "tsv"]).unwrap();
+        assert_eq!(args.format, Some(OutputFormat::Tsv));
+        assert!(Args::try_parse_from(["cache-sweep", "--format", "xls"]).is_err());
+        assert!(Args::try_parse_from(["cache-sweep", "--format", "csv", "--json"]).is_err());
+        assert!(Args::try_parse_from(["cache-sweep", "-o", "metrics.prom"]).is_err());
+    }
+
+    #[test]
+    fn test_path_modes_are_non_interactive() {
+        assert!(!default_args().is_non_interactive());
+        let args = Args::try_parse_from(["cache-sweep", "--print0"]).unwrap();
+        assert!(args.print0 && args.is_non_interactive());
+        assert!(Args::try_parse_from(["cache-sweep", "--paths", "--print0"]).is_err());
+        assert!(Args::try_parse_from(["cache-sweep", "--paths", "--json"]).is_err());
+    }
+
+    #[test]
+    fn test_free_target() {
+        let args = Args::try_parse_from(["cache-sweep", "--free", "20GB"]).unwrap();
+        assert_eq!(args.free, Some(20_000_000_000));
+        assert!(Args::try_parse_from(["cache-sweep", "--free", "20GB", "--json"]).is_err());
+
+        let args = Args::try_parse_from(["cache-sweep", "clean", "--free", "1GiB"]).unwrap();
+        let Some(Command::Clean(clean)) = args.command else {
+            panic!("expected clean");
+        };
+        assert_eq!(clean.free, Some(1 << 30));
+    }
+
+    #[test]
+    fn test_fail_thresholds() {
+        let args = Args::try_parse_from([
+            "cache-sweep",
+            "--fail-over",
+            "50GB,node=20GB",
+            "--fail-over",
+            "rust=1GiB",
+            "--fail-count",
+            "100",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.fail_over,
+            vec![
+                SizeThreshold {
+                    profile: None,
+                    limit: 50_000_000_000
+                },
+                SizeThreshold {
+                    profile: Some("node".to_string()),
+                    limit: 20_000_000_000
+                },
+                SizeThreshold {
+                    profile: Some("rust".to_string()),
+                    limit: 1 << 30
+                },
+            ]
+        );
+        assert_eq!(args.fail_count, Some(100));
+        assert!(args.is_non_interactive());
+
+        assert!(Args::try_parse_from(["cache-sweep", "--fail-over", "cobol=1GB"]).is_err());
+        assert!(Args::try_parse_from(["cache-sweep", "--fail-over", "node=lots"]).is_err());
+        assert!(
+            Args::try_parse_from(["cache-sweep", "--fail-count", "1", "--free", "1GB"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_filter_query_is_parsed_up_front() {
+        let args =
+            Args::try_parse_from(["cache-sweep", "--json", "--filter", "size>1G !path:work/"])
+                .unwrap();
+        assert_eq!(args.scan.query.unwrap().source(), "size>1G !path:work/");
+
+        let error =
+            Args::try_parse_from(["cache-sweep", "clean", "--filter", "colour:red"]).unwrap_err();
+        assert!(error.to_string().contains("unknown field \"colour\""));
+    }
+
+    #[test]
+    fn test_parse_size_and_age_filters() {
+        let args =
+            Args::try_parse_from(["cache-sweep", "--min-size", "500MB", "--older-than", "3mo"])
+                .unwrap();
+        let filter = args.scan.filter();
+        assert_eq!(filter.min_size, Some(500_000_000));
+        assert_eq!(filter.older_than, Some(Duration::from_hours(24 * 90)));
+        assert!(filter.max_size.is_none() && filter.newer_than.is_none());
+
+        assert!(Args::try_parse_from(["cache-sweep", "--older-than", "90"]).is_err());
+        assert!(Args::try_parse_from(["cache-sweep", "clean", "--min-size", "big"]).is_err());
+    }
+
     #[test]
     fn test_effective_targets_explicit() {
         let mut args = default_args();
-        args.targets = Some(vec!["custom_target".to_string()]);
-        assert_eq!(args.effective_targets(), vec!["custom_target".to_string()]);
+        args.scan.targets = Some(vec!["custom_target".to_string()]);
+        assert_eq!(
+            args.scan.effective_targets(),
+            vec!["custom_target".to_string()]
+        );
     }
 
     #[test]
     fn test_effective_targets_default_all_profiles() {
         let args = default_args();
-        let targets = args.effective_targets();
+        let targets = args.scan.effective_targets();
         // Default is "all" - should include targets from multiple profiles
         assert!(targets.contains(&"node_modules".to_string()));
         assert!(targets.contains(&"__pycache__".to_string()));
@@ -143,8 +717,8 @@ mod tests {
     #[test]
     fn test_effective_targets_python_profile() {
         let mut args = default_args();
-        args.profiles = Some(vec!["python".to_string()]);
-        let targets = args.effective_targets();
+        args.scan.profiles = Some(vec!["python".to_string()]);
+        let targets = args.scan.effective_targets();
         assert!(targets.contains(&"__pycache__".to_string()));
         assert!(targets.contains(&".venv".to_string()));
     }
@@ -152,8 +726,8 @@ mod tests {
     #[test]
     fn test_effective_targets_multiple_profiles() {
         let mut args = default_args();
-        args.profiles = Some(vec!["node".to_string(), "rust".to_string()]);
-        let targets = args.effective_targets();
+        args.scan.profiles = Some(vec!["node".to_string(), "rust".to_string()]);
+        let targets = args.scan.effective_targets();
         assert!(targets.contains(&"node_modules".to_string()));
         assert!(targets.contains(&"target".to_string()));
     }
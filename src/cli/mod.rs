@@ -1,3 +1,6 @@
 mod args;
 
-pub use args::Args;
+pub use args::{
+    ApplyArgs, Args, CleanArgs, Command, HistoryArgs, OutputFormat, ScanArgs, SizeThreshold,
+    TrashCommand,
+};
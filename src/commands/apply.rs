@@ -0,0 +1,358 @@
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+use anyhow::{bail, Context, Result};
+use bytesize::ByteSize;
+use futures::{stream, StreamExt};
+use serde::Deserialize;
+use tokio_util::sync::CancellationToken;
+
+use super::clean::{active_reason, activity, clean_one, finish, Candidate};
+use crate::cli::ApplyArgs;
+use crate::delete::{
+    verify_identity, AuditLog, DeleteError, DirIdentity, Leftover, DELETE_CONCURRENCY,
+};
+use crate::output::{JsonPath, JSON_VERSION};
+use crate::profiles::profiles_for_target;
+use crate::risk::analyze_scan_result;
+use crate::scanner::{calculate_size, ProjectConfigs, ProjectMark, ScanResult};
+
+/// A `--json` result, as far as `apply` reads it (other fields are ignored)
+#[derive(Debug, Deserialize)]
+struct Plan {
+    version: u8,
+    results: Vec<PlanItem>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlanItem {
+    #[serde(flatten)]
+    path: JsonPath,
+    size: u64,
+    /// Null in plans written on Windows
+    #[serde(default)]
+    identity: Option<DirIdentity>,
+}
+
+pub async fn run(args: &ApplyArgs, cancel_token: CancellationToken) -> Result<()> {
+    if !args.yes && !args.dry_run {
+        bail!("refusing to delete without --yes (use --dry-run to check the plan)");
+    }
+
+    let start = Instant::now();
+    let plan = load(&args.plan)?;
+    let audit = AuditLog::from_env().ok();
+    let strategy = args.strategy.delete_strategy();
+
+    let results: Vec<_> = stream::iter(plan.results)
+        .map(|item| {
+            let (audit, cancel_token) = (audit.as_ref(), &cancel_token);
+            async move {
+                let candidate = check(item, args).await;
+                clean_one(candidate, strategy, args.dry_run, audit, cancel_token).await
+            }
+        })
+        .buffered(DELETE_CONCURRENCY)
+        .collect()
+        .await;
+
+    finish(&results, args.dry_run, args.json, strategy, start)
+}
+
+fn load(path: &Path) -> Result<Plan> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("cannot read plan {}", path.display()))?;
+    let plan: Plan = serde_json::from_str(&content)
+        .with_context(|| format!("{} is not a `cache-sweep --json` result", path.display()))?;
+    if plan.version != JSON_VERSION {
+        bail!(
+            "{} has format version {}, expected {JSON_VERSION} (write it again with this version's --json)",
+            path.display(),
+            plan.version
+        );
+    }
+    Ok(plan)
+}
+
+/// Re-checks a planned folder against the disk: it must still exist, be the same
+/// directory, not be protected, be a folder a scan would find, be about the size it
+/// was when planned, and not be in use by a build
+async fn check(item: PlanItem, args: &ApplyArgs) -> Candidate {
+    let path = match item.path.to_path_buf() {
+        Ok(path) => path,
+        Err(e) => {
+            let result = planned(PathBuf::from(&item.path.path), &item);
+            return skip(result, format!("invalid entry: {e}"));
+        }
+    };
+    let mut result = planned(path, &item);
+    let found = result.identity;
+    result.identity = item.identity;
+
+    match verify_identity(&result.path, item.identity.as_ref()) {
+        Ok(()) => {}
+        Err(DeleteError::NotFound(_)) => return skip(result, "missing: no longer exists".into()),
+        Err(DeleteError::IdentityChanged(_, change)) => {
+            return skip(result, format!("changed: {change}"));
+        }
+        Err(e) => return skip(result, format!("unreadable: {e}")),
+    }
+    if item.identity.is_none() && found.is_some() {
+        return skip(
+            result,
+            "changed: the plan doesn't record which directory it was".into(),
+        );
+    }
+
+    // Protected by a project config added since, or a sensitive path (with -X in the plan)
+    let project = ProjectConfigs::for_root(&result.path).lookup(&result.path);
+    if project.excluded {
+        return skip(result, "protected: excluded by its project config".into());
+    }
+    result.project_mark = project.mark;
+    let risk = analyze_scan_result(&result);
+    if risk.is_sensitive {
+        let reason = risk.reason.unwrap_or_else(|| "sensitive path".to_string());
+        return skip(result, format!("protected: {reason}"));
+    }
+    if !is_target(&result, &args.targets) {
+        return skip(
+            result,
+            "not a target: no profile, --targets or project config names it".into(),
+        );
+    }
+
+    let stats = calculate_size(&result.path).await;
+    result.size = Some(stats.size);
+    result.file_count = Some(stats.file_count);
+    result.newest_modified = stats.newest_modified;
+    let tolerance = args.tolerance;
+    if !tolerance.allows(item.size, stats.size) {
+        let reason = format!(
+            "size changed: {} now, {} in the plan (tolerance {tolerance})",
+            ByteSize::b(stats.size),
+            ByteSize::b(item.size)
+        );
+        return skip(result, reason);
+    }
+
+    let (active, idle) = activity(&result, args.active_window(), SystemTime::now());
+    let skip = (active && !args.include_active).then(|| active_reason(idle));
+    Candidate {
+        result,
+        active,
+        skip,
+    }
+}
+
+/// Whether a scan could have found the folder: its name is a profile target or one of
+/// `--targets`, or a project config adds it. Undo folders a killed run left behind
+/// count as the folder they came from, as they do in scans.
+fn is_target(result: &ScanResult, targets: &[String]) -> bool {
+    if matches!(result.project_mark, Some(ProjectMark::Added { .. })) {
+        return true;
+    }
+    let Some(name) = result.path.file_name().map(|name| name.to_string_lossy()) else {
+        return false;
+    };
+    let name = match Leftover::parse(&name) {
+        Some(leftover) if leftover.is_orphaned() => leftover.original,
+        Some(_) => return false,
+        None => &name,
+    };
+    targets.iter().any(|target| target == name) || !profiles_for_target(name).is_empty()
+}
+
+/// The folder as the plan describes it (reported with the planned size until re-measured)
+fn planned(path: PathBuf, item: &PlanItem) -> ScanResult {
+    let mut result = ScanResult::new(path);
+    result.size = Some(item.size);
+    result
+}
+
+const fn skip(result: ScanResult, reason: String) -> Candidate {
+    Candidate {
+        result,
+        active: false,
+        skip: Some(reason),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Args, Command};
+    use clap::Parser;
+    use std::fs;
+
+    /// `apply` flags; fixture folders were just written, so the active guard is off
+    fn args(extra: &[&str]) -> ApplyArgs {
+        let base = ["cache-sweep", "apply", "plan.json", "--active-window", "0"];
+        let Some(Command::Apply(apply)) = Args::parse_from(base.iter().chain(extra)).command else {
+            panic!("expected apply");
+        };
+        apply
+    }
+
+    fn item(path: &Path, size: u64) -> PlanItem {
+        PlanItem {
+            path: JsonPath::new(path),
+            size,
+            identity: DirIdentity::of(&path.symlink_metadata().unwrap()),
+        }
+    }
+
+    fn folder(temp: &Path, name: &str, bytes: usize) -> PathBuf {
+        let dir = temp.join(name).join("node_modules");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("index.js"), vec![0u8; bytes]).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_folder_passes() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = folder(temp.path(), "app", 100);
+
+        let candidate = check(item(&dir, 100), &args(&[])).await;
+
+        assert!(candidate.skip.is_none());
+        assert_eq!(candidate.result.size, Some(100));
+    }
+
+    #[tokio::test]
+    async fn test_missing_folder_is_skipped() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = folder(temp.path(), "app", 100);
+        let planned = item(&dir, 100);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let candidate = check(planned, &args(&[])).await;
+
+        assert_eq!(candidate.skip.as_deref(), Some("missing: no longer exists"));
+    }
+
+    #[tokio::test]
+    async fn test_size_outside_tolerance_is_skipped() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = folder(temp.path(), "app", 500);
+
+        let candidate = check(item(&dir, 100), &args(&[])).await;
+        assert!(candidate
+            .skip
+            .unwrap()
+            .starts_with("size changed: 500 B now"));
+
+        let candidate = check(item(&dir, 100), &args(&["--tolerance", "400B"])).await;
+        assert!(candidate.skip.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_kept_project_is_skipped() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = folder(temp.path(), "app", 100);
+        fs::write(temp.path().join("app").join(".cache-sweep-keep"), "").unwrap();
+
+        let candidate = check(item(&dir, 100), &args(&[])).await;
+
+        assert!(candidate.skip.unwrap().starts_with("protected: Kept by"));
+    }
+
+    #[tokio::test]
+    async fn test_active_folder_is_skipped_unless_included() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = folder(temp.path(), "app", 100);
+        let mut guarded = args(&[]);
+        guarded.active_window = 10;
+
+        let candidate = check(item(&dir, 100), &guarded).await;
+        assert!(candidate
+            .skip
+            .unwrap()
+            .starts_with("active: changed 0 min ago"));
+
+        guarded.include_active = true;
+        let candidate = check(item(&dir, 100), &guarded).await;
+        assert!(candidate.skip.is_none());
+        assert!(candidate.active);
+    }
+
+    #[tokio::test]
+    async fn test_folder_that_is_not_a_target_is_skipped() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().join("app/src");
+        fs::create_dir_all(&dir).unwrap();
+
+        let candidate = check(item(&dir, 0), &args(&[])).await;
+        assert!(candidate.skip.unwrap().starts_with("not a target"));
+
+        let candidate = check(item(&dir, 0), &args(&["-t", "build,src"])).await;
+        assert!(candidate.skip.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_folder_added_by_project_config_is_a_target() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().join("app/fixtures");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            temp.path().join("app/.cache-sweep.toml"),
+            "targets = [\"fixtures\"]\n",
+        )
+        .unwrap();
+
+        let candidate = check(item(&dir, 0), &args(&[])).await;
+
+        assert!(candidate.skip.is_none());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_replaced_or_unidentified_folder_is_skipped() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = folder(temp.path(), "app", 100);
+        let planned = item(&dir, 100);
+        // Keep the old inode alive so the new directory can't reuse its number
+        fs::rename(&dir, temp.path().join("old")).unwrap();
+        fs::create_dir(&dir).unwrap();
+
+        let candidate = check(planned, &args(&[])).await;
+        assert_eq!(
+            candidate.skip.as_deref(),
+            Some("changed: it was replaced since the scan")
+        );
+
+        let mut unidentified = item(&dir, 0);
+        unidentified.identity = None;
+        let candidate = check(unidentified, &args(&[])).await;
+        assert!(candidate.skip.unwrap().starts_with("changed: the plan"));
+    }
+
+    #[test]
+    fn test_load_reads_json_output_and_checks_version() {
+        let temp = tempfile::tempdir().unwrap();
+        let plan = temp.path().join("plan.json");
+        fs::write(
+            &plan,
+            r#"{"version": 2, "results": [{"path": "/a/node_modules", "target": "node_modules",
+                "size": 10, "identity": {"dev": 1, "ino": 2, "uid": 3}}], "meta": {}}"#,
+        )
+        .unwrap();
+        let loaded = load(&plan).unwrap();
+        assert_eq!(loaded.results.len(), 1);
+        assert_eq!(
+            loaded.results[0].identity,
+            Some(DirIdentity {
+                dev: 1,
+                ino: 2,
+                uid: 3
+            })
+        );
+
+        fs::write(&plan, r#"{"version": 1, "results": []}"#).unwrap();
+        assert!(load(&plan).unwrap_err().to_string().contains("version 1"));
+        fs::write(&plan, "not json").unwrap();
+        assert!(load(&plan).is_err());
+    }
+}
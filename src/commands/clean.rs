@@ -0,0 +1,595 @@
+use std::collections::HashSet;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{bail, Result};
+use bytesize::ByteSize;
+use futures::{stream, StreamExt};
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+
+use crate::cli::CleanArgs;
+use crate::delete::{
+    remove_directory, AuditEntry, AuditLog, DeleteResult, DeleteStrategy, RiskLevel,
+    DELETE_CONCURRENCY,
+};
+use crate::output::JsonPath;
+use crate::risk::analyze_scan_result;
+use crate::scanner::{
+    calculate_size, plan_to_free, start_scan, BudgetItem, ScanErrors, ScanResult,
+};
+
+/// Format version of the `clean --json` and `apply --json` reports (the scan's
+/// `--json` has its own, `JSON_VERSION`)
+const REPORT_VERSION: u8 = 1;
+
+/// How many folders are sized at once (`calculate_size` has its own global limit)
+const SIZE_CONCURRENCY: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum Outcome {
+    Deleted,
+    Trashed,
+    /// Dry run: nothing was touched
+    WouldDelete,
+    /// Protected, active, or interrupted; never touched
+    Skipped,
+    /// Some or all of the folder is still there
+    Failed,
+}
+
+impl Outcome {
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Deleted => "deleted",
+            Self::Trashed => "trashed",
+            Self::WouldDelete => "would delete",
+            Self::Skipped => "skipped",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct CleanResult {
+    #[serde(flatten)]
+    path: JsonPath,
+    size: u64,
+    /// Bytes actually released (0 for dry runs, skips and trash moves)
+    freed: u64,
+    outcome: Outcome,
+    /// Why it was skipped, or what went wrong
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CleanReport<'a> {
+    version: u8,
+    results: &'a [CleanResult],
+    meta: CleanMeta,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CleanMeta {
+    dry_run: bool,
+    /// Deleted, trashed, or (in a dry run) would be deleted
+    removed: usize,
+    failed: usize,
+    skipped: usize,
+    freed: u64,
+    run_duration: u64,
+}
+
+/// A scanned folder and, if it must be left alone, why
+pub(super) struct Candidate {
+    pub(super) result: ScanResult,
+    pub(super) active: bool,
+    pub(super) skip: Option<String>,
+}
+
+pub async fn run(args: &CleanArgs, cancel_token: CancellationToken) -> Result<()> {
+    if !args.yes && !args.dry_run {
+        bail!("refusing to delete without --yes (use --dry-run to see what would be deleted)");
+    }
+
+    let start = Instant::now();
+    let mut candidates = scan(args, &cancel_token).await;
+    if let Some(target) = args.free {
+        candidates = budget(candidates, target, SystemTime::now());
+    }
+    let audit = AuditLog::from_env().ok();
+    let strategy = args.strategy.delete_strategy();
+
+    let results: Vec<CleanResult> = stream::iter(candidates)
+        .map(|candidate| {
+            clean_one(
+                candidate,
+                strategy,
+                args.dry_run,
+                audit.as_ref(),
+                &cancel_token,
+            )
+        })
+        .buffered(DELETE_CONCURRENCY)
+        .collect()
+        .await;
+
+    finish(&results, args.dry_run, args.json, strategy, start)
+}
+
+/// Prints the outcomes as a table or JSON; fails if any folder could not be deleted
+pub(super) fn finish(
+    results: &[CleanResult],
+    dry_run: bool,
+    json: bool,
+    strategy: DeleteStrategy,
+    start: Instant,
+) -> Result<()> {
+    let mut meta = summarize(results);
+    meta.dry_run = dry_run;
+    meta.run_duration = u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX);
+
+    if json {
+        let report = CleanReport {
+            version: REPORT_VERSION,
+            results,
+            meta,
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        check_failures(&report.meta, results.len())
+    } else {
+        print_table(results, &meta, strategy);
+        check_failures(&meta, results.len())
+    }
+}
+
+/// Scans like `--json` does, sizes the matches, and decides which to leave alone
+async fn scan(args: &CleanArgs, cancel_token: &CancellationToken) -> Vec<Candidate> {
+    let mut rx = start_scan(
+        args.scan.effective_directory(),
+        args.scan.effective_targets(),
+        args.scan.exclude.clone().unwrap_or_default(),
+        args.scan.follow_links,
+        args.scan.respect_ignore,
+        ScanErrors::default(),
+        cancel_token.clone(),
+    );
+    let pattern = args.pattern.as_deref().map(str::to_lowercase);
+
+    let mut found = Vec::new();
+    while let Some(batch) = rx.recv().await {
+        found.extend(batch.into_iter().filter(|result| {
+            pattern.as_deref().is_none_or(|pattern| {
+                result
+                    .path
+                    .to_string_lossy()
+                    .to_lowercase()
+                    .contains(pattern)
+            })
+        }));
+    }
+
+    let mut candidates: Vec<Candidate> = stream::iter(found)
+        .map(|mut result| async move {
+            let stats = calculate_size(&result.path).await;
+            result.size = Some(stats.size);
+            result.file_count = Some(stats.file_count);
+            result.newest_modified = stats.newest_modified;
+            result
+        })
+        .buffer_unordered(SIZE_CONCURRENCY)
+        .filter_map(|result| {
+            let candidate = classify(result, args, SystemTime::now());
+            async move { candidate }
+        })
+        .collect()
+        .await;
+    candidates.sort_by(|a, b| a.result.path.cmp(&b.result.path));
+    candidates
+}
+
+/// Drops hidden protected folders and those outside the size/age filters or `--filter`;
+/// marks the rest that must not be deleted
+fn classify(mut result: ScanResult, args: &CleanArgs, now: SystemTime) -> Option<Candidate> {
+    let risk = analyze_scan_result(&result);
+    if risk.is_sensitive && !args.scan.show_protected {
+        return None;
+    }
+    result.is_sensitive = risk.is_sensitive;
+    let active_window = args.scan.active_window();
+    let query_ok = args
+        .scan
+        .query
+        .as_ref()
+        .is_none_or(|query| query.matches(&result, now, active_window));
+    if !query_ok || !args.scan.filter().matches(&result, now) {
+        return None;
+    }
+
+    let (active, idle) = activity(&result, active_window, now);
+
+    // Protected folders are listed with -X, but never deleted
+    let skip = if risk.is_sensitive {
+        Some(format!(
+            "protected: {}",
+            risk.reason.unwrap_or_else(|| "sensitive path".to_string())
+        ))
+    } else if active && !args.include_active {
+        Some(active_reason(idle))
+    } else {
+        None
+    };
+    Some(Candidate {
+        result,
+        active,
+        skip,
+    })
+}
+
+/// Whether `result` changed within `window` (a build may be writing to it), and how
+/// long ago it last changed
+pub(super) fn activity(
+    result: &ScanResult,
+    window: Option<Duration>,
+    now: SystemTime,
+) -> (bool, Option<Duration>) {
+    let idle = result
+        .modified
+        .max(result.newest_modified)
+        .map(|time| now.duration_since(time).unwrap_or(Duration::ZERO));
+    let active = window.zip(idle).is_some_and(|(window, idle)| idle < window);
+    (active, idle)
+}
+
+pub(super) fn active_reason(idle: Option<Duration>) -> String {
+    let mins = idle.unwrap_or(Duration::ZERO).as_secs() / 60;
+    format!("active: changed {mins} min ago (use --include-active to delete anyway)")
+}
+
+/// Keeps just the deletable folders `--free` needs (active ones only as a last resort)
+fn budget(candidates: Vec<Candidate>, target: u64, now: SystemTime) -> Vec<Candidate> {
+    let mut eligible: Vec<Candidate> = candidates
+        .into_iter()
+        .filter(|candidate| candidate.skip.is_none())
+        .collect();
+    let items: Vec<BudgetItem> = eligible
+        .iter()
+        .map(|candidate| BudgetItem::for_result(&candidate.result, candidate.active, now))
+        .collect();
+    let plan = plan_to_free(&items, target);
+    if !plan.reached() {
+        eprintln!(
+            "Only {} of {} can be freed; deleting everything eligible",
+            ByteSize::b(plan.total()),
+            ByteSize::b(target)
+        );
+    }
+
+    let picked: HashSet<usize> = plan.indices().collect();
+    let mut index = 0;
+    eligible.retain(|_| {
+        index += 1;
+        picked.contains(&(index - 1))
+    });
+    eligible
+}
+
+pub(super) async fn clean_one(
+    candidate: Candidate,
+    strategy: DeleteStrategy,
+    dry_run: bool,
+    audit: Option<&AuditLog>,
+    cancel_token: &CancellationToken,
+) -> CleanResult {
+    let Candidate {
+        result,
+        active,
+        skip,
+    } = candidate;
+    let size = result.size.unwrap_or(0);
+    let report = |outcome, freed, reason| CleanResult {
+        path: JsonPath::new(&result.path),
+        size,
+        freed,
+        outcome,
+        reason,
+    };
+
+    if let Some(reason) = skip {
+        return report(Outcome::Skipped, 0, Some(reason));
+    }
+    if cancel_token.is_cancelled() {
+        return report(Outcome::Skipped, 0, Some("interrupted".to_string()));
+    }
+    // Dry runs are logged (flagged as such), as in the TUI; skipped folders aren't
+    let log = |deleted: &DeleteResult| {
+        let Some(log) = audit else {
+            return;
+        };
+        let risk = if active {
+            RiskLevel::Active
+        } else {
+            RiskLevel::Safe
+        };
+        let entry = AuditEntry::new(&result.path, size, risk, dry_run, strategy).finish(deleted);
+        if let Err(e) = log.append(&entry) {
+            eprintln!("{}: {e}", log.path().display());
+        }
+    };
+    if dry_run {
+        log(&DeleteResult::ok());
+        return report(Outcome::WouldDelete, 0, None);
+    }
+
+    let deleted = remove_directory(&result.path, result.identity, strategy, false, |_| {}).await;
+    log(&deleted);
+
+    if !deleted.success {
+        report(Outcome::Failed, deleted.size_freed, deleted.error)
+    } else if strategy == DeleteStrategy::Trash {
+        report(Outcome::Trashed, 0, None)
+    } else {
+        report(Outcome::Deleted, deleted.size_freed, None)
+    }
+}
+
+fn summarize(results: &[CleanResult]) -> CleanMeta {
+    let mut meta = CleanMeta::default();
+    for result in results {
+        meta.freed += result.freed;
+        match result.outcome {
+            Outcome::Deleted | Outcome::Trashed | Outcome::WouldDelete => meta.removed += 1,
+            Outcome::Skipped => meta.skipped += 1,
+            Outcome::Failed => meta.failed += 1,
+        }
+    }
+    meta
+}
+
+fn print_table(results: &[CleanResult], meta: &CleanMeta, strategy: DeleteStrategy) {
+    if results.is_empty() {
+        println!("No matching folders found");
+        return;
+    }
+
+    println!("{:<12}  {:>10}  {:>10}  PATH", "OUTCOME", "SIZE", "FREED");
+    for result in results {
+        let freed = if result.freed > 0 {
+            ByteSize::b(result.freed).to_string()
+        } else {
+            "-".to_string()
+        };
+        println!(
+            "{:<12}  {:>10}  {freed:>10}  {}",
+            result.outcome.label(),
+            ByteSize::b(result.size).to_string(),
+            result.path
+        );
+        if let Some(reason) = &result.reason {
+            println!("{:<12}  {:>10}  {:>10}  ({reason})", "", "", "");
+        }
+    }
+
+    let would_free: u64 = results
+        .iter()
+        .filter(|result| result.outcome == Outcome::WouldDelete)
+        .map(|result| result.size)
+        .sum();
+    let summary = if meta.dry_run {
+        format!(
+            "\nWould delete {} folders ({})",
+            meta.removed,
+            ByteSize::b(would_free)
+        )
+    } else if strategy == DeleteStrategy::Trash {
+        format!("\nMoved {} folders to the trash", meta.removed)
+    } else {
+        format!(
+            "\nDeleted {} folders, freed {}",
+            meta.removed,
+            ByteSize::b(meta.freed)
+        )
+    };
+    println!(
+        "{summary}; {} failed, {} skipped",
+        meta.failed, meta.skipped
+    );
+}
+
+fn check_failures(meta: &CleanMeta, total: usize) -> Result<()> {
+    if meta.failed > 0 {
+        bail!("{} of {total} folders could not be deleted", meta.failed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Args, Command};
+    use clap::Parser;
+    use std::path::{Path, PathBuf};
+
+    fn args(extra: &[&str]) -> CleanArgs {
+        let args = Args::parse_from(["cache-sweep", "clean"].iter().chain(extra));
+        let Some(Command::Clean(clean)) = args.command else {
+            panic!("expected clean");
+        };
+        clean
+    }
+
+    fn scanned(path: &str, changed_ago: Duration) -> (ScanResult, SystemTime) {
+        let now = SystemTime::now();
+        let mut result = ScanResult::new(PathBuf::from(path));
+        result.modified = Some(now - changed_ago);
+        result.size = Some(100);
+        (result, now)
+    }
+
+    fn outcome(outcome: Outcome, freed: u64) -> CleanResult {
+        CleanResult {
+            path: JsonPath::new(Path::new("/p/node_modules")),
+            size: 100,
+            freed,
+            outcome,
+            reason: None,
+        }
+    }
+
+    #[test]
+    fn test_idle_folder_is_deletable() {
+        let (result, now) = scanned("/code/app/node_modules", Duration::from_hours(24));
+        let candidate = classify(result, &args(&[]), now).unwrap();
+
+        assert!(candidate.skip.is_none());
+        assert!(!candidate.active);
+    }
+
+    #[test]
+    fn test_active_folder_is_skipped_unless_included() {
+        let (result, now) = scanned("/code/app/node_modules", Duration::from_mins(1));
+        let candidate = classify(result.clone(), &args(&[]), now).unwrap();
+        assert!(candidate.skip.unwrap().starts_with("active:"));
+
+        let candidate = classify(result, &args(&["--include-active"]), now).unwrap();
+        assert!(candidate.skip.is_none());
+        assert!(candidate.active);
+    }
+
+    #[test]
+    fn test_protected_folder_is_hidden_or_skipped() {
+        let (result, now) = scanned("/System/Library/Caches", Duration::from_hours(24));
+        assert!(classify(result.clone(), &args(&[]), now).is_none());
+
+        let candidate = classify(result, &args(&["-X"]), now).unwrap();
+        assert!(candidate.skip.unwrap().starts_with("protected:"));
+    }
+
+    #[test]
+    fn test_size_and_age_filters_drop_folders() {
+        let (result, now) = scanned("/code/app/node_modules", Duration::from_hours(24));
+        assert!(classify(result.clone(), &args(&["--min-size", "1KB"]), now).is_none());
+        assert!(classify(result.clone(), &args(&["--older-than", "7d"]), now).is_none());
+        assert!(classify(
+            result,
+            &args(&["--max-size", "1KB", "--older-than", "12h"]),
+            now
+        )
+        .is_some());
+    }
+
+    #[test]
+    fn test_filter_query_drops_folders() {
+        let (result, now) = scanned("/code/work/node_modules", Duration::from_hours(24));
+        let filter = |query: &str| classify(result.clone(), &args(&["--filter", query]), now);
+        assert!(filter("!path:work/").is_none());
+        assert!(filter("age>7d").is_none());
+        assert!(filter("profile:node age<2d risk:safe").is_some());
+    }
+
+    #[test]
+    fn test_budget_keeps_only_what_free_needs() {
+        let now = SystemTime::now();
+        let candidate = |path: &str, size: u64, idle_days: u64, skip: Option<&str>| {
+            let mut result = ScanResult::new(PathBuf::from(path));
+            result.size = Some(size);
+            result.modified = Some(now - Duration::from_hours(24 * idle_days));
+            Candidate {
+                result,
+                active: false,
+                skip: skip.map(str::to_string),
+            }
+        };
+        let candidates = vec![
+            candidate("/code/a/node_modules", 500, 10, None),
+            candidate("/code/b/node_modules", 500, 300, None),
+            candidate("/code/c/target", 800, 300, None),
+            candidate("/code/d/node_modules", 5000, 900, Some("protected: kept")),
+        ];
+
+        let kept: Vec<_> = budget(candidates, 400, now)
+            .into_iter()
+            .map(|candidate| candidate.result.path)
+            .collect();
+
+        // Stale beats recent, cheap beats expensive, and skipped folders are never picked
+        assert_eq!(kept, vec![PathBuf::from("/code/b/node_modules")]);
+    }
+
+    #[test]
+    fn test_summarize_counts_outcomes() {
+        let results = [
+            outcome(Outcome::Deleted, 100),
+            outcome(Outcome::Trashed, 0),
+            outcome(Outcome::Failed, 40),
+            outcome(Outcome::Skipped, 0),
+        ];
+        let meta = summarize(&results);
+
+        assert_eq!(
+            (meta.removed, meta.failed, meta.skipped, meta.freed),
+            (2, 1, 1, 140)
+        );
+        assert!(check_failures(&meta, results.len()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_clean_one_deletes_and_reports_freed_bytes() {
+        let temp = tempfile::tempdir().unwrap();
+        let target = temp.path().join("node_modules");
+        std::fs::create_dir(&target).unwrap();
+        std::fs::write(target.join("index.js"), "x".repeat(10)).unwrap();
+        let candidate = Candidate {
+            result: ScanResult::new(target.clone()),
+            active: false,
+            skip: None,
+        };
+
+        let result = clean_one(
+            candidate,
+            DeleteStrategy::Delete,
+            false,
+            None,
+            &CancellationToken::new(),
+        )
+        .await;
+
+        assert_eq!(result.outcome, Outcome::Deleted);
+        assert_eq!(result.freed, 10);
+        assert!(!target.exists());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_is_logged_but_skips_are_not() {
+        let temp = tempfile::tempdir().unwrap();
+        let log = AuditLog::new(temp.path().join("audit.jsonl"));
+        let candidate = |name: &str, skip: Option<&str>| Candidate {
+            result: ScanResult::new(temp.path().join(name)),
+            active: false,
+            skip: skip.map(str::to_string),
+        };
+
+        for candidate in [
+            candidate("a/node_modules", None),
+            candidate("b/node_modules", Some("protected: test")),
+        ] {
+            let result = clean_one(
+                candidate,
+                DeleteStrategy::Delete,
+                true,
+                Some(&log),
+                &CancellationToken::new(),
+            )
+            .await;
+            assert_ne!(result.outcome, Outcome::Failed);
+        }
+
+        let entries = log.read().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].dry_run);
+        assert!(entries[0].path.path.ends_with("a/node_modules"));
+    }
+}
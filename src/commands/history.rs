@@ -0,0 +1,245 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use bytesize::ByteSize;
+
+use crate::cli::HistoryArgs;
+use crate::delete::{AuditEntry, AuditLog, AuditOutcome};
+
+pub fn run(args: &HistoryArgs) -> Result<()> {
+    let log = AuditLog::from_env()?;
+    let filter = Filter::new(args)?;
+    let recorded = log.read()?;
+    if recorded.is_empty() {
+        println!("No deletions recorded in {}", log.path().display());
+        return Ok(());
+    }
+    let entries: Vec<AuditEntry> = recorded
+        .into_iter()
+        .filter(|entry| filter.matches(entry))
+        .collect();
+    if entries.is_empty() {
+        println!("No deletions match");
+        return Ok(());
+    }
+
+    if !args.summary {
+        println!("{:<19}  {:<8}  {:>10}  PATH", "WHEN", "OUTCOME", "FREED");
+        for entry in &entries {
+            println!(
+                "{:<19}  {:<8}  {:>10}  {}",
+                when(entry),
+                outcome_label(entry),
+                ByteSize::b(entry.freed).to_string(),
+                entry.path
+            );
+            if let Some(error) = &entry.error {
+                println!("{:<19}  {:<8}  {:>10}  ({error})", "", "", "");
+            }
+            if let Some(archive) = &entry.archive {
+                println!(
+                    "{:<19}  {:<8}  {:>10}  (archived to {})",
+                    "", "", "", archive
+                );
+            }
+        }
+        println!();
+    }
+
+    println!("{:<7}  {:>9}  {:>10}", "MONTH", "DELETIONS", "FREED");
+    for (month, total) in monthly_totals(&entries) {
+        println!(
+            "{month:<7}  {:>9}  {:>10}",
+            total.count,
+            ByteSize::b(total.freed).to_string()
+        );
+    }
+    Ok(())
+}
+
+struct Filter {
+    since: Option<chrono::NaiveDate>,
+    until: Option<chrono::NaiveDate>,
+    path: Option<PathBuf>,
+    target: Option<String>,
+    failed: bool,
+}
+
+impl Filter {
+    fn new(args: &HistoryArgs) -> Result<Self> {
+        Ok(Self {
+            since: args.since,
+            until: args.until,
+            // The log stores absolute paths (compared by their exact bytes, from `pathBytes`)
+            path: args.path.as_deref().map(std::path::absolute).transpose()?,
+            target: args.target.clone(),
+            failed: args.failed,
+        })
+    }
+
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        let day = entry.time().map(|time| time.date_naive());
+        self.since
+            .is_none_or(|since| day.is_some_and(|day| day >= since))
+            && self
+                .until
+                .is_none_or(|until| day.is_some_and(|day| day <= until))
+            && self.path.as_ref().is_none_or(|dir| {
+                entry
+                    .path
+                    .to_path_buf()
+                    .is_ok_and(|path| path.starts_with(dir))
+            })
+            && self.target.as_ref().is_none_or(|t| *t == entry.target)
+            && (!self.failed || entry.outcome == AuditOutcome::Failed)
+    }
+}
+
+fn when(entry: &AuditEntry) -> String {
+    entry.time().map_or_else(
+        || "unknown".to_string(),
+        |time| time.format("%Y-%m-%d %H:%M:%S").to_string(),
+    )
+}
+
+const fn outcome_label(entry: &AuditEntry) -> &'static str {
+    if entry.dry_run {
+        return "dry run";
+    }
+    match entry.outcome {
+        AuditOutcome::Deleted => "deleted",
+        AuditOutcome::Trashed => "trashed",
+        AuditOutcome::Archived => "archived",
+        AuditOutcome::Failed => "failed",
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct MonthTotal {
+    count: usize,
+    freed: u64,
+}
+
+/// Real deletions per month, oldest first (dry runs freed nothing and aren't counted)
+fn monthly_totals(entries: &[AuditEntry]) -> BTreeMap<String, MonthTotal> {
+    let mut months: BTreeMap<String, MonthTotal> = BTreeMap::new();
+    for entry in entries.iter().filter(|entry| !entry.dry_run) {
+        let month = entry.time().map_or_else(
+            || "unknown".to_string(),
+            |time| time.format("%Y-%m").to_string(),
+        );
+        let total = months.entry(month).or_default();
+        total.count += 1;
+        total.freed += entry.freed;
+    }
+    months
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delete::{DeleteStrategy, RiskLevel};
+    use chrono::{Local, NaiveDate, TimeZone};
+    use std::path::Path;
+
+    fn entry(path: &str, day: (i32, u32, u32), freed: u64) -> AuditEntry {
+        let mut entry = AuditEntry::new(
+            Path::new(path),
+            freed,
+            RiskLevel::Safe,
+            false,
+            DeleteStrategy::Delete,
+        );
+        entry.timestamp = Local
+            .with_ymd_and_hms(day.0, day.1, day.2, 12, 0, 0)
+            .unwrap()
+            .timestamp();
+        entry.freed = freed;
+        entry
+    }
+
+    fn filter(args: &HistoryArgs) -> Filter {
+        Filter::new(args).unwrap()
+    }
+
+    #[test]
+    fn test_filter_by_day_range() {
+        let tuesday = entry("/code/a/node_modules", (2026, 10, 13), 1);
+        let args = HistoryArgs {
+            since: NaiveDate::from_ymd_opt(2026, 10, 13),
+            until: NaiveDate::from_ymd_opt(2026, 10, 13),
+            ..HistoryArgs::default()
+        };
+
+        assert!(filter(&args).matches(&tuesday));
+        assert!(!filter(&args).matches(&entry("/code/a/node_modules", (2026, 10, 14), 1)));
+    }
+
+    #[test]
+    fn test_filter_by_path_target_and_outcome() {
+        let mut failed = entry("/code/a/target", (2026, 10, 13), 1);
+        failed.outcome = AuditOutcome::Failed;
+        let ok = entry("/code/b/node_modules", (2026, 10, 13), 1);
+
+        let under_a = HistoryArgs {
+            path: Some(PathBuf::from("/code/a")),
+            ..HistoryArgs::default()
+        };
+        assert!(filter(&under_a).matches(&failed));
+        assert!(!filter(&under_a).matches(&ok));
+
+        let node = HistoryArgs {
+            target: Some("node_modules".to_string()),
+            ..HistoryArgs::default()
+        };
+        assert!(filter(&node).matches(&ok));
+        assert!(!filter(&node).matches(&failed));
+
+        let only_failed = HistoryArgs {
+            failed: true,
+            ..HistoryArgs::default()
+        };
+        assert!(filter(&only_failed).matches(&failed));
+        assert!(!filter(&only_failed).matches(&ok));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_filter_by_path_uses_exact_bytes() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let latin1 = entry("/code/caf\u{e9}/target", (2026, 10, 13), 1);
+        let mut raw = latin1.clone();
+        raw.path =
+            crate::output::JsonPath::new(Path::new(OsStr::from_bytes(b"/code/caf\xe9/target")));
+
+        let args = HistoryArgs {
+            path: Some(PathBuf::from(OsStr::from_bytes(b"/code/caf\xe9"))),
+            ..HistoryArgs::default()
+        };
+        assert!(filter(&args).matches(&raw));
+        assert!(!filter(&args).matches(&latin1));
+    }
+
+    #[test]
+    fn test_monthly_totals_skip_dry_runs() {
+        let mut dry = entry("/c/node_modules", (2026, 10, 2), 500);
+        dry.dry_run = true;
+        let entries = vec![
+            entry("/a/node_modules", (2026, 9, 30), 10),
+            entry("/b/node_modules", (2026, 10, 1), 20),
+            dry,
+            entry("/d/target", (2026, 10, 20), 30),
+        ];
+
+        let totals = monthly_totals(&entries);
+
+        let months: Vec<(&str, usize, u64)> = totals
+            .iter()
+            .map(|(month, total)| (month.as_str(), total.count, total.freed))
+            .collect();
+        assert_eq!(months, vec![("2026-09", 1, 10), ("2026-10", 2, 50)]);
+    }
+}
@@ -0,0 +1,20 @@
+mod apply;
+mod clean;
+mod history;
+mod restore;
+mod trash;
+
+use anyhow::Result;
+use tokio_util::sync::CancellationToken;
+
+use crate::cli::Command;
+
+pub async fn run(command: &Command, cancel_token: CancellationToken) -> Result<()> {
+    match command {
+        Command::Clean(args) => clean::run(args, cancel_token).await,
+        Command::Apply(args) => apply::run(args, cancel_token).await,
+        Command::Trash(action) => trash::run(action).await,
+        Command::History(args) => history::run(args),
+        Command::Restore { items } => restore::run(items),
+    }
+}
@@ -0,0 +1,135 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use bytesize::ByteSize;
+
+use crate::delete::{Archive, ArchivedItem};
+
+pub fn run(requested: &[String]) -> Result<()> {
+    // The index records full archive paths, so the archive dir doesn't matter here
+    let archive = Archive::from_env(None)?;
+    if requested.is_empty() {
+        list(&archive)
+    } else {
+        restore(&archive, requested)
+    }
+}
+
+fn list(archive: &Archive) -> Result<()> {
+    let items = archive.list()?;
+    if items.is_empty() {
+        println!("No archives from cache-sweep");
+        return Ok(());
+    }
+
+    let (mut original, mut compressed) = (0, 0);
+    println!(
+        "{:<19}  {:>10}  {:>10}  PATH",
+        "ARCHIVED", "SIZE", "ARCHIVE"
+    );
+    for item in &items {
+        original += item.size;
+        compressed += item.compressed_size;
+        let archived = item.archived_at().map_or_else(
+            || "unknown".to_string(),
+            |time| time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        );
+        println!(
+            "{archived:<19}  {:>10}  {:>10}  {}",
+            ByteSize::b(item.size).to_string(),
+            ByteSize::b(item.compressed_size).to_string(),
+            item.original_path.display()
+        );
+        println!(
+            "{:<19}  {:>10}  {:>10}  ({})",
+            "",
+            "",
+            "",
+            item.archive.display()
+        );
+    }
+    println!(
+        "\n{} archives, {} of folders stored in {}",
+        items.len(),
+        ByteSize::b(original),
+        ByteSize::b(compressed)
+    );
+    Ok(())
+}
+
+fn restore(archive: &Archive, requested: &[String]) -> Result<()> {
+    let items = archive.list()?;
+    let mut failed = 0;
+
+    for request in requested {
+        let Some(item) = find_item(&items, request) else {
+            eprintln!("{request}: no archive found");
+            failed += 1;
+            continue;
+        };
+        match archive.restore(item) {
+            Ok(()) => println!(
+                "Restored {} from {}",
+                item.original_path.display(),
+                item.archive.display()
+            ),
+            Err(e) => {
+                eprintln!("{}: {e}", item.original_path.display());
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!(
+            "{failed} of {} folders could not be restored",
+            requested.len()
+        );
+    }
+    Ok(())
+}
+
+/// Matches an archive file, then an original path (most recently archived first)
+fn find_item<'a>(items: &'a [ArchivedItem], request: &str) -> Option<&'a ArchivedItem> {
+    let path = std::path::absolute(Path::new(request)).ok()?;
+    items
+        .iter()
+        .find(|item| item.archive == path)
+        .or_else(|| items.iter().rev().find(|item| item.original_path == path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn item(archive: &str, original: &str) -> ArchivedItem {
+        ArchivedItem {
+            archive: PathBuf::from(archive),
+            original_path: PathBuf::from(original),
+            archived_at: 0,
+            entries: 1,
+            size: 100,
+            compressed_size: 30,
+        }
+    }
+
+    #[test]
+    fn test_find_item_by_archive_or_latest_original() {
+        let items = vec![
+            item("/a/app-.venv-1.tar.gz", "/code/app/.venv"),
+            item("/a/app-.venv-1.2.tar.gz", "/code/app/.venv"),
+        ];
+
+        let by_archive = find_item(&items, "/a/app-.venv-1.tar.gz").unwrap();
+        assert_eq!(by_archive.archive, PathBuf::from("/a/app-.venv-1.tar.gz"));
+
+        let by_original = find_item(&items, "/code/app/.venv").unwrap();
+        assert_eq!(
+            by_original.archive,
+            PathBuf::from("/a/app-.venv-1.2.tar.gz")
+        );
+
+        assert!(find_item(&items, "/code/other/.venv").is_none());
+    }
+}
@@ -0,0 +1,150 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use bytesize::ByteSize;
+
+use crate::cli::TrashCommand;
+use crate::delete::{Trash, TrashedItem};
+use crate::scanner::calculate_size;
+
+pub async fn run(command: &TrashCommand) -> Result<()> {
+    let trash = Trash::from_env()?;
+    match command {
+        TrashCommand::List => list(&trash).await,
+        TrashCommand::Restore { items } => restore(&trash, items),
+        TrashCommand::Empty => empty(&trash),
+    }
+}
+
+async fn list(trash: &Trash) -> Result<()> {
+    let items = trash.list()?;
+    if items.is_empty() {
+        println!("Nothing in the trash from cache-sweep");
+        return Ok(());
+    }
+
+    let mut total = 0;
+    println!("{:<19}  {:>10}  PATH", "DELETED", "SIZE");
+    for item in &items {
+        let size = calculate_size(&item.files_path()).await.size;
+        total += size;
+        let deleted = item.deleted_at().map_or_else(
+            || "unknown".to_string(),
+            |time| time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        );
+        println!(
+            "{deleted:<19}  {:>10}  {}",
+            ByteSize::b(size).to_string(),
+            item.original_path.display()
+        );
+        if item.original_path.file_name() != Some(item.name.as_ref()) {
+            println!("{:<19}  {:>10}  (in trash as {})", "", "", item.name);
+        }
+    }
+    println!(
+        "\n{} folders, {} pending (freed when the trash is emptied)",
+        items.len(),
+        ByteSize::b(total)
+    );
+    Ok(())
+}
+
+fn restore(trash: &Trash, requested: &[String]) -> Result<()> {
+    let items = trash.list()?;
+    let mut failed = 0;
+
+    for request in requested {
+        let Some(item) = find_item(&items, request) else {
+            eprintln!("{request}: not in the trash");
+            failed += 1;
+            continue;
+        };
+        match trash.restore(item) {
+            Ok(()) => println!("Restored {}", item.original_path.display()),
+            Err(e) => {
+                eprintln!("{}: {e}", item.original_path.display());
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!(
+            "{failed} of {} folders could not be restored",
+            requested.len()
+        );
+    }
+    Ok(())
+}
+
+/// Matches an original path (most recently trashed first), then a name in the trash
+fn find_item<'a>(items: &'a [TrashedItem], request: &str) -> Option<&'a TrashedItem> {
+    let path = std::path::absolute(Path::new(request)).ok();
+    items
+        .iter()
+        .rev()
+        .find(|item| path.as_ref() == Some(&item.original_path))
+        .or_else(|| items.iter().find(|item| item.name == request))
+}
+
+fn empty(trash: &Trash) -> Result<()> {
+    let items = trash.list()?;
+    let mut freed = 0;
+    let mut failed = 0;
+
+    for item in &items {
+        match trash.purge(item) {
+            Ok(size) => freed += size,
+            Err(e) => {
+                eprintln!("{}: {e}", item.files_path().display());
+                failed += 1;
+            }
+        }
+    }
+
+    println!(
+        "Emptied {} folders, freed {}",
+        items.len() - failed,
+        ByteSize::b(freed)
+    );
+    if failed > 0 {
+        bail!("{failed} folders could not be removed from the trash");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn item(name: &str, original: &str) -> TrashedItem {
+        TrashedItem {
+            trash_dir: PathBuf::from("/home/me/.local/share/Trash"),
+            name: name.to_string(),
+            original_path: PathBuf::from(original),
+            deleted_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_find_item_prefers_latest_original_path() {
+        let items = vec![
+            item("node_modules", "/code/app/node_modules"),
+            item("node_modules.2", "/code/app/node_modules"),
+        ];
+        let found = find_item(&items, "/code/app/node_modules").unwrap();
+        assert_eq!(found.name, "node_modules.2");
+    }
+
+    #[test]
+    fn test_find_item_by_trash_name() {
+        let items = vec![
+            item("target", "/code/a/target"),
+            item("target.2", "/code/b/target"),
+        ];
+        let found = find_item(&items, "target.2").unwrap();
+        assert_eq!(found.original_path, PathBuf::from("/code/b/target"));
+        assert!(find_item(&items, "missing").is_none());
+    }
+}
@@ -0,0 +1,551 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, TimeZone};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+
+use super::engine::{delete_tree, DeleteProgress, DeleteResult, ProgressTracker};
+use super::identity::{verify_identity, DirIdentity};
+use super::trash::{state_dir, xdg_dir};
+use crate::output::nested_json_path;
+
+// Some folders are slow to rebuild (a large `.venv`, Xcode's `DerivedData`) but rarely
+// needed. Archiving writes one to `<archive dir>/<name>.tar.gz`, reads the tarball back
+// to make sure it's complete, and only then deletes the original. Archives are recorded
+// in `$XDG_STATE_HOME/cache-sweep/archives.jsonl` so `cache-sweep restore` can find them.
+
+const INDEX_FILE: &str = "archives.jsonl";
+
+/// Typical gzip ratio for dependency folders (mostly text, some binaries), in percent
+const ESTIMATED_RATIO_PERCENT: u64 = 35;
+
+/// What a folder of `size` bytes is expected to compress to
+pub const fn estimated_compressed_size(size: u64) -> u64 {
+    size / 100 * ESTIMATED_RATIO_PERCENT
+}
+
+/// A folder cache-sweep archived and deleted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedItem {
+    /// The `.tar.gz` file
+    #[serde(with = "nested_json_path")]
+    pub archive: PathBuf,
+    #[serde(with = "nested_json_path")]
+    pub original_path: PathBuf,
+    /// Unix timestamp (seconds)
+    pub archived_at: i64,
+    /// Entries in the tarball (files, directories and symlinks)
+    pub entries: u64,
+    /// Bytes of file content archived
+    pub size: u64,
+    /// Size of the archive on disk
+    pub compressed_size: u64,
+}
+
+impl ArchivedItem {
+    pub fn archived_at(&self) -> Option<DateTime<Local>> {
+        Local.timestamp_opt(self.archived_at, 0).single()
+    }
+
+    /// False once the archive was restored or removed by hand
+    pub fn exists(&self) -> bool {
+        self.archive.is_file()
+    }
+}
+
+/// The archive directory and its index
+#[derive(Debug, Clone)]
+pub struct Archive {
+    dir: PathBuf,
+    index: PathBuf,
+}
+
+impl Archive {
+    pub const fn new(dir: PathBuf, index: PathBuf) -> Self {
+        Self { dir, index }
+    }
+
+    /// Uses `dir` if given, else `$XDG_DATA_HOME/cache-sweep/archives`; the index
+    /// always lives in `$XDG_STATE_HOME/cache-sweep/archives.jsonl`
+    pub fn from_env(dir: Option<&Path>) -> io::Result<Self> {
+        let dir = match dir {
+            Some(dir) => std::path::absolute(dir)?,
+            None => xdg_dir("XDG_DATA_HOME", ".local/share")?.join("cache-sweep/archives"),
+        };
+        Ok(Self::new(dir, state_dir()?.join(INDEX_FILE)))
+    }
+
+    /// Where `path` will be archived: `<parent>-<name>-<hash>.tar.gz`, where the hash
+    /// of the full path keeps same-named projects apart (and stays the same across builds)
+    pub fn archive_path(&self, path: &Path) -> PathBuf {
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        let name_of = |p: Option<&Path>| {
+            p.and_then(Path::file_name)
+                .map_or_else(|| "root".to_string(), |n| n.to_string_lossy().to_string())
+        };
+        self.dir.join(format!(
+            "{}-{}-{:08x}.tar.gz",
+            name_of(path.parent()),
+            name_of(Some(&path)),
+            fnv1a(path.as_os_str().as_encoded_bytes()) & 0xffff_ffff,
+        ))
+    }
+
+    /// Writes `path` into a new tarball and checks it reads back in full. The folder
+    /// itself is left alone; `archive_directory` deletes it afterwards.
+    pub fn archive<F: FnMut(DeleteProgress)>(
+        &self,
+        path: &Path,
+        on_progress: F,
+    ) -> io::Result<ArchivedItem> {
+        let original_path = std::path::absolute(path)?;
+        let name = original_path
+            .file_name()
+            .map(PathBuf::from)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no folder name"))?;
+        fs::create_dir_all(&self.dir)?;
+
+        let archive = unused_path(&self.archive_path(&original_path));
+        let partial = PathBuf::from(format!("{}.partial", archive.display()));
+        let written = write_tarball(&original_path, &name, &partial, on_progress)
+            .and_then(|written| {
+                let read = read_tarball(&partial)?;
+                if read == written {
+                    Ok(written)
+                } else {
+                    Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "archive check failed: wrote {} entries ({} bytes), read back {} ({} bytes)",
+                            written.entries, written.bytes, read.entries, read.bytes
+                        ),
+                    ))
+                }
+            })
+            .and_then(|written| fs::rename(&partial, &archive).map(|()| written));
+        let written = match written {
+            Ok(written) => written,
+            Err(e) => {
+                fs::remove_file(&partial).ok();
+                return Err(e);
+            }
+        };
+
+        let item = ArchivedItem {
+            compressed_size: fs::metadata(&archive)?.len(),
+            archive,
+            original_path,
+            archived_at: Local::now().timestamp(),
+            entries: written.entries,
+            size: written.bytes,
+        };
+        self.append(&item)?;
+        Ok(item)
+    }
+
+    /// Archives that are still on disk (oldest first)
+    pub fn list(&self) -> io::Result<Vec<ArchivedItem>> {
+        Ok(self
+            .read_index()?
+            .into_iter()
+            .filter(ArchivedItem::exists)
+            .collect())
+    }
+
+    /// Unpacks an archive back to its original location, then removes the archive
+    pub fn restore(&self, item: &ArchivedItem) -> io::Result<()> {
+        let original = &item.original_path;
+        if original.symlink_metadata().is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", original.display()),
+            ));
+        }
+        let (Some(parent), Some(name)) = (original.parent(), original.file_name()) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} has no parent folder", original.display()),
+            ));
+        };
+        fs::create_dir_all(parent)?;
+
+        // Unpack next to the original, so nothing shows up at the path until it's complete
+        let staging = parent.join(format!(".{}.cache-sweep-restore", name.to_string_lossy()));
+        // Left behind by a restore that was killed part way
+        remove_stale(&staging)?;
+        fs::create_dir(&staging)?;
+        let unpacked = File::open(&item.archive)
+            .and_then(|file| {
+                tar::Archive::new(GzDecoder::new(BufReader::new(file))).unpack(&staging)
+            })
+            .and_then(|()| fs::rename(staging.join(name), original));
+        if let Err(e) = unpacked {
+            delete_tree(&staging, |_| {});
+            return Err(e);
+        }
+        fs::remove_dir(&staging).ok();
+
+        fs::remove_file(&item.archive)?;
+        self.forget(item)
+    }
+
+    fn read_index(&self) -> io::Result<Vec<ArchivedItem>> {
+        let content = match fs::read_to_string(&self.index) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        // Skip lines we can't parse rather than losing track of the rest
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    fn append(&self, item: &ArchivedItem) -> io::Result<()> {
+        if let Some(parent) = self.index.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut line = serde_json::to_string(item).map_err(io::Error::other)?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.index)?
+            .write_all(line.as_bytes())
+    }
+
+    /// Drops `item` (and entries whose archives are gone) from the index
+    fn forget(&self, item: &ArchivedItem) -> io::Result<()> {
+        let remaining: String = self
+            .read_index()?
+            .into_iter()
+            .filter(|entry| entry != item && entry.exists())
+            .filter_map(|entry| serde_json::to_string(&entry).ok())
+            .map(|line| line + "\n")
+            .collect();
+
+        let tmp = self.index.with_extension("jsonl.tmp");
+        fs::write(&tmp, remaining)?;
+        fs::rename(tmp, &self.index)
+    }
+}
+
+/// 64-bit FNV-1a, fixed so archive names don't change between Rust releases
+/// (unlike `DefaultHasher`)
+const fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+        i += 1;
+    }
+    hash
+}
+
+/// Removes whatever is at `path`; a symlink is removed itself, never followed
+fn remove_stale(path: &Path) -> io::Result<()> {
+    match path.symlink_metadata() {
+        Ok(metadata) if metadata.is_dir() => delete_tree(path, |_| {})
+            .error
+            .map_or(Ok(()), |error| Err(io::Error::other(error))),
+        Ok(_) => fs::remove_file(path),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Archives `path` and deletes it once the archive checks out. The archive is kept
+/// even if the deletion only partly succeeds.
+pub async fn archive_directory<F>(
+    archive: Archive,
+    path: &Path,
+    identity: Option<DirIdentity>,
+    on_progress: F,
+) -> (DeleteResult, Option<ArchivedItem>)
+where
+    F: FnMut(DeleteProgress) + Send + 'static,
+{
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = verify_identity(&path, identity.as_ref()) {
+            return (DeleteResult::from_error(&path, e), None);
+        }
+        match archive.archive(&path, on_progress) {
+            Ok(item) => {
+                let mut result = delete_tree(&path, |_| {});
+                result.error = result
+                    .error
+                    .map(|e| format!("{e} (archive kept at {})", item.archive.display()));
+                (result, Some(item))
+            }
+            Err(e) => (DeleteResult::failed(format!("archive failed: {e}")), None),
+        }
+    })
+    .await
+    .unwrap_or_else(|e| (DeleteResult::failed(e.to_string()), None))
+}
+
+/// What went into (or came out of) a tarball
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Contents {
+    entries: u64,
+    bytes: u64,
+}
+
+/// `path`, or `path` with `.N` before `.tar.gz` if that's taken
+fn unused_path(path: &Path) -> PathBuf {
+    let base = path.to_string_lossy();
+    let stem = base.strip_suffix(".tar.gz").unwrap_or(&base);
+    for n in 1u32.. {
+        let candidate = if n == 1 {
+            path.to_path_buf()
+        } else {
+            PathBuf::from(format!("{stem}.{n}.tar.gz"))
+        };
+        if candidate.symlink_metadata().is_err() {
+            return candidate;
+        }
+    }
+    unreachable!("u32 range exhausted while naming archive")
+}
+
+fn write_tarball<F: FnMut(DeleteProgress)>(
+    root: &Path,
+    name: &Path,
+    dest: &Path,
+    on_progress: F,
+) -> io::Result<Contents> {
+    let file = File::create(dest)?;
+    let mut builder =
+        tar::Builder::new(GzEncoder::new(BufWriter::new(file), Compression::default()));
+    builder.follow_symlinks(false);
+
+    let mut tracker = ProgressTracker::new(on_progress);
+    let mut contents = Contents::default();
+    append_dir(&mut builder, root, name, &mut tracker, &mut contents)?;
+    tracker.flush();
+
+    let file = builder
+        .into_inner()?
+        .finish()?
+        .into_inner()
+        .map_err(io::IntoInnerError::into_error)?;
+    file.sync_all()?;
+    Ok(contents)
+}
+
+/// Adds `dir` as `name` and everything below it (symlinks are stored, never followed)
+fn append_dir<W: Write, F: FnMut(DeleteProgress)>(
+    builder: &mut tar::Builder<W>,
+    dir: &Path,
+    name: &Path,
+    tracker: &mut ProgressTracker<F>,
+    contents: &mut Contents,
+) -> io::Result<()> {
+    builder.append_dir(name, dir)?;
+    contents.entries += 1;
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let entry_name = name.join(entry.file_name());
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            append_dir(builder, &path, &entry_name, tracker, contents)?;
+        } else {
+            builder.append_path_with_name(&path, &entry_name)?;
+            contents.entries += 1;
+            if metadata.is_file() {
+                contents.bytes += metadata.len();
+                tracker.add(metadata.len());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads every entry back, which also checks the gzip CRC at the end of the stream
+fn read_tarball(path: &Path) -> io::Result<Contents> {
+    let mut archive = tar::Archive::new(GzDecoder::new(BufReader::new(File::open(path)?)));
+    let mut contents = Contents::default();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        contents.entries += 1;
+        if entry.header().entry_type().is_file() {
+            contents.bytes += io::copy(&mut entry, &mut io::sink())?;
+        }
+    }
+    io::copy(&mut archive.into_inner(), &mut io::sink())?;
+    Ok(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn setup() -> (tempfile::TempDir, Archive, PathBuf) {
+        let temp = tempdir().unwrap();
+        let archive = Archive::new(
+            temp.path().join("archives"),
+            temp.path().join("state/archives.jsonl"),
+        );
+        let target = temp.path().join("app/.venv");
+        fs::create_dir_all(target.join("lib/site-packages")).unwrap();
+        fs::write(target.join("pyvenv.cfg"), "home = /usr/bin\n").unwrap();
+        fs::write(
+            target.join("lib/site-packages/big.py"),
+            "x = 1\n".repeat(1000),
+        )
+        .unwrap();
+        (temp, archive, target)
+    }
+
+    #[test]
+    fn test_archive_path_is_named_after_parent_and_target() {
+        let (temp, archive, target) = setup();
+        let path = archive.archive_path(&target);
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+
+        assert!(path.starts_with(temp.path().join("archives")));
+        assert!(name.starts_with("app-.venv-"), "{name}");
+        assert!(name.ends_with(".tar.gz"));
+        assert_eq!(path, archive.archive_path(&target));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_archive_path_hash_is_stable() {
+        let archive = Archive::new(PathBuf::from("/archives"), PathBuf::from("/state/index"));
+        assert_eq!(
+            archive.archive_path(Path::new("/code/app/.venv")),
+            Path::new("/archives/app-.venv-05f40cab.tar.gz")
+        );
+    }
+
+    #[test]
+    fn test_archive_writes_verified_tarball_and_keeps_original() {
+        let (_temp, archive, target) = setup();
+
+        let item = archive.archive(&target, |_| {}).unwrap();
+
+        assert!(item.exists());
+        assert!(target.exists());
+        assert_eq!(item.original_path, target);
+        assert_eq!(item.entries, 5); // .venv, lib, site-packages, two files
+        assert_eq!(item.size, 16 + 6000);
+        assert!(item.compressed_size < item.size);
+        assert_eq!(archive.list().unwrap(), vec![item]);
+    }
+
+    #[test]
+    fn test_archive_avoids_overwriting_existing_archive() {
+        let (_temp, archive, target) = setup();
+
+        let first = archive.archive(&target, |_| {}).unwrap();
+        let second = archive.archive(&target, |_| {}).unwrap();
+
+        assert_ne!(first.archive, second.archive);
+        assert!(second.archive.to_string_lossy().ends_with(".2.tar.gz"));
+    }
+
+    #[test]
+    fn test_restore_puts_folder_back_and_removes_archive() {
+        let (_temp, archive, target) = setup();
+        let item = archive.archive(&target, |_| {}).unwrap();
+        fs::remove_dir_all(&target).unwrap();
+
+        archive.restore(&item).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(target.join("pyvenv.cfg")).unwrap(),
+            "home = /usr/bin\n"
+        );
+        assert!(target.join("lib/site-packages/big.py").is_file());
+        assert!(!item.exists());
+        assert!(archive.list().unwrap().is_empty());
+        // No staging folder left next to it
+        assert_eq!(fs::read_dir(target.parent().unwrap()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_restore_clears_stale_staging_folder() {
+        let (_temp, archive, target) = setup();
+        let item = archive.archive(&target, |_| {}).unwrap();
+        fs::remove_dir_all(&target).unwrap();
+        let staging = target.with_file_name("..venv.cache-sweep-restore");
+        fs::create_dir_all(staging.join(".venv/lib")).unwrap();
+        fs::write(staging.join(".venv/lib/half.py"), "x").unwrap();
+
+        archive.restore(&item).unwrap();
+
+        assert!(target.join("lib/site-packages/big.py").is_file());
+        assert!(!target.join("lib/half.py").exists());
+        assert!(!staging.exists());
+    }
+
+    #[test]
+    fn test_restore_refuses_to_overwrite() {
+        let (_temp, archive, target) = setup();
+        let item = archive.archive(&target, |_| {}).unwrap();
+
+        let err = archive.restore(&item).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert!(item.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_are_stored_not_followed() {
+        let (temp, archive, target) = setup();
+        let outside = temp.path().join("outside");
+        fs::create_dir(&outside).unwrap();
+        fs::write(outside.join("secret"), "keep").unwrap();
+        std::os::unix::fs::symlink(&outside, target.join("link")).unwrap();
+
+        let item = archive.archive(&target, |_| {}).unwrap();
+        fs::remove_dir_all(&target).unwrap();
+        archive.restore(&item).unwrap();
+
+        assert!(target.join("link").symlink_metadata().unwrap().is_symlink());
+        assert_eq!(item.size, 16 + 6000);
+    }
+
+    #[test]
+    fn test_corrupt_archive_fails_verification() {
+        let (_temp, archive, target) = setup();
+        let item = archive.archive(&target, |_| {}).unwrap();
+        let mut bytes = fs::read(&item.archive).unwrap();
+        let middle = bytes.len() / 2;
+        bytes[middle] ^= 0xff;
+        fs::write(&item.archive, bytes).unwrap();
+
+        assert!(read_tarball(&item.archive).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_archive_directory_deletes_original() {
+        let (_temp, archive, target) = setup();
+
+        let (result, item) = archive_directory(archive, &target, None, |_| {}).await;
+
+        assert!(result.success);
+        assert!(result.size_freed >= 6000);
+        assert!(!target.exists());
+        assert!(item.unwrap().exists());
+    }
+
+    #[test]
+    fn test_estimate_is_smaller_than_original() {
+        assert_eq!(estimated_compressed_size(1000), 350);
+        assert_eq!(estimated_compressed_size(0), 0);
+    }
+}
@@ -0,0 +1,321 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use super::engine::{DeleteResult, DeleteStrategy};
+use super::trash::state_dir;
+use crate::output::{optional_or_text, JsonPath};
+use crate::profiles::profiles_for_target;
+
+// Every deletion attempt is appended to `$XDG_STATE_HOME/cache-sweep/audit.jsonl`, one
+// JSON object per line, so `cache-sweep history` can answer "what was deleted, and when".
+// Entries are only ever appended; the file is meant to be read by people and scripts.
+
+const AUDIT_FILE: &str = "audit.jsonl";
+
+/// How risky a folder looked when it was deleted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RiskLevel {
+    Safe,
+    /// Recently modified (a build may have been running); the user confirmed anyway
+    Active,
+    Sensitive,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcome {
+    Deleted,
+    Trashed,
+    /// Packed into a tarball, then deleted (`cache-sweep restore` brings it back)
+    Archived,
+    /// Some or all of the folder is still there (`freed` says how much went)
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    /// Unix timestamp (seconds)
+    pub timestamp: i64,
+    /// `path`, plus `pathBytes` for names that aren't valid UTF-8
+    #[serde(flatten)]
+    pub path: JsonPath,
+    /// Size found by the scan
+    pub size: u64,
+    /// Bytes actually released (0 for dry runs and trash moves)
+    pub freed: u64,
+    /// Folder name that matched, e.g. `node_modules`
+    pub target: String,
+    /// Built-in profiles that include `target`
+    pub profiles: Vec<String>,
+    pub risk: RiskLevel,
+    pub dry_run: bool,
+    pub strategy: DeleteStrategy,
+    pub outcome: AuditOutcome,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The tarball, for archived folders
+    #[serde(
+        default,
+        deserialize_with = "optional_or_text",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub archive: Option<JsonPath>,
+}
+
+impl AuditEntry {
+    /// Describes a folder about to be deleted; `finish` fills in what happened. Relative
+    /// paths are made absolute, so `history --path` finds them from any directory.
+    pub fn new(
+        path: &Path,
+        size: u64,
+        risk: RiskLevel,
+        dry_run: bool,
+        strategy: DeleteStrategy,
+    ) -> Self {
+        let target = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Self {
+            timestamp: 0,
+            path: JsonPath::new(&std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())),
+            size,
+            freed: 0,
+            profiles: profiles_for_target(&target)
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            target,
+            risk,
+            dry_run,
+            strategy,
+            outcome: AuditOutcome::Deleted,
+            error: None,
+            archive: None,
+        }
+    }
+
+    /// Stamps the entry with the current time and the result of the deletion
+    pub fn finish(mut self, result: &DeleteResult) -> Self {
+        self.timestamp = Local::now().timestamp();
+        self.freed = result.size_freed;
+        self.error.clone_from(&result.error);
+        self.outcome = if !result.success {
+            AuditOutcome::Failed
+        } else if self.strategy == DeleteStrategy::Trash && !self.dry_run {
+            AuditOutcome::Trashed
+        } else {
+            AuditOutcome::Deleted
+        };
+        self
+    }
+
+    /// Marks a finished entry as archived to `archive`. The tarball still takes up
+    /// `compressed_size`, so only the difference counts as freed.
+    pub fn archived_to(mut self, archive: &Path, compressed_size: u64) -> Self {
+        if self.outcome != AuditOutcome::Failed {
+            self.outcome = AuditOutcome::Archived;
+        }
+        self.freed = self.freed.saturating_sub(compressed_size);
+        self.archive = Some(JsonPath::new(archive));
+        self
+    }
+
+    pub fn time(&self) -> Option<DateTime<Local>> {
+        Local.timestamp_opt(self.timestamp, 0).single()
+    }
+}
+
+/// The append-only log of deletions
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub const fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Uses `$XDG_STATE_HOME/cache-sweep/audit.jsonl`
+    pub fn from_env() -> io::Result<Self> {
+        Ok(Self::new(state_dir()?.join(AUDIT_FILE)))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, entry: &AuditEntry) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut line = serde_json::to_string(entry).map_err(io::Error::other)?;
+        line.push('\n');
+        // One write per entry, so concurrent workers never interleave lines
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())
+    }
+
+    /// All entries, oldest first
+    pub fn read(&self) -> io::Result<Vec<AuditEntry>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        // Skip lines we can't parse (e.g., a write cut short by a crash)
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn removed(size_freed: u64) -> DeleteResult {
+        DeleteResult {
+            success: true,
+            size_freed,
+            error: None,
+            failures: Vec::new(),
+        }
+    }
+
+    fn entry(path: &str) -> AuditEntry {
+        AuditEntry::new(
+            Path::new(path),
+            100,
+            RiskLevel::Safe,
+            false,
+            DeleteStrategy::Delete,
+        )
+    }
+
+    #[test]
+    fn test_new_entry_knows_target_and_profile() {
+        let entry = entry("/home/me/app/node_modules");
+        assert_eq!(entry.target, "node_modules");
+        assert_eq!(entry.profiles, vec!["node"]);
+    }
+
+    #[test]
+    fn test_new_entry_stores_absolute_path() {
+        let entry = entry("./app/node_modules");
+        let path = entry.path.to_path_buf().unwrap();
+        assert!(path.is_absolute());
+        assert!(path.ends_with("app/node_modules"));
+        assert_eq!(entry.target, "node_modules");
+    }
+
+    #[test]
+    fn test_finish_records_outcome() {
+        let deleted = entry("/p/node_modules").finish(&removed(90));
+        assert_eq!(deleted.outcome, AuditOutcome::Deleted);
+        assert_eq!(deleted.freed, 90);
+        assert!(deleted.timestamp > 0);
+
+        let mut trashed = entry("/p/node_modules");
+        trashed.strategy = DeleteStrategy::Trash;
+        assert_eq!(trashed.finish(&removed(0)).outcome, AuditOutcome::Trashed);
+
+        let failed = entry("/p/node_modules").finish(&DeleteResult::failed("busy".to_string()));
+        assert_eq!(failed.outcome, AuditOutcome::Failed);
+        assert_eq!(failed.error.as_deref(), Some("busy"));
+    }
+
+    #[test]
+    fn test_archived_entry_counts_net_savings() {
+        let archived = entry("/p/.venv")
+            .finish(&removed(1000))
+            .archived_to(Path::new("/archives/p-.venv-1.tar.gz"), 300);
+
+        assert_eq!(archived.outcome, AuditOutcome::Archived);
+        assert_eq!(archived.freed, 700);
+        assert_eq!(
+            archived.archive.unwrap().to_path_buf().unwrap(),
+            Path::new("/archives/p-.venv-1.tar.gz")
+        );
+    }
+
+    #[test]
+    fn test_append_and_read_round_trip() {
+        let temp = tempdir().unwrap();
+        let log = AuditLog::new(temp.path().join("state/audit.jsonl"));
+        let first = entry("/a/node_modules").finish(&removed(1));
+        let second = entry("/b/target").finish(&removed(2));
+
+        log.append(&first).unwrap();
+        log.append(&second).unwrap();
+
+        assert_eq!(log.read().unwrap(), vec![first, second]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_paths_round_trip() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp = tempdir().unwrap();
+        let log = AuditLog::new(temp.path().join("audit.jsonl"));
+        let path = Path::new(OsStr::from_bytes(b"/code/\xff/.venv"));
+        let archive = Path::new(OsStr::from_bytes(b"/archives/\xff-.venv-1.tar.gz"));
+        let entry = AuditEntry::new(path, 100, RiskLevel::Safe, false, DeleteStrategy::Delete)
+            .finish(&removed(100))
+            .archived_to(archive, 10);
+
+        log.append(&entry).unwrap();
+
+        let read = log.read().unwrap();
+        assert_eq!(read, vec![entry]);
+        assert_eq!(read[0].path.to_path_buf().unwrap(), path);
+        assert_eq!(
+            read[0].archive.as_ref().unwrap().to_path_buf().unwrap(),
+            archive
+        );
+    }
+
+    #[test]
+    fn test_reads_archive_written_as_plain_string() {
+        let line = r#"{"timestamp":1,"path":"/p/.venv","size":100,"freed":70,"target":".venv","profiles":["python"],"risk":"safe","dryRun":false,"strategy":"delete","outcome":"archived","archive":"/archives/p-.venv-1.tar.gz"}"#;
+
+        let entry: AuditEntry = serde_json::from_str(line).unwrap();
+
+        assert_eq!(
+            entry.archive.unwrap().to_path_buf().unwrap(),
+            Path::new("/archives/p-.venv-1.tar.gz")
+        );
+    }
+
+    #[test]
+    fn test_read_skips_bad_lines_and_missing_file() {
+        let temp = tempdir().unwrap();
+        let log = AuditLog::new(temp.path().join("audit.jsonl"));
+        assert!(log.read().unwrap().is_empty());
+
+        let good = entry("/a/node_modules").finish(&removed(1));
+        log.append(&good).unwrap();
+        fs::OpenOptions::new()
+            .append(true)
+            .open(log.path())
+            .unwrap()
+            .write_all(b"{\"timestamp\":")
+            .unwrap();
+
+        assert_eq!(log.read().unwrap(), vec![good]);
+    }
+}
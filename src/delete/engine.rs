@@ -1,59 +1,363 @@
-use std::path::Path;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+
+use super::identity::{verify_identity, DirIdentity, IdentityChange};
+use super::trash::Trash;
+
+/// How a folder is removed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeleteStrategy {
+    /// Permanently delete (`remove_dir_all`)
+    #[default]
+    Delete,
+    /// Move to the freedesktop.org trash; space is only freed once the trash is emptied
+    Trash,
+}
+
+/// How many folders are deleted (or purged) at once, by the TUI queue and by `clean`
+pub const DELETE_CONCURRENCY: usize = 4;
+
+/// Files and bytes removed so far while deleting a folder
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeleteProgress {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// Progress callbacks fire at most this often (plus once when done)
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
 
 #[derive(Debug, Clone)]
 pub struct DeleteResult {
     pub success: bool,
-    #[allow(dead_code)]
+    /// Bytes actually released: files whose last link was removed (0 for dry runs and trash moves)
     pub size_freed: u64,
     pub error: Option<String>,
+    /// Everything that could not be removed; the rest of the tree is gone
+    pub failures: Vec<DeleteFailure>,
+}
+
+impl DeleteResult {
+    pub const fn ok() -> Self {
+        Self {
+            success: true,
+            size_freed: 0,
+            error: None,
+            failures: Vec::new(),
+        }
+    }
+
+    pub const fn failed(error: String) -> Self {
+        Self {
+            success: false,
+            size_freed: 0,
+            error: Some(error),
+            failures: Vec::new(),
+        }
+    }
+
+    /// Nothing was removed because of `error`
+    pub fn from_error(path: &Path, error: DeleteError) -> Self {
+        Self::from_failures(
+            0,
+            vec![DeleteFailure {
+                path: path.to_path_buf(),
+                error,
+            }],
+        )
+    }
+
+    /// Success only if nothing was left behind; `error` summarizes the failures
+    fn from_failures(size_freed: u64, failures: Vec<DeleteFailure>) -> Self {
+        let error = match failures.as_slice() {
+            [] => None,
+            [only] => Some(only.error.to_string()),
+            [first, ..] => Some(format!(
+                "{} paths could not be removed (first: {})",
+                failures.len(),
+                first.error
+            )),
+        };
+        Self {
+            success: failures.is_empty(),
+            size_freed,
+            error,
+            failures,
+        }
+    }
 }
 
-#[allow(dead_code)]
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
 pub enum DeleteError {
     #[error("Permission denied: {0}")]
     PermissionDenied(String),
     #[error("Path not found: {0}")]
     NotFound(String),
     #[error("IO error: {0}")]
-    Io(#[from] std::io::Error),
+    Io(String),
+    /// The path no longer points at the directory that was scanned
+    #[error("Refusing to delete {0}: {1}")]
+    IdentityChanged(String, IdentityChange),
 }
 
-pub async fn delete_directory(path: &Path, dry_run: bool) -> DeleteResult {
+impl DeleteError {
+    pub fn from_io(path: &Path, err: &io::Error) -> Self {
+        let path = path.display().to_string();
+        match err.kind() {
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied(path),
+            io::ErrorKind::NotFound => Self::NotFound(path),
+            _ => Self::Io(format!("{path}: {err}")),
+        }
+    }
+}
+
+/// A path left behind by a delete, and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeleteFailure {
+    pub path: PathBuf,
+    pub error: DeleteError,
+}
+
+pub async fn delete_directory<F>(path: &Path, dry_run: bool, on_progress: F) -> DeleteResult
+where
+    F: FnMut(DeleteProgress) + Send + 'static,
+{
     let path = path.to_path_buf();
 
     if dry_run {
         // Simulate deletion with a short delay
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        return DeleteResult::ok();
+    }
+
+    tokio::task::spawn_blocking(move || delete_tree(&path, on_progress))
+        .await
+        .unwrap_or_else(|e| DeleteResult::failed(e.to_string()))
+}
+
+/// Deletes `path` on the current thread (use `spawn_blocking` from async code).
+/// Unlike `remove_dir_all`, it fixes permissions on read-only directories we own
+/// and keeps going past individual failures, so as much as possible is removed.
+pub fn delete_tree<F: FnMut(DeleteProgress)>(path: &Path, on_progress: F) -> DeleteResult {
+    delete_tree_until(path, &CancellationToken::new(), on_progress)
+}
+
+/// Like `delete_tree`, but stops between entries once `stop` is cancelled; the result
+/// then fails, and whatever is left stays where it is
+pub(super) fn delete_tree_until<F: FnMut(DeleteProgress)>(
+    path: &Path,
+    stop: &CancellationToken,
+    on_progress: F,
+) -> DeleteResult {
+    let mut walk = TreeRemover {
+        tracker: ProgressTracker::new(on_progress),
+        freed: 0,
+        failures: Vec::new(),
+        stop,
+        stopped: false,
+    };
+    walk.remove_dir(path, false);
+    walk.tracker.flush();
+    if walk.stopped {
         return DeleteResult {
-            success: true,
-            size_freed: 0,
-            error: None,
+            success: false,
+            size_freed: walk.freed,
+            error: Some("stopped before it finished".to_string()),
+            failures: walk.failures,
+        };
+    }
+    DeleteResult::from_failures(walk.freed, walk.failures)
+}
+
+/// Depth-first delete that counts what it removes (symlinks are removed, never followed)
+struct TreeRemover<'a, F> {
+    tracker: ProgressTracker<F>,
+    freed: u64,
+    failures: Vec<DeleteFailure>,
+    stop: &'a CancellationToken,
+    stopped: bool,
+}
+
+impl<F: FnMut(DeleteProgress)> TreeRemover<'_, F> {
+    /// Returns true if `dir` is gone. `nested` is false for the root, whose parent
+    /// is outside the tree and never has its permissions changed.
+    fn remove_dir(&mut self, dir: &Path, nested: bool) -> bool {
+        let entries = match retry_denied(dir, || fs::read_dir(dir)) {
+            Ok(entries) => entries,
+            Err(e) => return self.fail(dir, &e),
         };
+
+        let mut emptied = true;
+        for entry in entries {
+            if self.stop.is_cancelled() {
+                self.stopped = true;
+                return false;
+            }
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    emptied = self.fail(dir, &e);
+                    continue;
+                }
+            };
+            let path = entry.path();
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                emptied &= self.remove_dir(&path, true);
+            } else {
+                let metadata = entry.metadata().ok();
+                let len = metadata.as_ref().map_or(0, fs::Metadata::len);
+                match retry_denied(dir, || fs::remove_file(&path)) {
+                    Ok(()) => {
+                        self.tracker.add(len);
+                        if metadata.as_ref().is_some_and(is_last_link) {
+                            self.freed += len;
+                        }
+                    }
+                    Err(e) => emptied = self.fail(&path, &e),
+                }
+            }
+        }
+        if !emptied {
+            // Already reported what's left inside; the folder itself can't go
+            return false;
+        }
+
+        let removed = match dir.parent() {
+            Some(parent) if nested => retry_denied(parent, || fs::remove_dir(dir)),
+            _ => fs::remove_dir(dir),
+        };
+        removed.map_or_else(|e| self.fail(dir, &e), |()| true)
+    }
+
+    fn fail(&mut self, path: &Path, err: &io::Error) -> bool {
+        self.failures.push(DeleteFailure {
+            path: path.to_path_buf(),
+            error: DeleteError::from_io(path, err),
+        });
+        false
+    }
+}
+
+/// A hard-linked file (pnpm store, cargo's incremental cache) keeps its data
+/// until its last link is gone, so unlinking it frees nothing
+#[cfg(unix)]
+fn is_last_link(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    metadata.nlink() <= 1
+}
+
+#[cfg(not(unix))]
+const fn is_last_link(_metadata: &fs::Metadata) -> bool {
+    true
+}
+
+/// Runs `op`, and if it was refused, unlocks `dir` and tries once more
+fn retry_denied<T>(dir: &Path, mut op: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    match op() {
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied && unlock_dir(dir) => op(),
+        result => result,
+    }
+}
+
+/// Gives the owner read/write/search on `dir` if we own it; true if anything changed
+#[cfg(unix)]
+fn unlock_dir(dir: &Path) -> bool {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let Ok(meta) = dir.symlink_metadata() else {
+        return false;
+    };
+    let mode = meta.permissions().mode();
+    if !meta.is_dir() || meta.uid() != rustix::process::getuid().as_raw() || mode & 0o700 == 0o700 {
+        return false;
+    }
+    fs::set_permissions(dir, fs::Permissions::from_mode(mode | 0o700)).is_ok()
+}
+
+/// Clears the read-only attribute (the only permission Windows has on folders)
+#[cfg(not(unix))]
+fn unlock_dir(dir: &Path) -> bool {
+    let Ok(meta) = dir.symlink_metadata() else {
+        return false;
+    };
+    let mut perms = meta.permissions();
+    if !meta.is_dir() || !perms.readonly() {
+        return false;
+    }
+    #[allow(clippy::permissions_set_readonly_false)] // Owner-only on Windows, which is all we want
+    perms.set_readonly(false);
+    fs::set_permissions(dir, perms).is_ok()
+}
+
+pub(super) struct ProgressTracker<F> {
+    progress: DeleteProgress,
+    on_progress: F,
+    last_report: Instant,
+}
+
+impl<F: FnMut(DeleteProgress)> ProgressTracker<F> {
+    pub(super) fn new(on_progress: F) -> Self {
+        Self {
+            progress: DeleteProgress::default(),
+            on_progress,
+            last_report: Instant::now(),
+        }
+    }
+
+    pub(super) fn add(&mut self, bytes: u64) {
+        self.progress.files += 1;
+        self.progress.bytes += bytes;
+        if self.last_report.elapsed() >= PROGRESS_INTERVAL {
+            self.flush();
+        }
     }
 
+    pub(super) fn flush(&mut self) {
+        (self.on_progress)(self.progress);
+        self.last_report = Instant::now();
+    }
+}
+
+/// Removes `path` using `strategy` (honoring `dry_run` for every strategy), after
+/// checking it's still the directory the scan found (`identity`).
+/// `on_progress` only fires for permanent deletes; a trash move is a single rename.
+pub async fn remove_directory<F>(
+    path: &Path,
+    identity: Option<DirIdentity>,
+    strategy: DeleteStrategy,
+    dry_run: bool,
+    on_progress: F,
+) -> DeleteResult
+where
+    F: FnMut(DeleteProgress) + Send + 'static,
+{
+    if dry_run {
+        return delete_directory(path, dry_run, on_progress).await;
+    }
+
+    let path = path.to_path_buf();
     tokio::task::spawn_blocking(move || {
-        match std::fs::remove_dir_all(&path) {
-            Ok(()) => DeleteResult {
-                success: true,
-                size_freed: 0, // Size should be calculated before deletion
-                error: None,
-            },
-            Err(e) => DeleteResult {
-                success: false,
-                size_freed: 0,
-                error: Some(e.to_string()),
+        if let Err(e) = verify_identity(&path, identity.as_ref()) {
+            return DeleteResult::from_error(&path, e);
+        }
+        match strategy {
+            DeleteStrategy::Delete => delete_tree(&path, on_progress),
+            DeleteStrategy::Trash => match Trash::from_env().and_then(|trash| trash.trash(&path)) {
+                // Nothing is freed until the trash is emptied
+                Ok(_) => DeleteResult::ok(),
+                Err(e) => DeleteResult::failed(format!("move to trash failed: {e}")),
             },
         }
     })
     .await
-    .unwrap_or_else(|e| DeleteResult {
-        success: false,
-        size_freed: 0,
-        error: Some(e.to_string()),
-    })
+    .unwrap_or_else(|e| DeleteResult::failed(e.to_string()))
 }
 
 #[cfg(test)]
@@ -69,7 +373,7 @@ mod tests {
         fs::create_dir(&target).unwrap();
         fs::write(target.join("file.txt"), "content").unwrap();
 
-        let result = delete_directory(&target, true).await;
+        let result = delete_directory(&target, true, |_| {}).await;
 
         assert!(result.success);
         assert!(result.error.is_none());
@@ -83,7 +387,7 @@ mod tests {
         fs::create_dir(&target).unwrap();
         fs::write(target.join("file.txt"), "content").unwrap();
 
-        let result = delete_directory(&target, false).await;
+        let result = delete_directory(&target, false, |_| {}).await;
 
         assert!(result.success);
         assert!(result.error.is_none());
@@ -92,7 +396,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_delete_nonexistent_returns_error() {
-        let result = delete_directory(Path::new("/nonexistent/path/xyz123"), false).await;
+        let result = delete_directory(Path::new("/nonexistent/path/xyz123"), false, |_| {}).await;
 
         assert!(!result.success);
         assert!(result.error.is_some());
@@ -106,9 +410,180 @@ mod tests {
         fs::create_dir_all(&inner).unwrap();
         fs::write(inner.join("file.txt"), "nested").unwrap();
 
-        let result = delete_directory(&target, false).await;
+        let result = delete_directory(&target, false, |_| {}).await;
 
         assert!(result.success);
         assert!(!target.exists());
     }
+
+    #[tokio::test]
+    async fn test_delete_reports_files_and_bytes() {
+        let temp = tempdir().unwrap();
+        let target = temp.path().join("outer");
+        fs::create_dir_all(target.join("inner")).unwrap();
+        fs::write(target.join("a.txt"), "12345").unwrap();
+        fs::write(target.join("inner/b.txt"), "123").unwrap();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let result = delete_directory(&target, false, move |p| {
+            tx.send(p).ok();
+        })
+        .await;
+
+        assert!(result.success);
+        assert_eq!(result.size_freed, 8);
+        let last = rx.try_iter().last().unwrap();
+        assert_eq!(last, DeleteProgress { files: 2, bytes: 8 });
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_hard_links_are_not_counted_as_freed() {
+        let temp = tempdir().unwrap();
+        let store = temp.path().join("store.bin");
+        fs::write(&store, "1234567890").unwrap();
+        let target = temp.path().join("node_modules");
+        fs::create_dir(&target).unwrap();
+        fs::hard_link(&store, target.join("linked.bin")).unwrap();
+        fs::write(target.join("own.bin"), "123").unwrap();
+
+        let result = delete_directory(&target, false, |_| {}).await;
+
+        assert!(result.success);
+        assert_eq!(
+            result.size_freed, 3,
+            "the store still holds the linked data"
+        );
+        assert!(store.exists());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_frees_nothing() {
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join("file.txt"), "content").unwrap();
+
+        let result = delete_directory(temp.path(), true, |_| {}).await;
+
+        assert_eq!(result.size_freed, 0);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_delete_does_not_follow_symlinks() {
+        let temp = tempdir().unwrap();
+        let outside = temp.path().join("outside");
+        fs::create_dir(&outside).unwrap();
+        fs::write(outside.join("keep.txt"), "keep").unwrap();
+
+        let target = temp.path().join("target");
+        fs::create_dir(&target).unwrap();
+        std::os::unix::fs::symlink(&outside, target.join("link")).unwrap();
+
+        let result = delete_directory(&target, false, |_| {}).await;
+
+        assert!(result.success);
+        assert!(!target.exists());
+        assert!(outside.join("keep.txt").exists());
+    }
+
+    #[cfg(unix)]
+    fn set_mode(path: &Path, mode: u32) {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_delete_read_only_tree() {
+        // Go's module cache makes every directory 0555
+        let temp = tempdir().unwrap();
+        let target = temp.path().join("mod");
+        let pkg = target.join("golang.org/x/text@v0.3.0");
+        fs::create_dir_all(&pkg).unwrap();
+        fs::write(pkg.join("doc.go"), "package text").unwrap();
+        for dir in [&pkg, &pkg.parent().unwrap().to_path_buf(), &target] {
+            set_mode(dir, 0o555);
+        }
+
+        let result = delete_directory(&target, false, |_| {}).await;
+
+        assert!(result.success, "{:?}", result.failures);
+        assert!(!target.exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_delete_keeps_going_and_reports_what_is_left() {
+        if rustix::process::geteuid().is_root() {
+            return; // root ignores directory permissions
+        }
+        let temp = tempdir().unwrap();
+        let parent = temp.path().join("project");
+        let target = parent.join("node_modules");
+        fs::create_dir_all(target.join("a")).unwrap();
+        fs::write(target.join("a/index.js"), "x").unwrap();
+        fs::write(target.join("b.js"), "y").unwrap();
+        // The parent is outside the tree, so it must not be unlocked
+        set_mode(&parent, 0o555);
+
+        let result = delete_directory(&target, false, |_| {}).await;
+        set_mode(&parent, 0o755);
+
+        assert!(!result.success);
+        assert_eq!(
+            result.failures,
+            vec![DeleteFailure {
+                path: target.clone(),
+                error: DeleteError::PermissionDenied(target.display().to_string()),
+            }]
+        );
+        assert!(target.exists());
+        assert_eq!(
+            fs::read_dir(&target).unwrap().count(),
+            0,
+            "contents removed"
+        );
+    }
+
+    #[test]
+    fn test_result_summarizes_failures() {
+        let failure = |p: &str| DeleteFailure {
+            path: PathBuf::from(p),
+            error: DeleteError::PermissionDenied(p.to_string()),
+        };
+
+        let one = DeleteResult::from_failures(0, vec![failure("/a")]);
+        assert!(!one.success);
+        assert_eq!(one.error.as_deref(), Some("Permission denied: /a"));
+
+        let many = DeleteResult::from_failures(0, vec![failure("/a"), failure("/b")]);
+        assert_eq!(
+            many.error.as_deref(),
+            Some("2 paths could not be removed (first: Permission denied: /a)")
+        );
+
+        assert!(DeleteResult::from_failures(0, Vec::new()).success);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_remove_directory_refuses_replaced_folder() {
+        let temp = tempdir().unwrap();
+        let target = temp.path().join("node_modules");
+        fs::create_dir(&target).unwrap();
+        let scanned = DirIdentity::of(&target.symlink_metadata().unwrap());
+        fs::rename(&target, temp.path().join("old")).unwrap();
+        fs::create_dir(&target).unwrap();
+        fs::write(target.join("keep.txt"), "new").unwrap();
+
+        let result =
+            remove_directory(&target, scanned, DeleteStrategy::Delete, false, |_| {}).await;
+
+        assert!(!result.success);
+        assert!(matches!(
+            result.failures[0].error,
+            DeleteError::IdentityChanged(_, IdentityChange::Replaced)
+        ));
+        assert!(target.join("keep.txt").exists());
+    }
 }
@@ -0,0 +1,203 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::engine::DeleteError;
+
+// Minutes can pass between scanning a folder and deleting it. In that time the path
+// could be swapped for a symlink, a different directory, or a mount. The scanner records
+// who the folder was, and every deletion checks it's still the same folder first.
+
+/// Which directory a path pointed at when it was scanned (also written to `--json`,
+/// so `apply` can check a saved plan against the disk)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirIdentity {
+    pub dev: u64,
+    pub ino: u64,
+    pub uid: u32,
+}
+
+impl DirIdentity {
+    /// `None` where the platform has no stable file identity (Windows)
+    #[cfg(unix)]
+    #[allow(clippy::unnecessary_wraps)] // Same signature as the Windows version
+    pub fn of(metadata: &fs::Metadata) -> Option<Self> {
+        use std::os::unix::fs::MetadataExt;
+        Some(Self {
+            dev: metadata.dev(),
+            ino: metadata.ino(),
+            uid: metadata.uid(),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub const fn of(_metadata: &fs::Metadata) -> Option<Self> {
+        None
+    }
+}
+
+/// Why a folder is no longer safe to delete
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdentityChange {
+    /// The path is now a symlink (deleting would follow it somewhere else)
+    Symlink,
+    /// The path is the root of a mounted filesystem
+    MountPoint,
+    /// A different directory (or a file) now lives at the path
+    Replaced,
+    /// Same directory, but it changed hands
+    OwnerChanged,
+}
+
+impl fmt::Display for IdentityChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Symlink => "it is now a symlink",
+            Self::MountPoint => "it is a mount point",
+            Self::Replaced => "it was replaced since the scan",
+            Self::OwnerChanged => "its owner changed since the scan",
+        })
+    }
+}
+
+/// Checks that `path` is still the directory the scan found (`expected`), and that
+/// it's neither a symlink nor a mount point. Without `expected` only the latter is checked.
+pub fn verify_identity(path: &Path, expected: Option<&DirIdentity>) -> Result<(), DeleteError> {
+    let refuse = |change| DeleteError::IdentityChanged(path.display().to_string(), change);
+    let metadata = path
+        .symlink_metadata()
+        .map_err(|e| DeleteError::from_io(path, &e))?;
+
+    if metadata.file_type().is_symlink() {
+        return Err(refuse(IdentityChange::Symlink));
+    }
+    if !metadata.is_dir() {
+        return Err(refuse(IdentityChange::Replaced));
+    }
+    if is_mount_point(path, &metadata).map_err(|e| DeleteError::from_io(path, &e))? {
+        return Err(refuse(IdentityChange::MountPoint));
+    }
+
+    let (Some(expected), Some(current)) = (expected, DirIdentity::of(&metadata)) else {
+        return Ok(());
+    };
+    if (current.dev, current.ino) != (expected.dev, expected.ino) {
+        return Err(refuse(IdentityChange::Replaced));
+    }
+    if current.uid != expected.uid {
+        return Err(refuse(IdentityChange::OwnerChanged));
+    }
+    Ok(())
+}
+
+/// A directory on a different device than its parent is the root of a mount
+#[cfg(unix)]
+fn is_mount_point(path: &Path, metadata: &fs::Metadata) -> io::Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    let Some(parent) = std::path::absolute(path)?.parent().map(Path::to_path_buf) else {
+        return Ok(true); // The filesystem root
+    };
+    Ok(parent.metadata()?.dev() != metadata.dev())
+}
+
+#[cfg(not(unix))]
+#[allow(clippy::unnecessary_wraps)] // Same signature as the Unix version
+fn is_mount_point(_path: &Path, _metadata: &fs::Metadata) -> io::Result<bool> {
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn identity(path: &Path) -> Option<DirIdentity> {
+        DirIdentity::of(&path.symlink_metadata().unwrap())
+    }
+
+    #[test]
+    fn test_same_directory_passes() {
+        let temp = tempdir().unwrap();
+        let target = temp.path().join("node_modules");
+        fs::create_dir(&target).unwrap();
+        let scanned = identity(&target);
+
+        assert_eq!(verify_identity(&target, scanned.as_ref()), Ok(()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_replaced_directory_is_refused() {
+        let temp = tempdir().unwrap();
+        let target = temp.path().join("node_modules");
+        fs::create_dir(&target).unwrap();
+        let scanned = identity(&target);
+        // Hold on to the old inode so the new directory can't reuse its number
+        let old = temp.path().join("old");
+        fs::rename(&target, &old).unwrap();
+        fs::create_dir(&target).unwrap();
+
+        assert_eq!(
+            verify_identity(&target, scanned.as_ref()),
+            Err(DeleteError::IdentityChanged(
+                target.display().to_string(),
+                IdentityChange::Replaced
+            ))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_is_refused() {
+        let temp = tempdir().unwrap();
+        let elsewhere = temp.path().join("elsewhere");
+        fs::create_dir(&elsewhere).unwrap();
+        let target = temp.path().join("node_modules");
+        std::os::unix::fs::symlink(&elsewhere, &target).unwrap();
+
+        let err = verify_identity(&target, identity(&elsewhere).as_ref()).unwrap_err();
+
+        assert!(matches!(
+            err,
+            DeleteError::IdentityChanged(_, IdentityChange::Symlink)
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_owner_change_is_refused() {
+        let temp = tempdir().unwrap();
+        let target = temp.path().join("node_modules");
+        fs::create_dir(&target).unwrap();
+        let mut scanned = identity(&target).unwrap();
+        scanned.uid = scanned.uid.wrapping_add(1);
+
+        let err = verify_identity(&target, Some(&scanned)).unwrap_err();
+
+        assert!(matches!(
+            err,
+            DeleteError::IdentityChanged(_, IdentityChange::OwnerChanged)
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_filesystem_root_is_a_mount_point() {
+        let err = verify_identity(Path::new("/"), None).unwrap_err();
+        assert!(matches!(
+            err,
+            DeleteError::IdentityChanged(_, IdentityChange::MountPoint)
+        ));
+    }
+
+    #[test]
+    fn test_missing_path_is_not_found() {
+        let temp = tempdir().unwrap();
+        let err = verify_identity(&temp.path().join("gone"), None).unwrap_err();
+        assert!(matches!(err, DeleteError::NotFound(_)));
+    }
+}
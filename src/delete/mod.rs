@@ -1,3 +1,18 @@
+mod archive;
+mod audit;
 mod engine;
+mod identity;
+mod quarantine;
+mod trash;
 
-pub use engine::delete_directory;
+pub use archive::{archive_directory, estimated_compressed_size, Archive, ArchivedItem};
+pub use audit::{AuditEntry, AuditLog, AuditOutcome, RiskLevel};
+pub use engine::{
+    remove_directory, DeleteError, DeleteFailure, DeleteProgress, DeleteResult, DeleteStrategy,
+    DELETE_CONCURRENCY,
+};
+pub use identity::{verify_identity, DirIdentity};
+pub use quarantine::{
+    purge_directory, quarantine_directory, Leftover, Quarantined, QUARANTINE_PREFIX,
+};
+pub use trash::{Trash, TrashedItem};
@@ -0,0 +1,278 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tokio_util::sync::CancellationToken;
+
+use super::engine::{delete_tree_until, DeleteError, DeleteProgress, DeleteResult};
+use super::identity::{verify_identity, DirIdentity};
+
+/// Prefix of folders waiting to be purged, left next to where they used to be
+pub const QUARANTINE_PREFIX: &str = ".cache-sweep-deleted.";
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A folder renamed aside instead of deleted, so the deletion can still be undone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quarantined {
+    pub original: PathBuf,
+    pub quarantined: PathBuf,
+}
+
+impl Quarantined {
+    /// Moves the folder back (fails if something new was created in its place)
+    pub fn restore(&self) -> io::Result<()> {
+        if self.original.symlink_metadata().is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", self.original.display()),
+            ));
+        }
+        fs::rename(&self.quarantined, &self.original)
+    }
+}
+
+/// A `.cache-sweep-deleted.<pid>.<id>.<name>` folder found on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Leftover<'a> {
+    /// The run that quarantined it
+    pub pid: u32,
+    /// The folder's name before it was quarantined (the innermost one, if it was nested again)
+    pub original: &'a str,
+}
+
+impl<'a> Leftover<'a> {
+    pub fn parse(name: &'a str) -> Option<Self> {
+        let rest = name.strip_prefix(QUARANTINE_PREFIX)?;
+        let (pid, rest) = rest.split_once('.')?;
+        let (id, original) = rest.split_once('.')?;
+        id.parse::<u64>().ok()?;
+        Some(Self {
+            pid: pid.parse().ok()?,
+            original: Self::parse(original).map_or(original, |inner| inner.original),
+        })
+    }
+
+    /// Whether the run that quarantined it is gone (killed before purging), so nothing
+    /// will purge or restore it
+    pub fn is_orphaned(&self) -> bool {
+        self.pid != std::process::id() && !process_alive(self.pid)
+    }
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    let Some(pid) = i32::try_from(pid)
+        .ok()
+        .and_then(rustix::process::Pid::from_raw)
+    else {
+        return false;
+    };
+    // EPERM still means the process exists
+    rustix::process::test_kill_process(pid) != Err(rustix::io::Errno::SRCH)
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    // No cheap check here, so never touch another run's folders
+    true
+}
+
+/// Renames `path` to a hidden sibling. Staying in the same directory keeps the
+/// rename atomic and on the same filesystem, so it's instant even for huge folders.
+pub fn quarantine(path: &Path) -> io::Result<Quarantined> {
+    let metadata = path.symlink_metadata()?;
+    if !metadata.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "not a directory",
+        ));
+    }
+
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no folder name"))?;
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let mut hidden = format!("{QUARANTINE_PREFIX}{}.{id}.", std::process::id());
+    hidden.push_str(&name.to_string_lossy());
+    let quarantined = path.with_file_name(hidden);
+
+    if quarantined.symlink_metadata().is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", quarantined.display()),
+        ));
+    }
+    fs::rename(path, &quarantined)?;
+
+    Ok(Quarantined {
+        original: path.to_path_buf(),
+        quarantined,
+    })
+}
+
+/// Quarantines `path` once it's confirmed to be the directory the scan found
+pub async fn quarantine_directory(
+    path: &Path,
+    identity: Option<DirIdentity>,
+) -> Result<Quarantined, DeleteError> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        verify_identity(&path, identity.as_ref())?;
+        quarantine(&path).map_err(|e| DeleteError::from_io(&path, &e))
+    })
+    .await
+    .map_err(|e| DeleteError::Io(e.to_string()))?
+}
+
+/// Deletes a quarantined folder for good. Once `stop` is cancelled it gives up; what's
+/// left keeps its quarantine name, so a later scan offers it once this process is gone.
+pub async fn purge_directory<F>(
+    quarantined: &Path,
+    stop: CancellationToken,
+    on_progress: F,
+) -> DeleteResult
+where
+    F: FnMut(DeleteProgress) + Send + 'static,
+{
+    let path = quarantined.to_path_buf();
+    tokio::task::spawn_blocking(move || delete_tree_until(&path, &stop, on_progress))
+        .await
+        .unwrap_or_else(|e| DeleteResult::failed(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn make_target(root: &Path) -> PathBuf {
+        let target = root.join("node_modules");
+        fs::create_dir_all(target.join("pkg")).unwrap();
+        fs::write(target.join("pkg/index.js"), "x").unwrap();
+        target
+    }
+
+    #[test]
+    fn test_quarantine_renames_to_hidden_sibling() {
+        let temp = tempdir().unwrap();
+        let target = make_target(temp.path());
+
+        let item = quarantine(&target).unwrap();
+
+        assert!(!target.exists());
+        assert_eq!(item.quarantined.parent(), Some(temp.path()));
+        let name = item.quarantined.file_name().unwrap().to_string_lossy();
+        assert!(name.starts_with(QUARANTINE_PREFIX));
+        assert!(name.ends_with(".node_modules"));
+        assert!(item.quarantined.join("pkg/index.js").exists());
+    }
+
+    #[test]
+    fn test_restore_moves_folder_back() {
+        let temp = tempdir().unwrap();
+        let target = make_target(temp.path());
+        let item = quarantine(&target).unwrap();
+
+        item.restore().unwrap();
+
+        assert!(target.join("pkg/index.js").exists());
+        assert!(!item.quarantined.exists());
+    }
+
+    #[test]
+    fn test_restore_refuses_to_overwrite() {
+        let temp = tempdir().unwrap();
+        let target = make_target(temp.path());
+        let item = quarantine(&target).unwrap();
+        fs::create_dir(&target).unwrap(); // e.g., npm install ran again
+
+        assert_eq!(
+            item.restore().unwrap_err().kind(),
+            io::ErrorKind::AlreadyExists
+        );
+        assert!(item.quarantined.exists());
+    }
+
+    #[tokio::test]
+    async fn test_purge_deletes_quarantined_folder() {
+        let temp = tempdir().unwrap();
+        let target = make_target(temp.path());
+        let item = quarantine(&target).unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        let result = purge_directory(
+            &item.quarantined,
+            CancellationToken::new(),
+            move |progress| {
+                tx.send(progress).ok();
+            },
+        )
+        .await;
+
+        assert!(result.success);
+        assert!(rx.try_recv().is_ok(), "progress is reported");
+        assert!(!item.quarantined.exists());
+        assert!(!target.exists());
+    }
+
+    #[tokio::test]
+    async fn test_stopped_purge_leaves_folder_quarantined() {
+        let temp = tempdir().unwrap();
+        let item = quarantine(&make_target(temp.path())).unwrap();
+        let stop = CancellationToken::new();
+        stop.cancel();
+
+        let result = purge_directory(&item.quarantined, stop, |_| {}).await;
+
+        assert!(!result.success);
+        assert!(item.quarantined.join("pkg/index.js").exists());
+    }
+
+    #[test]
+    fn test_leftover_parse() {
+        let leftover = Leftover::parse(".cache-sweep-deleted.42.7.node_modules").unwrap();
+        assert_eq!(leftover.pid, 42);
+        assert_eq!(leftover.original, "node_modules");
+
+        // A leftover deleted (and quarantined) again by a later run
+        let nested =
+            Leftover::parse(".cache-sweep-deleted.50.1..cache-sweep-deleted.42.7.node_modules")
+                .unwrap();
+        assert_eq!((nested.pid, nested.original), (50, "node_modules"));
+
+        assert_eq!(Leftover::parse("node_modules"), None);
+        assert_eq!(
+            Leftover::parse(".cache-sweep-deleted.x.7.node_modules"),
+            None
+        );
+        assert_eq!(
+            Leftover::parse(".cache-sweep-deleted.42.node_modules"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_leftover_orphaned_only_when_its_run_is_gone() {
+        let temp = tempdir().unwrap();
+        let ours = quarantine(&make_target(temp.path())).unwrap();
+        let name = ours.quarantined.file_name().unwrap().to_string_lossy();
+        assert!(!Leftover::parse(&name).unwrap().is_orphaned());
+
+        #[cfg(unix)]
+        {
+            let mut child = std::process::Command::new("true").spawn().unwrap();
+            let dead = child.id();
+            child.wait().unwrap();
+            let name = format!("{QUARANTINE_PREFIX}{dead}.1.node_modules");
+            assert!(Leftover::parse(&name).unwrap().is_orphaned());
+        }
+    }
+
+    #[test]
+    fn test_quarantine_missing_path_fails() {
+        let temp = tempdir().unwrap();
+        assert!(quarantine(&temp.path().join("missing")).is_err());
+    }
+}
@@ -0,0 +1,551 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use super::engine::delete_tree;
+use crate::output::nested_json_path;
+
+// Implements the freedesktop.org Trash spec (https://specifications.freedesktop.org/trash-spec/latest/).
+// Items go to the home trash ($XDG_DATA_HOME/Trash) when they live on the same device,
+// otherwise to the mount's $topdir/.Trash/$uid or $topdir/.Trash-$uid.
+// Everything cache-sweep trashes is also recorded in an index under $XDG_STATE_HOME/cache-sweep/,
+// so `trash list/restore/empty` never touch files trashed by other programs.
+
+const INDEX_FILE: &str = "trash.jsonl";
+
+/// A folder cache-sweep moved to a trash directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashedItem {
+    /// Trash directory root (the one holding `files/` and `info/`)
+    pub trash_dir: PathBuf,
+    /// Name under `files/` (may carry a `.N` suffix to avoid collisions)
+    pub name: String,
+    #[serde(with = "nested_json_path")]
+    pub original_path: PathBuf,
+    /// Unix timestamp (seconds)
+    pub deleted_at: i64,
+}
+
+impl TrashedItem {
+    pub fn files_path(&self) -> PathBuf {
+        self.trash_dir.join("files").join(&self.name)
+    }
+
+    pub fn info_path(&self) -> PathBuf {
+        self.trash_dir
+            .join("info")
+            .join(format!("{}.trashinfo", self.name))
+    }
+
+    pub fn deleted_at(&self) -> Option<DateTime<Local>> {
+        Local.timestamp_opt(self.deleted_at, 0).single()
+    }
+
+    /// False once the item was restored or emptied by another tool
+    pub fn exists(&self) -> bool {
+        self.files_path().symlink_metadata().is_ok()
+    }
+}
+
+/// The trash directories and index used for one user
+#[derive(Debug, Clone)]
+pub struct Trash {
+    home_trash: PathBuf,
+    index: PathBuf,
+}
+
+impl Trash {
+    pub const fn new(home_trash: PathBuf, index: PathBuf) -> Self {
+        Self { home_trash, index }
+    }
+
+    /// Uses `$XDG_DATA_HOME/Trash` and `$XDG_STATE_HOME/cache-sweep/trash.jsonl`
+    pub fn from_env() -> io::Result<Self> {
+        let data_home = xdg_dir("XDG_DATA_HOME", ".local/share")?;
+        let state_dir = state_dir()?;
+        Ok(Self::new(
+            data_home.join("Trash"),
+            state_dir.join(INDEX_FILE),
+        ))
+    }
+
+    /// Moves `path` into the trash that belongs to its mount and records it
+    pub fn trash(&self, path: &Path) -> io::Result<TrashedItem> {
+        let original_path = std::path::absolute(path)?;
+        let metadata = original_path.symlink_metadata()?;
+        if !metadata.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "not a directory",
+            ));
+        }
+
+        let trash_dir = self.trash_dir_for(&original_path, &metadata)?;
+        let (name, info_path) = create_info_file(&trash_dir, &original_path)?;
+
+        let item = TrashedItem {
+            trash_dir,
+            name,
+            original_path,
+            deleted_at: Local::now().timestamp(),
+        };
+        if let Err(e) = fs::rename(&item.original_path, item.files_path()) {
+            fs::remove_file(&info_path).ok();
+            return Err(e);
+        }
+
+        // Without an index entry `trash restore` can't find it, so put it back
+        if let Err(e) = self.append(&item) {
+            if fs::rename(item.files_path(), &item.original_path).is_ok() {
+                fs::remove_file(&info_path).ok();
+            }
+            return Err(e);
+        }
+        Ok(item)
+    }
+
+    /// Items cache-sweep trashed that are still in the trash (oldest first)
+    pub fn list(&self) -> io::Result<Vec<TrashedItem>> {
+        Ok(self
+            .read_index()?
+            .into_iter()
+            .filter(TrashedItem::exists)
+            .collect())
+    }
+
+    /// Moves an item back to its original location
+    pub fn restore(&self, item: &TrashedItem) -> io::Result<()> {
+        if item.original_path.symlink_metadata().is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", item.original_path.display()),
+            ));
+        }
+        if let Some(parent) = item.original_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(item.files_path(), &item.original_path)?;
+        fs::remove_file(item.info_path()).ok();
+        self.forget(item)
+    }
+
+    /// Permanently deletes an item from the trash; returns the bytes freed
+    pub fn purge(&self, item: &TrashedItem) -> io::Result<u64> {
+        let files = item.files_path();
+        let freed = if files.symlink_metadata().is_ok() {
+            let result = delete_tree(&files, |_| {});
+            if let Some(error) = result.error {
+                // Keep it listed so a later `empty` can retry what's left
+                return Err(io::Error::other(error));
+            }
+            result.size_freed
+        } else {
+            0
+        };
+        fs::remove_file(item.info_path()).ok();
+        self.forget(item)?;
+        Ok(freed)
+    }
+
+    fn read_index(&self) -> io::Result<Vec<TrashedItem>> {
+        let content = match fs::read_to_string(&self.index) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        // Skip lines we can't parse rather than losing track of the rest
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    fn append(&self, item: &TrashedItem) -> io::Result<()> {
+        if let Some(parent) = self.index.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut line = serde_json::to_string(item).map_err(io::Error::other)?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.index)?
+            .write_all(line.as_bytes())
+    }
+
+    /// Drops `item` (and entries whose files are gone) from the index
+    fn forget(&self, item: &TrashedItem) -> io::Result<()> {
+        let remaining: String = self
+            .read_index()?
+            .into_iter()
+            .filter(|entry| entry != item && entry.exists())
+            .filter_map(|entry| serde_json::to_string(&entry).ok())
+            .map(|line| line + "\n")
+            .collect();
+
+        let tmp = self.index.with_extension("jsonl.tmp");
+        fs::write(&tmp, remaining)?;
+        fs::rename(tmp, &self.index)
+    }
+
+    /// Home trash for the home device, otherwise a trash at the top of the path's mount
+    #[cfg(unix)]
+    fn trash_dir_for(&self, path: &Path, metadata: &fs::Metadata) -> io::Result<PathBuf> {
+        use std::os::unix::fs::MetadataExt;
+
+        let home_dev = self
+            .home_trash
+            .ancestors()
+            .find_map(|dir| dir.metadata().ok())
+            .map(|m| m.dev());
+        if home_dev == Some(metadata.dev()) {
+            return ensure_trash_dir(&self.home_trash);
+        }
+
+        let uid = rustix::process::getuid().as_raw();
+        let topdir = mount_top(path, metadata.dev());
+        topdir_trash(&topdir, uid)
+    }
+
+    #[cfg(not(unix))]
+    fn trash_dir_for(&self, _path: &Path, _metadata: &fs::Metadata) -> io::Result<PathBuf> {
+        ensure_trash_dir(&self.home_trash)
+    }
+}
+
+/// `$topdir/.Trash/$uid` if the admin set up a shared `.Trash`, else `$topdir/.Trash-$uid`
+#[cfg(unix)]
+fn topdir_trash(topdir: &Path, uid: u32) -> io::Result<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    const STICKY_BIT: u32 = 0o1000;
+
+    let shared = topdir.join(".Trash");
+    // The spec requires a real directory with the sticky bit; otherwise ignore it
+    let shared_ok = shared.symlink_metadata().is_ok_and(|m| {
+        m.is_dir() && !m.file_type().is_symlink() && m.permissions().mode() & STICKY_BIT != 0
+    });
+    if shared_ok {
+        if let Ok(dir) = ensure_trash_dir(&shared.join(uid.to_string())) {
+            return Ok(dir);
+        }
+    }
+
+    let own = topdir.join(format!(".Trash-{uid}"));
+    if own.symlink_metadata().is_err() {
+        fs::create_dir(&own)?;
+        fs::set_permissions(&own, fs::Permissions::from_mode(0o700))?;
+    }
+    ensure_trash_dir(&own)
+}
+
+/// Highest ancestor of `path` on the same device
+#[cfg(unix)]
+fn mount_top(path: &Path, dev: u64) -> PathBuf {
+    use std::os::unix::fs::MetadataExt;
+
+    path.ancestors()
+        .skip(1)
+        .take_while(|dir| dir.metadata().is_ok_and(|m| m.dev() == dev))
+        .last()
+        .unwrap_or(path)
+        .to_path_buf()
+}
+
+fn ensure_trash_dir(dir: &Path) -> io::Result<PathBuf> {
+    if dir
+        .symlink_metadata()
+        .is_ok_and(|m| m.file_type().is_symlink())
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is a symlink", dir.display()),
+        ));
+    }
+    fs::create_dir_all(dir.join("files"))?;
+    fs::create_dir_all(dir.join("info"))?;
+    Ok(dir.to_path_buf())
+}
+
+/// Claims a unique name by creating its `.trashinfo` first, as the spec requires
+fn create_info_file(trash_dir: &Path, original_path: &Path) -> io::Result<(String, PathBuf)> {
+    let base = original_path.file_name().map_or_else(
+        || "unnamed".to_string(),
+        |n| n.to_string_lossy().to_string(),
+    );
+    let contents = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(original_path),
+        Local::now().format("%Y-%m-%dT%H:%M:%S"),
+    );
+
+    for n in 1u32.. {
+        let name = if n == 1 {
+            base.clone()
+        } else {
+            format!("{base}.{n}")
+        };
+        if trash_dir
+            .join("files")
+            .join(&name)
+            .symlink_metadata()
+            .is_ok()
+        {
+            continue;
+        }
+        let info_path = trash_dir.join("info").join(format!("{name}.trashinfo"));
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+        {
+            Ok(mut file) => {
+                file.write_all(contents.as_bytes())?;
+                return Ok((name, info_path));
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!("u32 range exhausted while naming trash entry")
+}
+
+/// URL-escapes a path for the `Path=` key (RFC 2396, keeping `/`)
+fn encode_path(path: &Path) -> String {
+    use std::fmt::Write as _;
+
+    let bytes = path.as_os_str().as_encoded_bytes();
+    let mut encoded = String::with_capacity(bytes.len());
+    for &b in bytes {
+        if b.is_ascii_alphanumeric() || b"/-_.!~*'()".contains(&b) {
+            encoded.push(char::from(b));
+        } else {
+            // Writing to a String can't fail
+            let _ = write!(encoded, "%{b:02X}");
+        }
+    }
+    encoded
+}
+
+/// `$XDG_STATE_HOME/cache-sweep` (default `~/.local/state/cache-sweep`)
+pub fn state_dir() -> io::Result<PathBuf> {
+    Ok(xdg_dir("XDG_STATE_HOME", ".local/state")?.join("cache-sweep"))
+}
+
+/// An XDG base directory; relative values are invalid per the spec and ignored
+pub(super) fn xdg_dir(var: &str, default: &str) -> io::Result<PathBuf> {
+    if let Some(dir) = std::env::var_os(var).map(PathBuf::from) {
+        if dir.is_absolute() {
+            return Ok(dir);
+        }
+    }
+    dirs::home_dir()
+        .map(|home| home.join(default))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home directory"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn test_trash(root: &Path) -> Trash {
+        Trash::new(
+            root.join("data/Trash"),
+            root.join("state/cache-sweep").join(INDEX_FILE),
+        )
+    }
+
+    fn make_target(root: &Path, rel: &str) -> PathBuf {
+        let dir = root.join(rel);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("file.txt"), "content").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_trash_moves_folder_and_writes_trashinfo() {
+        let temp = tempdir().unwrap();
+        let trash = test_trash(temp.path());
+        let target = make_target(temp.path(), "project/node_modules");
+
+        let item = trash.trash(&target).unwrap();
+
+        assert!(!target.exists());
+        assert!(item.files_path().join("file.txt").exists());
+        assert_eq!(item.trash_dir, temp.path().join("data/Trash"));
+
+        let info = fs::read_to_string(item.info_path()).unwrap();
+        assert!(info.starts_with("[Trash Info]\n"));
+        assert!(info.contains(&format!("Path={}\n", encode_path(&target))));
+        assert!(info.contains("DeletionDate="));
+    }
+
+    #[test]
+    fn test_trash_name_collision_gets_suffix() {
+        let temp = tempdir().unwrap();
+        let trash = test_trash(temp.path());
+        let first = make_target(temp.path(), "a/node_modules");
+        let second = make_target(temp.path(), "b/node_modules");
+
+        let first = trash.trash(&first).unwrap();
+        let second = trash.trash(&second).unwrap();
+
+        assert_eq!(first.name, "node_modules");
+        assert_eq!(second.name, "node_modules.2");
+        assert!(second.info_path().exists());
+    }
+
+    #[test]
+    fn test_list_only_shows_items_still_in_trash() {
+        let temp = tempdir().unwrap();
+        let trash = test_trash(temp.path());
+        let kept = trash.trash(&make_target(temp.path(), "a/target")).unwrap();
+        let gone = trash.trash(&make_target(temp.path(), "b/.venv")).unwrap();
+
+        // Emptied by a file manager
+        fs::remove_dir_all(gone.files_path()).unwrap();
+
+        assert_eq!(trash.list().unwrap(), vec![kept]);
+    }
+
+    #[test]
+    fn test_restore_moves_folder_back() {
+        let temp = tempdir().unwrap();
+        let trash = test_trash(temp.path());
+        let target = make_target(temp.path(), "project/node_modules");
+        let item = trash.trash(&target).unwrap();
+
+        trash.restore(&item).unwrap();
+
+        assert!(target.join("file.txt").exists());
+        assert!(!item.info_path().exists());
+        assert!(trash.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_restore_refuses_to_overwrite() {
+        let temp = tempdir().unwrap();
+        let trash = test_trash(temp.path());
+        let target = make_target(temp.path(), "project/node_modules");
+        let item = trash.trash(&target).unwrap();
+        fs::create_dir(&target).unwrap();
+
+        let err = trash.restore(&item).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert!(item.exists(), "item should stay in the trash");
+    }
+
+    #[test]
+    fn test_purge_removes_files_and_info() {
+        let temp = tempdir().unwrap();
+        let trash = test_trash(temp.path());
+        let item = trash
+            .trash(&make_target(temp.path(), "project/target"))
+            .unwrap();
+
+        assert_eq!(trash.purge(&item).unwrap(), "content".len() as u64);
+
+        assert!(!item.files_path().exists());
+        assert!(!item.info_path().exists());
+        assert!(trash.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_trash_rejects_files() {
+        let temp = tempdir().unwrap();
+        let trash = test_trash(temp.path());
+        let file = temp.path().join("file.txt");
+        fs::write(&file, "x").unwrap();
+
+        assert!(trash.trash(&file).is_err());
+        assert!(file.exists());
+    }
+
+    #[test]
+    fn test_trash_puts_folder_back_when_index_fails() {
+        let temp = tempdir().unwrap();
+        let trash = test_trash(temp.path());
+        let target = make_target(temp.path(), "project/node_modules");
+        // A file where the state directory should be
+        fs::write(temp.path().join("state"), "").unwrap();
+
+        assert!(trash.trash(&target).is_err());
+
+        assert!(target.join("file.txt").exists());
+        let info = temp.path().join("data/Trash/info");
+        assert_eq!(fs::read_dir(info).unwrap().count(), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_trash_and_restore_non_utf8_folder() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp = tempdir().unwrap();
+        let trash = test_trash(temp.path());
+        let target = temp.path().join(OsStr::from_bytes(b"caf\xe9/node_modules"));
+        fs::create_dir_all(&target).unwrap();
+
+        let item = trash.trash(&target).unwrap();
+        let listed = trash.list().unwrap();
+        assert_eq!(listed, vec![item]);
+        assert_eq!(listed[0].original_path, target);
+
+        trash.restore(&listed[0]).unwrap();
+        assert!(target.is_dir());
+        assert!(trash.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_encode_path_escapes_reserved_bytes() {
+        assert_eq!(
+            encode_path(Path::new("/home/me/my project/50%")),
+            "/home/me/my%20project/50%25"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_topdir_trash_falls_back_to_uid_dir() {
+        let temp = tempdir().unwrap();
+
+        let dir = topdir_trash(temp.path(), 1234).unwrap();
+
+        assert_eq!(dir, temp.path().join(".Trash-1234"));
+        assert!(dir.join("files").is_dir());
+        assert!(dir.join("info").is_dir());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_topdir_trash_uses_shared_sticky_dir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = tempdir().unwrap();
+        let shared = temp.path().join(".Trash");
+        fs::create_dir(&shared).unwrap();
+        fs::set_permissions(&shared, fs::Permissions::from_mode(0o1777)).unwrap();
+
+        let dir = topdir_trash(temp.path(), 1234).unwrap();
+
+        assert_eq!(dir, shared.join("1234"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_topdir_trash_ignores_shared_dir_without_sticky_bit() {
+        let temp = tempdir().unwrap();
+        fs::create_dir(temp.path().join(".Trash")).unwrap();
+
+        let dir = topdir_trash(temp.path(), 1234).unwrap();
+
+        assert_eq!(dir, temp.path().join(".Trash-1234"));
+    }
+}
@@ -1,4 +1,5 @@
 mod cli;
+mod commands;
 mod delete;
 mod output;
 mod profiles;
@@ -6,13 +7,15 @@ mod risk;
 mod scanner;
 mod tui;
 
+use std::process::ExitCode;
+
 use anyhow::Result;
 use clap::Parser;
 use cli::Args;
 use tokio_util::sync::CancellationToken;
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> Result<ExitCode> {
     let args = Args::parse();
     let cancel_token = CancellationToken::new();
 
@@ -23,10 +26,18 @@ async fn main() -> Result<()> {
         cancel_clone.cancel();
     });
 
+    if let Some(command) = &args.command {
+        return commands::run(command, cancel_token)
+            .await
+            .map(|()| ExitCode::SUCCESS);
+    }
+
     // Determine output mode
-    if args.json || args.json_stream {
+    if args.is_non_interactive() {
         output::run_non_interactive(&args, cancel_token).await
     } else {
-        tui::run(&args, cancel_token).await
+        tui::run(&args, cancel_token)
+            .await
+            .map(|()| ExitCode::SUCCESS)
     }
 }
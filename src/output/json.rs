@@ -4,9 +4,15 @@ use anyhow::Result;
 use serde::Serialize;
 use tokio_util::sync::CancellationToken;
 
+use super::{collect_sorted, JsonPath, SizedResult, Tally};
 use crate::cli::Args;
-use crate::risk::analyze_risk;
-use crate::scanner::{calculate_size, start_scan};
+use crate::delete::DirIdentity;
+use crate::profiles::profiles_for_target;
+use crate::scanner::ScanErrors;
+use crate::tui::SortOrder;
+
+/// Bumped whenever a field changes meaning or is removed (see `docs/json-output.schema.json`)
+pub const JSON_VERSION: u8 = 2;
 
 #[derive(Serialize)]
 struct JsonOutput {
@@ -18,12 +24,31 @@ struct JsonOutput {
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct JsonResult {
-    path: String,
+    #[serde(flatten)]
+    path: JsonPath,
+    /// Folder name that matched (e.g., `node_modules`)
+    target: String,
+    /// Built-in profiles that include `target` (empty for folders added by a project config)
+    profiles: Vec<&'static str>,
+    project: Option<ProjectJson>,
     size: u64,
+    file_count: u64,
     modification_time: Option<u64>,
+    /// Device, inode and owner, so `apply` can tell the folder wasn't swapped out
+    identity: Option<DirIdentity>,
     risk_analysis: RiskJson,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectJson {
+    name: Option<String>,
+    root: String,
+    /// Like `pathBytes`, for roots that aren't valid UTF-8
+    #[serde(skip_serializing_if = "Option::is_none")]
+    root_bytes: Option<String>,
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct RiskJson {
@@ -36,64 +61,69 @@ struct RiskJson {
 struct JsonMeta {
     results_count: usize,
     run_duration: u64,
+    roots: Vec<String>,
+    targets: Vec<String>,
+    sort: &'static str,
+    total_size: u64,
+    /// Directories the scan couldn't read
+    error_count: usize,
+    tool_version: &'static str,
 }
 
-pub async fn run(args: &Args, cancel_token: CancellationToken) -> Result<()> {
-    let start = Instant::now();
-    let mut results = Vec::new();
-
-    let root = args.effective_directory();
-    let targets = args.effective_targets();
-    let excludes = args.exclude.clone().unwrap_or_default();
-
-    let mut rx = start_scan(
-        root,
-        targets,
-        excludes,
-        args.follow_links,
-        args.respect_ignore,
-        cancel_token.clone(),
-    );
-
-    // Collect all results
-    while let Some(batch) = rx.recv().await {
-        for result in batch {
-            if cancel_token.is_cancelled() {
-                break;
-            }
-
-            let risk = analyze_risk(&result.path);
-
-            if !args.show_protected && risk.is_sensitive {
-                continue;
+impl JsonResult {
+    fn new(sized: SizedResult) -> Self {
+        let modification_time = sized.modification_time();
+        let target = sized.target();
+        let project = sized.project().map(|(root, name)| {
+            let root = JsonPath::new(root);
+            ProjectJson {
+                name,
+                root: root.path,
+                root_bytes: root.path_bytes,
             }
-
-            let (size, _file_count) = calculate_size(&result.path).await;
-            let modification_time = result
-                .modified
-                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                .map(|d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX));
-
-            results.push(JsonResult {
-                path: result.path.to_string_lossy().to_string(),
-                size,
-                modification_time,
-                risk_analysis: RiskJson {
-                    is_sensitive: risk.is_sensitive,
-                    reason: risk.reason,
-                },
-            });
+        });
+        let SizedResult { result, risk } = sized;
+
+        Self {
+            path: JsonPath::new(&result.path),
+            profiles: profiles_for_target(&target),
+            target,
+            project,
+            size: result.size.unwrap_or(0),
+            file_count: result.file_count.unwrap_or(0),
+            modification_time,
+            identity: result.identity,
+            risk_analysis: RiskJson {
+                is_sensitive: risk.is_sensitive,
+                reason: risk.reason,
+            },
         }
     }
+}
 
-    let results_count = results.len();
+pub async fn run(args: &Args, tally: &Tally, cancel_token: CancellationToken) -> Result<()> {
+    let start = Instant::now();
+    let sort_order = SortOrder::from_str(&args.sort);
+    let errors = ScanErrors::default();
+
+    let sized = collect_sorted(&args.scan, sort_order, errors.clone(), tally, cancel_token).await;
+    let results: Vec<JsonResult> = sized.into_iter().map(JsonResult::new).collect();
+
+    let root = args.scan.effective_directory();
+    let root = std::path::absolute(&root).unwrap_or(root);
     let output = JsonOutput {
-        version: 1,
-        results,
+        version: JSON_VERSION,
         meta: JsonMeta {
-            results_count,
+            results_count: results.len(),
             run_duration: u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX),
+            roots: vec![root.to_string_lossy().to_string()],
+            targets: args.scan.effective_targets(),
+            sort: sort_order.name(),
+            total_size: results.iter().map(|r| r.size).sum(),
+            error_count: errors.count(),
+            tool_version: env!("CARGO_PKG_VERSION"),
         },
+        results,
     };
 
     println!("{}", serde_json::to_string_pretty(&output)?);
@@ -0,0 +1,174 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A path as written to JSON (flattened into the surrounding object).
+///
+/// `path` is always readable text, but lossy for names that aren't valid UTF-8;
+/// those also carry `pathBytes`, the exact bytes in base64, which readers must prefer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonPath {
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_bytes: Option<String>,
+}
+
+impl JsonPath {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_string_lossy().to_string(),
+            path_bytes: raw_bytes(path).map(|bytes| BASE64.encode(bytes)),
+        }
+    }
+
+    /// The exact path, from `pathBytes` when present
+    pub fn to_path_buf(&self) -> Result<PathBuf> {
+        let Some(encoded) = &self.path_bytes else {
+            return Ok(PathBuf::from(&self.path));
+        };
+        let bytes = BASE64
+            .decode(encoded)
+            .with_context(|| format!("{}: pathBytes is not valid base64", self.path))?;
+        from_bytes(bytes, &self.path)
+    }
+}
+
+/// `#[serde(with = "nested")]` for `PathBuf` fields kept in state files: the path is
+/// written as a `JsonPath` object, so names that aren't valid UTF-8 survive a round trip.
+/// Plain strings, as older versions wrote, are still read.
+pub mod nested {
+    use super::{Deserialize, Deserializer, JsonPath, Path, PathBuf, Serialize, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    pub(super) enum Stored {
+        Text(PathBuf),
+        Exact(JsonPath),
+    }
+
+    pub fn serialize<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+        JsonPath::new(path).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+        match Stored::deserialize(deserializer)? {
+            Stored::Text(path) => Ok(path),
+            Stored::Exact(path) => path.to_path_buf().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// `deserialize_with` for an optional nested `JsonPath` that older versions wrote as a
+/// plain string
+pub fn optional_or_text<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<JsonPath>, D::Error> {
+    Ok(
+        Option::<nested::Stored>::deserialize(deserializer)?.map(|stored| match stored {
+            nested::Stored::Text(path) => JsonPath::new(&path),
+            nested::Stored::Exact(path) => path,
+        }),
+    )
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.path)
+    }
+}
+
+/// The path's bytes, only when they aren't valid UTF-8 (and so `path` alone would be lossy)
+#[cfg(unix)]
+fn raw_bytes(path: &Path) -> Option<&[u8]> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let bytes = path.as_os_str().as_bytes();
+    std::str::from_utf8(bytes).is_err().then_some(bytes)
+}
+
+#[cfg(not(unix))]
+const fn raw_bytes(_path: &Path) -> Option<&[u8]> {
+    None
+}
+
+#[cfg(unix)]
+#[allow(clippy::unnecessary_wraps)] // Fails on other platforms
+fn from_bytes(bytes: Vec<u8>, _lossy: &str) -> Result<PathBuf> {
+    use std::os::unix::ffi::OsStringExt;
+
+    Ok(PathBuf::from(std::ffi::OsString::from_vec(bytes)))
+}
+
+#[cfg(not(unix))]
+fn from_bytes(_bytes: Vec<u8>, lossy: &str) -> Result<PathBuf> {
+    anyhow::bail!("{lossy}: pathBytes is only supported on Unix")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf8_path_has_no_bytes() {
+        let json = serde_json::to_value(JsonPath::new(Path::new("/code/café/node_modules")));
+        assert_eq!(
+            json.unwrap(),
+            serde_json::json!({"path": "/code/café/node_modules"})
+        );
+    }
+
+    #[test]
+    fn test_plain_path_round_trips() {
+        let parsed: JsonPath = serde_json::from_str(r#"{"path": "/a/target"}"#).unwrap();
+        assert_eq!(parsed.to_path_buf().unwrap(), PathBuf::from("/a/target"));
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Item {
+        #[serde(with = "nested")]
+        original: PathBuf,
+    }
+
+    #[test]
+    fn test_nested_reads_objects_and_plain_strings() {
+        let json = serde_json::to_string(&Item {
+            original: PathBuf::from("/a/target"),
+        })
+        .unwrap();
+        assert_eq!(json, r#"{"original":{"path":"/a/target"}}"#);
+
+        let old: Item = serde_json::from_str(r#"{"original":"/a/target"}"#).unwrap();
+        assert_eq!(old.original, PathBuf::from("/a/target"));
+    }
+
+    #[test]
+    fn test_bad_base64_is_an_error() {
+        let parsed = JsonPath {
+            path: "/a/target".to_string(),
+            path_bytes: Some("not base64!".to_string()),
+        };
+        assert!(parsed.to_path_buf().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_path_round_trips_through_path_bytes() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(OsStr::from_bytes(b"/code/caf\xe9/node_modules"));
+        let json = serde_json::to_string(&JsonPath::new(path)).unwrap();
+        assert_eq!(
+            json,
+            r#"{"path":"/code/caf�/node_modules","pathBytes":"L2NvZGUvY2Fm6S9ub2RlX21vZHVsZXM="}"#
+        );
+
+        let parsed: JsonPath = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.to_path_buf().unwrap(), path);
+    }
+}
@@ -1,15 +1,214 @@
 mod json;
+mod json_path;
+mod paths;
+mod prometheus;
+mod report;
 mod stream;
+mod table;
+mod threshold;
 
-use anyhow::Result;
+use std::io::Write;
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use bytesize::ByteSize;
+use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 
-use crate::cli::Args;
+use crate::cli::{Args, OutputFormat, ScanArgs};
+use crate::risk::{analyze_scan_result, RiskAnalysis};
+use crate::scanner::{calculate_size, project_name, start_scan, ScanErrors, ScanResult};
+use crate::tui::SortOrder;
+
+pub use json::JSON_VERSION;
+pub use json_path::{nested as nested_json_path, optional_or_text, JsonPath};
+use threshold::{breaches, Tally, THRESHOLD_EXIT_CODE};
 
-pub async fn run_non_interactive(args: &Args, cancel_token: CancellationToken) -> Result<()> {
-    if args.json_stream {
-        stream::run(args, cancel_token).await
+pub async fn run_non_interactive(args: &Args, cancel_token: CancellationToken) -> Result<ExitCode> {
+    let tally = Tally::default();
+    if args.paths || args.print0 {
+        paths::run(args, &tally, cancel_token).await?;
+    } else if let Some(path) = &args.report {
+        report::run(args, path, &tally, cancel_token).await?;
+    } else if let Some(format) = args.format {
+        let output = args.output.as_deref();
+        match format {
+            OutputFormat::Csv => table::run(args, ',', output, &tally, cancel_token).await?,
+            OutputFormat::Tsv => table::run(args, '\t', output, &tally, cancel_token).await?,
+            OutputFormat::Prometheus => prometheus::run(args, output, &tally, cancel_token).await?,
+        }
+    } else if args.json_stream {
+        stream::run(args, &tally, cancel_token).await?;
+    } else if args.json {
+        json::run(args, &tally, cancel_token).await?;
     } else {
-        json::run(args, cancel_token).await
+        // Only --fail-over/--fail-count: check without printing results
+        collect_sorted(
+            &args.scan,
+            SortOrder::Path,
+            ScanErrors::default(),
+            &tally,
+            cancel_token,
+        )
+        .await;
+    }
+
+    let totals = tally.totals();
+    let breaches = breaches(&args.fail_over, args.fail_count, &totals);
+    if breaches.is_empty() {
+        return Ok(ExitCode::SUCCESS);
+    }
+    eprintln!(
+        "cache-sweep: {} reclaimable in {} folders is over the limit",
+        ByteSize::b(totals.size),
+        totals.count
+    );
+    for breach in &breaches {
+        eprintln!("  {breach}");
+    }
+    Ok(ExitCode::from(THRESHOLD_EXIT_CODE))
+}
+
+/// A scan result with its size filled in
+struct SizedResult {
+    result: ScanResult,
+    risk: RiskAnalysis,
+}
+
+impl SizedResult {
+    /// Folder name that matched (e.g., `node_modules`)
+    fn target(&self) -> String {
+        self.result
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    /// The project's root (the parent directory) and its name, if it has a parent
+    fn project(&self) -> Option<(&Path, Option<String>)> {
+        let root = self.result.path.parent()?;
+        Some((root, project_name(root)))
+    }
+
+    fn modification_time(&self) -> Option<u64> {
+        self.result
+            .modified
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX))
     }
 }
+
+/// Scans and sizes results concurrently, sending each one as soon as its size is known.
+/// Sizing runs behind `calculate_size`'s semaphore, like the TUI's.
+/// Hidden protected folders are dropped before sizing, the size/age filters after.
+fn sized_results(
+    args: &ScanArgs,
+    errors: ScanErrors,
+    tally: &Tally,
+    cancel_token: CancellationToken,
+) -> mpsc::UnboundedReceiver<SizedResult> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let show_protected = args.show_protected;
+    let filter = args.filter();
+    let query = args.query.clone();
+    let active_window = args.active_window();
+    let tally = tally.clone();
+
+    let mut scan_rx = start_scan(
+        args.effective_directory(),
+        args.effective_targets(),
+        args.exclude.clone().unwrap_or_default(),
+        args.follow_links,
+        args.respect_ignore,
+        errors,
+        cancel_token.clone(),
+    );
+
+    tokio::spawn(async move {
+        while let Some(batch) = scan_rx.recv().await {
+            for mut result in batch {
+                let risk = analyze_scan_result(&result);
+                if !show_protected && risk.is_sensitive {
+                    continue;
+                }
+                result.is_sensitive = risk.is_sensitive;
+
+                let tx = tx.clone();
+                let tally = tally.clone();
+                let query = query.clone();
+                let cancel_token = cancel_token.clone();
+                tokio::spawn(async move {
+                    if cancel_token.is_cancelled() {
+                        return;
+                    }
+                    let stats = calculate_size(&result.path).await;
+                    result.size = Some(stats.size);
+                    result.file_count = Some(stats.file_count);
+                    result.newest_modified = stats.newest_modified;
+                    let now = SystemTime::now();
+                    if filter.matches(&result, now)
+                        && query
+                            .as_ref()
+                            .is_none_or(|query| query.matches(&result, now, active_window))
+                    {
+                        let sized = SizedResult { result, risk };
+                        tally.record(&sized);
+                        tx.send(sized).ok();
+                    }
+                });
+            }
+        }
+        // Each sizing task holds a sender, so the channel closes once the last one finishes
+    });
+
+    rx
+}
+
+/// Collects every sized result, then sorts them like the TUI would.
+/// Ties (and `path` order) fall back to the path, so the same tree always gives the same output.
+async fn collect_sorted(
+    args: &ScanArgs,
+    sort_order: SortOrder,
+    errors: ScanErrors,
+    tally: &Tally,
+    cancel_token: CancellationToken,
+) -> Vec<SizedResult> {
+    let mut rx = sized_results(args, errors, tally, cancel_token.clone());
+    let mut sized = Vec::new();
+    while let Some(result) = rx.recv().await {
+        if cancel_token.is_cancelled() {
+            break;
+        }
+        sized.push(result);
+    }
+
+    sized.sort_by(|a, b| {
+        let (a, b) = (&a.result, &b.result);
+        let order = match sort_order {
+            SortOrder::Size => b.size.cmp(&a.size), // Largest first
+            SortOrder::Path => std::cmp::Ordering::Equal,
+            SortOrder::Age => a.modified.cmp(&b.modified), // Oldest first
+        };
+        order.then_with(|| a.path.cmp(&b.path))
+    });
+    sized
+}
+
+/// Prints `contents`, or writes it to `path` via a temporary file and a rename,
+/// so readers (e.g., a metrics scrape) never see a partial file
+fn emit(path: Option<&Path>, contents: &[u8]) -> Result<()> {
+    let Some(path) = path else {
+        let mut out = std::io::stdout().lock();
+        out.write_all(contents)?;
+        return Ok(out.flush()?);
+    };
+
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, contents)
+        .and_then(|()| std::fs::rename(&tmp, path))
+        .with_context(|| format!("{}: could not write output", path.display()))
+}
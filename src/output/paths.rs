@@ -0,0 +1,63 @@
+use std::path::Path;
+
+use anyhow::Result;
+use tokio_util::sync::CancellationToken;
+
+use super::{collect_sorted, emit, Tally};
+use crate::cli::Args;
+use crate::scanner::ScanErrors;
+use crate::tui::SortOrder;
+
+/// Prints the matched paths, newline- or (`--print0`) NUL-terminated
+pub async fn run(args: &Args, tally: &Tally, cancel_token: CancellationToken) -> Result<()> {
+    let sort_order = SortOrder::from_str(&args.sort);
+    let sized = collect_sorted(
+        &args.scan,
+        sort_order,
+        ScanErrors::default(),
+        tally,
+        cancel_token,
+    )
+    .await;
+
+    let terminator = if args.print0 { b'\0' } else { b'\n' };
+    let mut out = Vec::new();
+    for sized in &sized {
+        push_path(&mut out, &sized.result.path, terminator);
+    }
+    emit(None, &out)
+}
+
+/// Appends the path's raw bytes, so non-UTF-8 names survive the trip through `xargs -0`
+fn push_path(out: &mut Vec<u8>, path: &Path, terminator: u8) {
+    #[cfg(unix)]
+    out.extend_from_slice(std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()));
+    #[cfg(not(unix))]
+    out.extend_from_slice(path.to_string_lossy().as_bytes());
+    out.push(terminator);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_path_terminators() {
+        let mut out = Vec::new();
+        push_path(&mut out, Path::new("/a/node_modules"), b'\n');
+        push_path(&mut out, Path::new("/b\nc/target"), b'\0');
+        assert_eq!(out, b"/a/node_modules\n/b\nc/target\0");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_push_path_keeps_non_utf8_bytes() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(OsStr::from_bytes(b"/caf\xe9/node_modules"));
+        let mut out = Vec::new();
+        push_path(&mut out, path, b'\0');
+        assert_eq!(out, b"/caf\xe9/node_modules\0");
+    }
+}
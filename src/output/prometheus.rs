@@ -0,0 +1,258 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use tokio_util::sync::CancellationToken;
+
+use super::{collect_sorted, emit, SizedResult, Tally};
+use crate::cli::Args;
+use crate::profiles::profiles_for_target;
+use crate::scanner::ScanErrors;
+use crate::tui::{AnalyticsData, SortOrder};
+
+/// Everything one metrics file reports
+struct Scan<'a> {
+    root: &'a str,
+    results: &'a [SizedResult],
+    duration: Duration,
+    errors: usize,
+    finished: SystemTime,
+}
+
+pub async fn run(
+    args: &Args,
+    output: Option<&Path>,
+    tally: &Tally,
+    cancel_token: CancellationToken,
+) -> Result<()> {
+    let start = Instant::now();
+    let errors = ScanErrors::default();
+    let results = collect_sorted(
+        &args.scan,
+        SortOrder::Path,
+        errors.clone(),
+        tally,
+        cancel_token,
+    )
+    .await;
+
+    let root = args.scan.effective_directory();
+    let root = std::path::absolute(&root).unwrap_or(root);
+    let metrics = render(&Scan {
+        root: &root.to_string_lossy(),
+        results: &results,
+        duration: start.elapsed(),
+        errors: errors.count(),
+        finished: SystemTime::now(),
+    });
+
+    emit(output, metrics.as_bytes())
+}
+
+/// Size and number of folders per profile. A folder counts towards every profile that
+/// has its target, as with `--fail-over`, so the profiles overlap; folders no profile
+/// has (from `--targets`) count as `other`.
+fn profile_totals(results: &[SizedResult]) -> BTreeMap<&'static str, (u64, u64)> {
+    let mut totals: BTreeMap<&'static str, (u64, u64)> = BTreeMap::new();
+    for sized in results {
+        let mut profiles = profiles_for_target(&sized.target());
+        if profiles.is_empty() {
+            profiles.push("other");
+        }
+        for profile in profiles {
+            let total = totals.entry(profile).or_default();
+            total.0 += sized.result.size.unwrap_or(0);
+            total.1 += 1;
+        }
+    }
+    totals
+}
+
+/// Metrics in the Prometheus text format (per profile and target)
+fn render(scan: &Scan) -> String {
+    let mut analytics = AnalyticsData::new();
+    for sized in scan.results {
+        analytics.record_result(&sized.result.path, sized.result.size);
+    }
+    let profiles = profile_totals(scan.results);
+    let mut targets = analytics.targets_by_size();
+    targets.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut out = String::new();
+    family(
+        &mut out,
+        "cache_sweep_profile_bytes",
+        "Total size of matching folders per profile, in bytes.",
+        profiles
+            .iter()
+            .map(|(name, (size, _))| (format!("profile={}", label(name)), *size)),
+    );
+    family(
+        &mut out,
+        "cache_sweep_profile_folders",
+        "Number of matching folders per profile.",
+        profiles
+            .iter()
+            .map(|(name, (_, count))| (format!("profile={}", label(name)), *count)),
+    );
+    family(
+        &mut out,
+        "cache_sweep_target_bytes",
+        "Total size of matching folders per target folder name, in bytes.",
+        targets
+            .iter()
+            .map(|t| (format!("target={}", label(&t.name)), t.total_size)),
+    );
+    family(
+        &mut out,
+        "cache_sweep_target_folders",
+        "Number of matching folders per target folder name.",
+        targets
+            .iter()
+            .map(|t| (format!("target={}", label(&t.name)), t.count as u64)),
+    );
+    let root = format!("root={}", label(scan.root));
+    family(
+        &mut out,
+        "cache_sweep_root_bytes",
+        "Total size of matching folders under each scanned root, in bytes.",
+        [(root.clone(), analytics.total_size())],
+    );
+    family(
+        &mut out,
+        "cache_sweep_root_folders",
+        "Number of matching folders under each scanned root.",
+        [(root, analytics.total_count() as u64)],
+    );
+
+    let _ = writeln!(
+        out,
+        "# HELP cache_sweep_scan_duration_seconds How long the scan took.\n\
+         # TYPE cache_sweep_scan_duration_seconds gauge\n\
+         cache_sweep_scan_duration_seconds {:.3}",
+        scan.duration.as_secs_f64()
+    );
+    let _ = writeln!(
+        out,
+        "# HELP cache_sweep_scan_errors Directories the scan couldn't read.\n\
+         # TYPE cache_sweep_scan_errors gauge\n\
+         cache_sweep_scan_errors {}",
+        scan.errors
+    );
+    let _ = writeln!(
+        out,
+        "# HELP cache_sweep_last_scan_timestamp_seconds When the scan finished, in seconds since the Unix epoch.\n\
+         # TYPE cache_sweep_last_scan_timestamp_seconds gauge\n\
+         cache_sweep_last_scan_timestamp_seconds {}",
+        scan.finished
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    );
+    out
+}
+
+/// Writes one gauge with a sample per label set
+fn family(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    samples: impl IntoIterator<Item = (String, u64)>,
+) {
+    let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} gauge");
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{name}{{{labels}}} {value}");
+    }
+}
+
+/// Quotes a label value, escaping backslashes, quotes and line breaks
+fn label(value: &str) -> String {
+    let escaped = value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n");
+    format!("\"{escaped}\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::risk::RiskAnalysis;
+    use crate::scanner::ScanResult;
+    use std::path::PathBuf;
+
+    fn sized(path: &str, size: u64) -> SizedResult {
+        let mut result = ScanResult::new(PathBuf::from(path));
+        result.size = Some(size);
+        SizedResult {
+            result,
+            risk: RiskAnalysis::default(),
+        }
+    }
+
+    #[test]
+    fn test_label_escaping() {
+        assert_eq!(label("node"), "\"node\"");
+        assert_eq!(label(r"C:\code"), r#""C:\\code""#);
+        assert_eq!(label("say \"hi\"\nbye"), r#""say \"hi\"\nbye""#);
+    }
+
+    #[test]
+    fn test_render_metrics() {
+        let results = [
+            sized("/code/web/node_modules", 300),
+            sized("/code/api/node_modules", 100),
+            sized("/code/tool/__pycache__", 50),
+        ];
+        let metrics = render(&Scan {
+            root: "/code \"main\"",
+            results: &results,
+            duration: Duration::from_millis(1500),
+            errors: 2,
+            finished: UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        });
+
+        for line in [
+            "# TYPE cache_sweep_profile_bytes gauge",
+            "cache_sweep_profile_bytes{profile=\"node\"} 400",
+            "cache_sweep_profile_bytes{profile=\"python\"} 50",
+            "cache_sweep_profile_folders{profile=\"node\"} 2",
+            "cache_sweep_target_bytes{target=\"__pycache__\"} 50",
+            "cache_sweep_target_folders{target=\"node_modules\"} 2",
+            "cache_sweep_root_bytes{root=\"/code \\\"main\\\"\"} 450",
+            "cache_sweep_root_folders{root=\"/code \\\"main\\\"\"} 3",
+            "cache_sweep_scan_duration_seconds 1.500",
+            "cache_sweep_scan_errors 2",
+            "cache_sweep_last_scan_timestamp_seconds 1700000000",
+        ] {
+            assert!(metrics.lines().any(|l| l == line), "missing {line}");
+        }
+        // Every family is declared once, before its samples
+        assert_eq!(metrics.matches("# TYPE").count(), 9);
+        assert!(metrics.ends_with('\n'));
+    }
+
+    #[test]
+    fn test_folder_counts_towards_every_profile_with_its_target() {
+        let results = [sized("/code/app/target", 70), sized("/code/app/models", 5)];
+        let metrics = render(&Scan {
+            root: "/code",
+            results: &results,
+            duration: Duration::ZERO,
+            errors: 0,
+            finished: UNIX_EPOCH,
+        });
+
+        for line in [
+            "cache_sweep_profile_bytes{profile=\"java\"} 70",
+            "cache_sweep_profile_bytes{profile=\"rust\"} 70",
+            "cache_sweep_profile_bytes{profile=\"scala\"} 70",
+            "cache_sweep_profile_folders{profile=\"other\"} 1",
+            "cache_sweep_root_bytes{root=\"/code\"} 75",
+        ] {
+            assert!(metrics.lines().any(|l| l == line), "missing {line}");
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use std::path::Path;
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use bytesize::ByteSize;
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+
+use super::{collect_sorted, SizedResult, Tally};
+use crate::cli::Args;
+use crate::scanner::ScanErrors;
+use crate::tui::{AnalyticsData, SortOrder};
+
+/// Page layout and scripts; `{{DATA}}` is replaced by the report as JSON
+const TEMPLATE: &str = include_str!("report.html");
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Report {
+    generated: String,
+    tool_version: &'static str,
+    roots: Vec<String>,
+    targets: Vec<String>,
+    summary: Summary,
+    profiles: Vec<Breakdown>,
+    target_stats: Vec<Breakdown>,
+    results: Vec<ReportResult>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Summary {
+    count: usize,
+    total_size: u64,
+    error_count: usize,
+    run_duration: u64,
+}
+
+/// One row of the per-profile or per-target tables
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Breakdown {
+    name: String,
+    count: usize,
+    total_size: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportResult {
+    path: String,
+    target: String,
+    /// Same attribution as the TUI's analytics panel
+    profile: String,
+    project: Option<String>,
+    size: u64,
+    file_count: u64,
+    modified: Option<u64>,
+    sensitive: bool,
+}
+
+pub async fn run(
+    args: &Args,
+    path: &Path,
+    tally: &Tally,
+    cancel_token: CancellationToken,
+) -> Result<()> {
+    let start = Instant::now();
+    let errors = ScanErrors::default();
+    let sized = collect_sorted(
+        &args.scan,
+        SortOrder::Size,
+        errors.clone(),
+        tally,
+        cancel_token,
+    )
+    .await;
+
+    let root = args.scan.effective_directory();
+    let root = std::path::absolute(&root).unwrap_or(root);
+    let mut report = Report::new(&sized, args.scan.effective_targets());
+    report.roots = vec![root.to_string_lossy().to_string()];
+    report.summary.error_count = errors.count();
+    report.summary.run_duration = u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX);
+
+    std::fs::write(path, render(&report)?)
+        .with_context(|| format!("{}: could not write report", path.display()))?;
+    println!(
+        "Wrote {} ({} folders, {})",
+        path.display(),
+        report.summary.count,
+        ByteSize::b(report.summary.total_size)
+    );
+    Ok(())
+}
+
+impl Report {
+    fn new(sized: &[SizedResult], targets: Vec<String>) -> Self {
+        let mut analytics = AnalyticsData::new();
+        for sized in sized {
+            analytics.record_result(&sized.result.path, sized.result.size);
+        }
+
+        let results = sized
+            .iter()
+            .map(|sized| {
+                let target = sized.target();
+                // Absolute, so the treemap can nest results under the (absolute) root
+                let path = std::path::absolute(&sized.result.path)
+                    .unwrap_or_else(|_| sized.result.path.clone());
+                ReportResult {
+                    path: path.to_string_lossy().to_string(),
+                    profile: AnalyticsData::profile_for_target(&target, &sized.result.path),
+                    target,
+                    project: sized.project().and_then(|(_, name)| name),
+                    size: sized.result.size.unwrap_or(0),
+                    file_count: sized.result.file_count.unwrap_or(0),
+                    modified: sized.modification_time(),
+                    sensitive: sized.risk.is_sensitive,
+                }
+            })
+            .collect();
+
+        Self {
+            generated: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            tool_version: env!("CARGO_PKG_VERSION"),
+            roots: Vec::new(),
+            targets,
+            summary: Summary {
+                count: analytics.total_count(),
+                total_size: analytics.total_size(),
+                error_count: 0,
+                run_duration: 0,
+            },
+            profiles: analytics
+                .profiles_by_size()
+                .into_iter()
+                .map(|p| Breakdown {
+                    name: p.name.clone(),
+                    count: p.count,
+                    total_size: p.total_size,
+                })
+                .collect(),
+            target_stats: analytics
+                .targets_by_size()
+                .into_iter()
+                .map(|t| Breakdown {
+                    name: t.name.clone(),
+                    count: t.count,
+                    total_size: t.total_size,
+                })
+                .collect(),
+            results,
+        }
+    }
+}
+
+/// Fills the template; `<` is escaped so no path can close the data's `<script>` tag
+fn render(report: &Report) -> Result<String> {
+    let data = serde_json::to_string(report)?.replace('<', "\\u003c");
+    Ok(TEMPLATE.replacen("{{DATA}}", &data, 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::risk::RiskAnalysis;
+    use crate::scanner::ScanResult;
+    use std::path::PathBuf;
+
+    fn sized(path: &str, size: u64) -> SizedResult {
+        let mut result = ScanResult::new(PathBuf::from(path));
+        result.size = Some(size);
+        result.file_count = Some(1);
+        SizedResult {
+            result,
+            risk: RiskAnalysis::default(),
+        }
+    }
+
+    #[test]
+    fn test_breakdowns_match_analytics() {
+        let results = [
+            sized("/code/web/node_modules", 300),
+            sized("/code/api/node_modules", 100),
+            sized("/code/tool/__pycache__", 50),
+        ];
+        let report = Report::new(&results, vec!["node_modules".to_string()]);
+
+        assert_eq!((report.summary.count, report.summary.total_size), (3, 450));
+        let profiles: Vec<_> = report
+            .profiles
+            .iter()
+            .map(|p| (p.name.as_str(), p.count, p.total_size))
+            .collect();
+        assert_eq!(profiles, [("node", 2, 400), ("python", 1, 50)]);
+        assert_eq!(report.target_stats[0].name, "node_modules");
+        assert_eq!(report.results[0].profile, "node");
+        assert_eq!(report.results[2].project.as_deref(), Some("tool"));
+    }
+
+    #[test]
+    fn test_render_embeds_data_safely() {
+        let results = [sized("/code/</script><script>alert(1)/node_modules", 10)];
+        let html = render(&Report::new(&results, Vec::new())).unwrap();
+
+        assert!(!html.contains("{{DATA}}"));
+        assert_eq!(html.matches("</script>").count(), 2);
+        assert!(html.contains(r"\u003c/script>\u003cscript>alert(1)"));
+    }
+
+    #[test]
+    fn test_template_needs_no_network() {
+        for remote in ["http://", "https://", "//cdn", "@import"] {
+            assert!(!TEMPLATE.contains(remote), "template references {remote}");
+        }
+    }
+}
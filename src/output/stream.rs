@@ -2,61 +2,43 @@ use anyhow::Result;
 use serde::Serialize;
 use tokio_util::sync::CancellationToken;
 
+use super::{sized_results, JsonPath, Tally};
 use crate::cli::Args;
-use crate::risk::analyze_risk;
-use crate::scanner::{calculate_size, start_scan};
+use crate::scanner::ScanErrors;
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct StreamResult {
-    path: String,
+    #[serde(flatten)]
+    path: JsonPath,
     size: u64,
     modification_time: Option<u64>,
     is_sensitive: bool,
 }
 
-pub async fn run(args: &Args, cancel_token: CancellationToken) -> Result<()> {
-    let root = args.effective_directory();
-    let targets = args.effective_targets();
-    let excludes = args.exclude.clone().unwrap_or_default();
-
-    let mut rx = start_scan(
-        root,
-        targets,
-        excludes,
-        args.follow_links,
-        args.respect_ignore,
+pub async fn run(args: &Args, tally: &Tally, cancel_token: CancellationToken) -> Result<()> {
+    let mut rx = sized_results(
+        &args.scan,
+        ScanErrors::default(),
+        tally,
         cancel_token.clone(),
     );
 
-    while let Some(batch) = rx.recv().await {
-        for result in batch {
-            if cancel_token.is_cancelled() {
-                break;
-            }
-
-            let risk = analyze_risk(&result.path);
-
-            if !args.show_protected && risk.is_sensitive {
-                continue;
-            }
-
-            let (size, _file_count) = calculate_size(&result.path).await;
-            let modification_time = result
-                .modified
-                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                .map(|d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX));
+    // Results arrive as their sizes complete, not in scan order
+    while let Some(sized) = rx.recv().await {
+        if cancel_token.is_cancelled() {
+            break;
+        }
 
-            let stream_result = StreamResult {
-                path: result.path.to_string_lossy().to_string(),
-                size,
-                modification_time,
-                is_sensitive: risk.is_sensitive,
-            };
+        let stream_result = StreamResult {
+            path: JsonPath::new(&sized.result.path),
+            size: sized.result.size.unwrap_or(0),
+            modification_time: sized.modification_time(),
+            is_sensitive: sized.risk.is_sensitive,
+        };
 
-            // One JSON object per line
-            println!("{}", serde_json::to_string(&stream_result)?);
-        }
+        // One JSON object per line
+        println!("{}", serde_json::to_string(&stream_result)?);
     }
 
     Ok(())
@@ -0,0 +1,191 @@
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use chrono::{DateTime, SecondsFormat, Utc};
+use tokio_util::sync::CancellationToken;
+
+use super::{collect_sorted, emit, SizedResult, Tally};
+use crate::cli::Args;
+use crate::profiles::profiles_for_target;
+use crate::scanner::ScanErrors;
+use crate::tui::SortOrder;
+
+const HEADER: [&str; 8] = [
+    "path",
+    "target",
+    "profile",
+    "project",
+    "size",
+    "file_count",
+    "modified",
+    "risk",
+];
+
+/// Prints CSV (`delimiter` = ',') or TSV ('\t')
+pub async fn run(
+    args: &Args,
+    delimiter: char,
+    output: Option<&Path>,
+    tally: &Tally,
+    cancel_token: CancellationToken,
+) -> Result<()> {
+    let sort_order = SortOrder::from_str(&args.sort);
+    let sized = collect_sorted(
+        &args.scan,
+        sort_order,
+        ScanErrors::default(),
+        tally,
+        cancel_token,
+    )
+    .await;
+
+    let now = SystemTime::now();
+    let mut out = Vec::new();
+    write_row(&mut out, delimiter, &HEADER)?;
+    for sized in &sized {
+        let row = row(sized, args.scan.active_window(), now);
+        write_row(&mut out, delimiter, &row)?;
+    }
+
+    emit(output, &out)
+}
+
+/// One line per result, in `HEADER` order
+fn row(sized: &SizedResult, active_window: Option<Duration>, now: SystemTime) -> [String; 8] {
+    let result = &sized.result;
+    let target = sized.target();
+    let profiles = profiles_for_target(&target).join(";");
+    let project = sized
+        .project()
+        .and_then(|(_, name)| name)
+        .unwrap_or_default();
+    let modified = result.modified.map_or_else(String::new, |time| {
+        DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Secs, true)
+    });
+
+    let idle = result
+        .modified
+        .max(result.newest_modified)
+        .map(|time| now.duration_since(time).unwrap_or(Duration::ZERO));
+    let risk = if sized.risk.is_sensitive {
+        "sensitive"
+    } else if active_window
+        .zip(idle)
+        .is_some_and(|(window, idle)| idle < window)
+    {
+        "active"
+    } else {
+        "safe"
+    };
+
+    [
+        result.path.to_string_lossy().to_string(),
+        target,
+        profiles,
+        project,
+        result.size.unwrap_or(0).to_string(),
+        result.file_count.unwrap_or(0).to_string(),
+        modified,
+        risk.to_string(),
+    ]
+}
+
+fn write_row<S: AsRef<str>>(out: &mut impl Write, delimiter: char, fields: &[S]) -> Result<()> {
+    let line: Vec<String> = fields
+        .iter()
+        .map(|field| quote(field.as_ref(), delimiter))
+        .collect();
+    write!(out, "{}\r\n", line.join(&delimiter.to_string()))?;
+    Ok(())
+}
+
+/// Quotes a field the RFC 4180 way when it contains the delimiter, a quote or a line break
+fn quote(field: &str, delimiter: char) -> String {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::risk::RiskAnalysis;
+    use crate::scanner::ScanResult;
+    use std::path::PathBuf;
+
+    fn sized(path: &str, idle: Duration, now: SystemTime) -> SizedResult {
+        let mut result = ScanResult::new(PathBuf::from(path));
+        result.size = Some(2048);
+        result.file_count = Some(3);
+        result.modified = Some(now - idle);
+        SizedResult {
+            result,
+            risk: RiskAnalysis::default(),
+        }
+    }
+
+    #[test]
+    fn test_quote_only_when_needed() {
+        assert_eq!(
+            quote("/code/app/node_modules", ','),
+            "/code/app/node_modules"
+        );
+        assert_eq!(quote("/code/a,b/target", ','), "\"/code/a,b/target\"");
+        assert_eq!(quote("/code/a,b/target", '\t'), "/code/a,b/target");
+        assert_eq!(quote("/code/a\tb/target", '\t'), "\"/code/a\tb/target\"");
+        assert_eq!(quote("say \"hi\"", ','), "\"say \"\"hi\"\"\"");
+        assert_eq!(quote("two\nlines", '\t'), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn test_write_row_joins_with_crlf() {
+        let mut out = Vec::new();
+        write_row(&mut out, ',', &["a", "b,c", ""]).unwrap();
+        write_row(&mut out, '\t', &["a", "b,c"]).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "a,\"b,c\",\r\na\tb,c\r\n");
+    }
+
+    #[test]
+    fn test_row_columns() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let row = row(
+            &sized(
+                "/nonexistent/app/node_modules",
+                Duration::from_hours(24),
+                now,
+            ),
+            Some(Duration::from_mins(10)),
+            now,
+        );
+        assert_eq!(
+            row,
+            [
+                "/nonexistent/app/node_modules",
+                "node_modules",
+                "node",
+                "app",
+                "2048",
+                "3",
+                "2023-11-13T22:13:20Z",
+                "safe",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_row_risk_levels() {
+        let now = SystemTime::now();
+        let window = Some(Duration::from_mins(10));
+        let busy = sized("/nonexistent/app/target", Duration::from_mins(1), now);
+        assert_eq!(row(&busy, window, now)[7], "active");
+        assert_eq!(row(&busy, None, now)[7], "safe");
+
+        let mut protected = sized("/nonexistent/app/target", Duration::from_hours(1), now);
+        protected.risk.is_sensitive = true;
+        assert_eq!(row(&protected, window, now)[7], "sensitive");
+    }
+}
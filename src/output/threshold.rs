@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use bytesize::ByteSize;
+
+use super::SizedResult;
+use crate::cli::SizeThreshold;
+use crate::profiles::profiles_for_target;
+
+/// Exit code when `--fail-over` or `--fail-count` is exceeded (1 is an error, 2 a usage error)
+pub const THRESHOLD_EXIT_CODE: u8 = 3;
+
+/// What the output listed that could be reclaimed (protected folders don't count)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Totals {
+    pub count: usize,
+    pub size: u64,
+    /// A folder counts towards every profile that has its target
+    pub by_profile: HashMap<&'static str, u64>,
+}
+
+/// Shared running `Totals`, recorded as results are sized
+#[derive(Debug, Clone, Default)]
+pub struct Tally(Arc<Mutex<Totals>>);
+
+impl Tally {
+    pub(super) fn record(&self, sized: &SizedResult) {
+        if sized.risk.is_sensitive {
+            return;
+        }
+        let size = sized.result.size.unwrap_or(0);
+        let mut totals = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        totals.count += 1;
+        totals.size += size;
+        for profile in profiles_for_target(&sized.target()) {
+            *totals.by_profile.entry(profile).or_default() += size;
+        }
+    }
+
+    pub fn totals(&self) -> Totals {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+/// One line per exceeded threshold, e.g. `node: 25.0 GiB reclaimable, over 18.6 GiB`
+pub fn breaches(
+    fail_over: &[SizeThreshold],
+    fail_count: Option<usize>,
+    totals: &Totals,
+) -> Vec<String> {
+    let mut breaches = Vec::new();
+    for threshold in fail_over {
+        let label = threshold.profile.as_deref().unwrap_or("total");
+        let size = threshold.profile.as_deref().map_or(totals.size, |profile| {
+            totals.by_profile.get(profile).copied().unwrap_or(0)
+        });
+        if size > threshold.limit {
+            breaches.push(format!(
+                "{label}: {} reclaimable, over {}",
+                ByteSize::b(size),
+                ByteSize::b(threshold.limit)
+            ));
+        }
+    }
+    if let Some(limit) = fail_count.filter(|&limit| totals.count > limit) {
+        breaches.push(format!("count: {} folders, over {limit}", totals.count));
+    }
+    breaches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::risk::analyze_scan_result;
+    use crate::scanner::ScanResult;
+    use std::path::PathBuf;
+
+    fn sized(path: &str, size: u64) -> SizedResult {
+        let mut result = ScanResult::new(PathBuf::from(path));
+        result.size = Some(size);
+        let risk = analyze_scan_result(&result);
+        SizedResult { result, risk }
+    }
+
+    fn over(profile: Option<&str>, limit: u64) -> SizeThreshold {
+        SizeThreshold {
+            profile: profile.map(str::to_string),
+            limit,
+        }
+    }
+
+    #[test]
+    fn test_tally_totals_by_profile_and_skips_protected() {
+        let tally = Tally::default();
+        tally.record(&sized("/code/a/node_modules", 300));
+        tally.record(&sized("/code/b/node_modules", 200));
+        tally.record(&sized("/code/c/__pycache__", 50));
+        tally.record(&sized("/usr/lib/node_modules", 10_000));
+
+        let totals = tally.totals();
+        assert_eq!((totals.count, totals.size), (3, 550));
+        assert_eq!(totals.by_profile.get("node"), Some(&500));
+        assert_eq!(totals.by_profile.get("python"), Some(&50));
+    }
+
+    #[test]
+    fn test_breaches() {
+        let tally = Tally::default();
+        tally.record(&sized("/code/a/node_modules", 3000));
+        tally.record(&sized("/code/c/__pycache__", 50));
+        let totals = tally.totals();
+
+        assert!(breaches(
+            &[over(None, 5000), over(Some("node"), 3000)],
+            Some(2),
+            &totals
+        )
+        .is_empty());
+        assert_eq!(
+            breaches(
+                &[
+                    over(None, 1000),
+                    over(Some("node"), 2000),
+                    over(Some("rust"), 0)
+                ],
+                Some(1),
+                &totals
+            ),
+            vec![
+                "total: 3.0 KiB reclaimable, over 1000 B".to_string(),
+                "node: 2.9 KiB reclaimable, over 2.0 KiB".to_string(),
+                "count: 2 folders, over 1".to_string(),
+            ]
+        );
+    }
+}
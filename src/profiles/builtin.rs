@@ -223,6 +223,17 @@ pub fn get_targets_for_profiles(profile_names: &[String]) -> Vec<String> {
     targets
 }
 
+/// Profiles that include `target`, by name (`target` alone is used by several)
+pub fn profiles_for_target(target: &str) -> Vec<&'static str> {
+    let mut names: Vec<&'static str> = PROFILES
+        .values()
+        .filter(|profile| profile.targets.contains(&target))
+        .map(|profile| profile.name)
+        .collect();
+    names.sort_unstable();
+    names
+}
+
 #[allow(dead_code)]
 pub fn list_profiles() -> Vec<&'static Profile> {
     let mut profiles: Vec<_> = PROFILES.values().collect();
@@ -0,0 +1,97 @@
+/// How much work it takes to get a folder back after deleting it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RebuildCost {
+    /// Regenerated on the fly by the next run (bytecode, lint and test caches)
+    Cheap,
+    /// Downloaded or installed again (dependencies, virtualenvs)
+    Moderate,
+    /// A full compile, an asset reimport, or data that may not come back
+    Expensive,
+}
+
+const CHEAP: &[&str] = &[
+    "__pycache__",
+    ".pytest_cache",
+    ".mypy_cache",
+    ".eslintcache",
+    ".parcel-cache",
+    ".jest",
+    ".cache",
+    ".sass-cache",
+    ".vite",
+    ".turbo",
+    ".nx",
+    ".next",
+    ".nuxt",
+    ".angular",
+    ".svelte-kit",
+    ".ipynb_checkpoints",
+    "TestResults",
+    "cover",
+    "Temp",
+    "nimcache",
+    ".serverless",
+    ".vercel",
+    ".netlify",
+];
+
+const EXPENSIVE: &[&str] = &[
+    "target",
+    "DerivedData",
+    "DerivedDataCache",
+    "Intermediate",
+    "Binaries",
+    "Library",
+    ".cxx",
+    "externalNativeBuild",
+    "CMakeFiles",
+    "cmake-build-debug",
+    "cmake-build-release",
+    "dist-newstyle",
+    ".stack-work",
+    "_build",
+    ".bloop",
+    ".metals",
+    ".gradle",
+    ".dvc",
+    ".mlruns",
+    "outputs",
+];
+
+/// Rebuild cost of a target folder; anything not listed (including project-config targets)
+/// counts as `Moderate`
+pub fn rebuild_cost(target: &str) -> RebuildCost {
+    if CHEAP.contains(&target) {
+        RebuildCost::Cheap
+    } else if EXPENSIVE.contains(&target) {
+        RebuildCost::Expensive
+    } else {
+        RebuildCost::Moderate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::get_targets_for_profiles;
+
+    #[test]
+    fn test_rebuild_costs() {
+        assert_eq!(rebuild_cost("__pycache__"), RebuildCost::Cheap);
+        assert_eq!(rebuild_cost("node_modules"), RebuildCost::Moderate);
+        assert_eq!(rebuild_cost("target"), RebuildCost::Expensive);
+        assert_eq!(rebuild_cost("fixtures"), RebuildCost::Moderate);
+        assert!(RebuildCost::Cheap < RebuildCost::Expensive);
+    }
+
+    #[test]
+    fn test_listed_targets_exist_in_profiles() {
+        let targets = get_targets_for_profiles(&["all".to_string()]);
+        for target in CHEAP.iter().chain(EXPENSIVE) {
+            assert!(
+                targets.iter().any(|t| t == target),
+                "{target} is in no profile"
+            );
+        }
+    }
+}
@@ -1,3 +1,5 @@
 mod builtin;
+mod cost;
 
-pub use builtin::{get_targets_for_profiles, PROFILES};
+pub use builtin::{get_targets_for_profiles, profiles_for_target, PROFILES};
+pub use cost::{rebuild_cost, RebuildCost};
@@ -1,284 +1,417 @@
 use std::path::Path;
 
+use crate::scanner::{ProjectMark, ScanResult};
+
 #[derive(Debug, Clone, Default)]
 pub struct RiskAnalysis {
     pub is_sensitive: bool,
     pub reason: Option<String>,
 }
 
-// Known applications that depend on node_modules (use path patterns)
-const SENSITIVE_APP_PATTERNS: &[(&str, &str)] = &[
-    // Desktop apps (Electron-based and others)
-    ("/visual studio code/", "Visual Studio Code"),
-    ("/vscode/", "Visual Studio Code"),
-    ("/code.app/", "Visual Studio Code"),
-    ("/discord/", "Discord"),
-    ("/discord.app/", "Discord"),
-    ("/slack/", "Slack"),
-    ("/slack.app/", "Slack"),
-    ("/atom/", "Atom"),
-    ("/postman/", "Postman"),
-    ("/figma/", "Figma"),
-    ("/notion/", "Notion"),
-    ("/obsidian/", "Obsidian"),
-    ("/spotify/", "Spotify"),
-    ("/microsoft teams/", "Microsoft Teams"),
-    ("/1password/", "1Password"),
-    ("/bitwarden/", "Bitwarden"),
+// Rules match whole path components, anchored to one of three boundaries:
+// - the filesystem root (or a Windows drive), for OS directories
+// - a home directory (the real one, or a conventional /home/<user>, /Users/<user>, /root)
+// - an app container (~/.config/<app>, ~/Library/Application Support/<app>, ...) or `.app` bundle
+// Unix names are case-sensitive except on macOS; macOS and Windows names are case-insensitive.
+
+/// Case-insensitive file systems by default
+const HOST_FOLDS_CASE: bool = cfg!(any(target_os = "macos", target_os = "windows"));
+
+/// Root-level Unix directories: (component, reason)
+const UNIX_SYSTEM_ROOTS: &[(&str, &str)] = &[
+    ("bin", "System binaries directory (system critical)"),
+    ("sbin", "System administration binaries (system critical)"),
+    ("lib", "System libraries (system critical)"),
+    ("lib32", "System libraries (32-bit) (system critical)"),
+    ("lib64", "System libraries (64-bit) (system critical)"),
+    ("etc", "System configuration directory (system critical)"),
+    ("boot", "Boot loader directory (system critical)"),
+    ("opt", "Optional packages directory (system critical)"),
+    ("srv", "Service data directory (system critical)"),
+    ("proc", "Process filesystem (virtual) (system critical)"),
+    ("sys", "System filesystem (virtual) (system critical)"),
+    ("dev", "Device files (virtual) (system critical)"),
+    ("run", "Runtime data (system critical)"),
+    ("snap", "Snap packages (system) (system critical)"),
+    ("flatpak", "Flatpak system directory"),
+    // All of /usr is protected (including /usr/local for safety)
+    ("usr", "System directory under /usr (protected)"),
+    // /var/www might hold user projects, but be conservative and protect all of /var
+    ("var", "System variable data directory"),
+];
+
+/// Root-level macOS directories (always case-insensitive): (component, reason)
+const MACOS_SYSTEM_ROOTS: &[(&str, &str)] = &[
+    ("System", "macOS system directory (SIP protected)"),
+    ("private", "macOS private system directory"),
+    ("Library", "macOS system Library directory"),
+    ("Applications", "macOS Applications directory"),
+];
+
+/// Drive-level Windows directories (always case-insensitive): (component, reason)
+const WINDOWS_SYSTEM_ROOTS: &[(&str, &str)] = &[
+    ("Windows", "Windows system directory"),
+    ("Program Files", "Windows Program Files directory"),
+    ("Program Files (x86)", "Windows Program Files directory"),
+    ("ProgramData", "Windows ProgramData directory"),
+    ("Recovery", "Windows Recovery partition"),
+    ("$Recycle.Bin", "Windows Recycle Bin"),
+    (
+        "System Volume Information",
+        "Windows System Volume Information",
+    ),
+];
+
+/// Known applications that ship `node_modules` etc. (matched case-insensitively by folder name)
+const KNOWN_APPS: &[(&str, &str)] = &[
+    ("Visual Studio Code", "Visual Studio Code"),
+    ("VSCode", "Visual Studio Code"),
+    ("Code", "Visual Studio Code"),
+    ("Discord", "Discord"),
+    ("Slack", "Slack"),
+    ("Atom", "Atom"),
+    ("Postman", "Postman"),
+    ("Figma", "Figma"),
+    ("Notion", "Notion"),
+    ("Obsidian", "Obsidian"),
+    ("Spotify", "Spotify"),
+    ("Microsoft Teams", "Microsoft Teams"),
+    ("1Password", "1Password"),
+    ("Bitwarden", "Bitwarden"),
+];
+
+/// Per-user directories that hold one folder per installed application
+const APP_CONTAINERS: &[&[&str]] = &[
+    &[".config"],
+    &[".local", "share"],
+    &["Library", "Application Support"],
+    &["AppData", "Roaming"],
+    &["AppData", "Local", "Programs"],
+    &["AppData", "Local"],
+];
+
+/// Tool directories directly under home: (dotfolder, tool)
+const HOME_TOOL_DIRS: &[(&str, &str)] = &[
     // Version managers
-    ("/.asdf/", "asdf version manager"),
-    ("/.nvm/", "Node Version Manager"),
-    ("/.pyenv/", "pyenv"),
-    ("/.rbenv/", "rbenv"),
-    ("/.volta/", "Volta"),
-    ("/.sdkman/", "SDKMAN"),
-    ("/.rustup/", "rustup"),
-    ("/.goenv/", "goenv"),
-    ("/.jabba/", "Jabba JDK manager"),
+    (".asdf", "asdf version manager"),
+    (".nvm", "Node Version Manager"),
+    (".pyenv", "pyenv"),
+    (".rbenv", "rbenv"),
+    (".volta", "Volta"),
+    (".sdkman", "SDKMAN"),
+    (".rustup", "rustup"),
+    (".goenv", "goenv"),
+    (".jabba", "Jabba JDK manager"),
     // Package managers and their caches
-    ("/.bun/", "Bun"),
-    ("/.cargo/", "Cargo"),
-    ("/.npm/", "npm"),
-    ("/.yarn/", "Yarn"),
-    ("/.pnpm/", "pnpm"),
-    ("/.composer/", "Composer"),
-    ("/.gem/", "RubyGems"),
-    ("/.gradle/", "Gradle"),
-    ("/.m2/", "Maven"),
-    ("/.nuget/", "NuGet"),
-    ("/.mix/", "Mix (Elixir)"),
-    ("/.hex/", "Hex (Elixir)"),
-    ("/.stack/", "Stack (Haskell)"),
-    ("/.cabal/", "Cabal (Haskell)"),
+    (".bun", "Bun"),
+    (".cargo", "Cargo"),
+    (".npm", "npm"),
+    (".yarn", "Yarn"),
+    (".pnpm", "pnpm"),
+    (".composer", "Composer"),
+    (".gem", "RubyGems"),
+    (".gradle", "Gradle"),
+    (".m2", "Maven"),
+    (".nuget", "NuGet"),
+    (".mix", "Mix (Elixir)"),
+    (".hex", "Hex (Elixir)"),
+    (".stack", "Stack (Haskell)"),
+    (".cabal", "Cabal (Haskell)"),
     // IDEs and editors
-    ("/.vscode/", "Visual Studio Code"),
-    ("/.vscode-server/", "Visual Studio Code Server"),
-    ("/.windsurf/", "Windsurf"),
-    ("/.cursor/", "Cursor"),
-    ("/.idea/", "JetBrains IDE"),
-    ("/.jetbrains/", "JetBrains"),
-    ("/.atom/", "Atom"),
-    ("/.sublime-text/", "Sublime Text"),
-    ("/.zed/", "Zed"),
+    (".vscode", "Visual Studio Code"),
+    (".vscode-server", "Visual Studio Code Server"),
+    (".windsurf", "Windsurf"),
+    (".cursor", "Cursor"),
+    (".idea", "JetBrains IDE"),
+    (".jetbrains", "JetBrains"),
+    (".atom", "Atom"),
+    (".sublime-text", "Sublime Text"),
+    (".zed", "Zed"),
     // Other tool directories
-    ("/.docker/", "Docker"),
-    ("/.kube/", "Kubernetes"),
-    ("/.aws/", "AWS CLI"),
-    ("/.azure/", "Azure CLI"),
-    ("/.gcloud/", "Google Cloud CLI"),
-    ("/.terraform.d/", "Terraform"),
-    ("/.pulumi/", "Pulumi"),
+    (".docker", "Docker"),
+    (".kube", "Kubernetes"),
+    (".aws", "AWS CLI"),
+    (".azure", "Azure CLI"),
+    (".gcloud", "Google Cloud CLI"),
+    (".terraform.d", "Terraform"),
+    (".pulumi", "Pulumi"),
 ];
 
+/// Dotfolder paths under home that hold user files rather than tool state
+const HOME_ALLOWED_DOTFOLDERS: &[&[&str]] = &[&[".local", "bin"]];
+
 pub fn analyze_risk(path: &Path) -> RiskAnalysis {
-    let path_str = path.to_string_lossy().to_lowercase();
+    let parts = PathParts::parse(&path.to_string_lossy());
+
+    let reason = check_system_paths(&parts)
+        .or_else(|| check_app_bundle(&parts))
+        .or_else(|| {
+            let home = parts.home_relative()?;
+            check_home_paths(home, parts.folds_case())
+        })
+        .or_else(|| check_root_home(&parts));
+
+    reason.map_or_else(RiskAnalysis::default, |reason| RiskAnalysis {
+        is_sensitive: true,
+        reason: Some(reason),
+    })
+}
 
-    // Check for OS-level system paths (Linux, macOS, Windows)
-    if let Some(reason) = check_system_paths(&path_str) {
-        return RiskAnalysis {
-            is_sensitive: true,
-            reason: Some(reason),
-        };
+/// Like [`analyze_risk`], but a project kept by its own config is always protected
+pub fn analyze_scan_result(result: &ScanResult) -> RiskAnalysis {
+    match &result.project_mark {
+        Some(ProjectMark::Kept { config, error }) => {
+            let reason = error.as_ref().map_or_else(
+                || format!("Kept by project config ({})", config.display()),
+                |error| {
+                    format!(
+                        "Kept by project config ({}): invalid config, {error}",
+                        config.display()
+                    )
+                },
+            );
+            RiskAnalysis {
+                is_sensitive: true,
+                reason: Some(reason),
+            }
+        }
+        _ => analyze_risk(&result.path),
     }
+}
 
-    // macOS ~/Library - check for /users/*/library pattern
-    if is_user_library_path(&path_str) {
-        return RiskAnalysis {
-            is_sensitive: true,
-            reason: Some("macOS user Library folder".to_string()),
-        };
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Anchor {
+    /// Unix root (`/`)
+    Root,
+    /// Windows drive (`C:`), including WSL/Cygwin mounts like `/mnt/c`
+    Drive,
+    /// Could not be made absolute
+    Relative,
+}
 
-    // Check for known sensitive applications using path patterns
-    for (pattern, app_name) in SENSITIVE_APP_PATTERNS {
-        if path_str.contains(*pattern) {
-            return RiskAnalysis {
-                is_sensitive: true,
-                reason: Some(format!("Part of {app_name}")),
+/// A path split on both `/` and `\` so Windows paths are understood on any host
+#[derive(Debug)]
+struct PathParts {
+    anchor: Anchor,
+    parts: Vec<String>,
+}
+
+impl PathParts {
+    fn parse(path_str: &str) -> Self {
+        let rooted = path_str.starts_with(['/', '\\']);
+        let mut parts: Vec<String> = Vec::new();
+        for part in path_str.split(['/', '\\']) {
+            match part {
+                "" | "." => {}
+                ".." => {
+                    parts.pop();
+                }
+                _ => parts.push(part.to_string()),
+            }
+        }
+
+        if is_drive(parts.first()) {
+            parts.remove(0);
+            return Self {
+                anchor: Anchor::Drive,
+                parts,
             };
         }
-    }
 
-    // Dotfolder rule: anything under ~/.<folder>/ is sensitive
-    // This catches unknown tools, configs, and caches
-    if let Some(reason) = check_dotfolder_rule(path) {
-        return RiskAnalysis {
-            is_sensitive: true,
-            reason: Some(reason),
-        };
+        if rooted {
+            // WSL (/mnt/c) and Cygwin (/cygdrive/c) expose Windows drives under the Unix root
+            let is_mount = parts.len() >= 2
+                && (parts[0] == "mnt" || parts[0] == "cygdrive")
+                && parts[1].len() == 1
+                && parts[1].chars().all(|c| c.is_ascii_alphabetic());
+            if is_mount {
+                parts.drain(..2);
+                return Self {
+                    anchor: Anchor::Drive,
+                    parts,
+                };
+            }
+            return Self {
+                anchor: Anchor::Root,
+                parts,
+            };
+        }
+
+        // Relative (e.g., `./project/node_modules` when scanning from `.`): resolve from cwd
+        match std::env::current_dir() {
+            Ok(cwd) if cwd.is_absolute() => {
+                let joined = format!("{}/{}", cwd.to_string_lossy(), path_str);
+                Self::parse(&joined)
+            }
+            _ => Self {
+                anchor: Anchor::Relative,
+                parts,
+            },
+        }
     }
 
-    // Check for common user application paths (fallback for non-home paths)
-    if path_str.contains("/.config/") || path_str.contains("/.local/share/") {
-        return RiskAnalysis {
-            is_sensitive: true,
-            reason: Some("User configuration or application data".to_string()),
-        };
+    /// Whether names under this path should be compared case-insensitively
+    fn folds_case(&self) -> bool {
+        HOST_FOLDS_CASE
+            || self.anchor == Anchor::Drive
+            || self
+                .parts
+                .first()
+                .is_some_and(|p| p.eq_ignore_ascii_case("Users"))
     }
 
-    RiskAnalysis::default()
-}
+    /// Components below the home directory that contains this path, if any
+    fn home_relative(&self) -> Option<&[String]> {
+        if self.anchor == Anchor::Relative {
+            return None;
+        }
 
-/// Check for OS-level system paths that should never be deleted
-/// Covers Linux (FHS), macOS (SIP-protected), and Windows system directories
-fn check_system_paths(path_str: &str) -> Option<String> {
-    // === macOS system paths (SIP-protected) ===
-    // /System, /usr, /bin, /sbin, /var, /private
-    if path_str.starts_with("/system/") || path_str.starts_with("/system") && path_str.len() == 7 {
-        return Some("macOS system directory (SIP protected)".to_string());
-    }
-    if path_str.starts_with("/private/") {
-        return Some("macOS private system directory".to_string());
-    }
-
-    // === Shared Unix paths (Linux + macOS) ===
-    // These are root-level system directories
-    let unix_system_paths = [
-        ("/bin/", "System binaries directory"),
-        ("/sbin/", "System administration binaries"),
-        ("/lib/", "System libraries"),
-        ("/lib64/", "System libraries (64-bit)"),
-        ("/etc/", "System configuration directory"),
-        ("/boot/", "Boot loader directory"),
-        ("/root/", "Root user home directory"),
-        ("/opt/", "Optional packages directory"),
-        ("/srv/", "Service data directory"),
-        ("/proc/", "Process filesystem (virtual)"),
-        ("/sys/", "System filesystem (virtual)"),
-        ("/dev/", "Device files (virtual)"),
-        ("/run/", "Runtime data"),
-        ("/snap/", "Snap packages (system)"),
-    ];
+        // The real home directory wins (it may live somewhere unconventional)
+        if let Some(home) = dirs::home_dir() {
+            let home = Self::parse(&home.to_string_lossy());
+            if home.anchor == self.anchor
+                && !home.parts.is_empty()
+                && starts_with(&self.parts, &home.parts, self.folds_case())
+            {
+                return Some(&self.parts[home.parts.len()..]);
+            }
+        }
 
-    for (prefix, reason) in unix_system_paths {
-        if path_str.starts_with(prefix) {
-            return Some(format!("{reason} (system critical)"));
+        // Conventional homes: /home/<user>, /Users/<user>, C:\Users\<user>, /root
+        let first = self.parts.first()?;
+        match self.anchor {
+            Anchor::Root if first == "root" => Some(&self.parts[1..]),
+            Anchor::Root if first == "home" || first.eq_ignore_ascii_case("Users") => {
+                self.parts.get(2..)
+            }
+            Anchor::Drive if first.eq_ignore_ascii_case("Users") => self.parts.get(2..),
+            _ => None,
         }
     }
+}
 
-    // /usr - all of it is protected (including /usr/local for safety)
-    if path_str.starts_with("/usr/") {
-        return Some("System directory under /usr (protected)".to_string());
-    }
+fn is_drive(part: Option<&String>) -> bool {
+    part.is_some_and(|p| {
+        let bytes = p.as_bytes();
+        bytes.len() == 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+    })
+}
 
-    // /var is tricky - /var/log, /var/lib are system, but /var/www might be user projects
-    // Be conservative and protect all of /var
-    if path_str.starts_with("/var/") {
-        return Some("System variable data directory".to_string());
+fn name_eq(a: &str, b: &str, fold_case: bool) -> bool {
+    if fold_case {
+        a.eq_ignore_ascii_case(b)
+    } else {
+        a == b
     }
+}
 
-    // === macOS specific ===
-    if path_str.contains("/applications/") {
-        return Some("macOS Applications directory".to_string());
-    }
-    // System /Library (not user ~/Library which is handled separately)
-    if path_str.starts_with("/library/") {
-        return Some("macOS system Library directory".to_string());
-    }
+fn starts_with<S: AsRef<str>>(parts: &[String], prefix: &[S], fold_case: bool) -> bool {
+    parts.len() >= prefix.len()
+        && parts
+            .iter()
+            .zip(prefix)
+            .all(|(a, b)| name_eq(a, b.as_ref(), fold_case))
+}
 
-    // === Windows system paths ===
-    // Case-insensitive matching (path_str is already lowercased)
-    if path_str.contains("\\windows\\") || path_str.contains("/windows/") {
-        return Some("Windows system directory".to_string());
-    }
-    if path_str.contains("\\system32") || path_str.contains("/system32") {
-        return Some("Windows System32 directory".to_string());
-    }
-    if path_str.contains("\\syswow64") || path_str.contains("/syswow64") {
-        return Some("Windows SysWOW64 directory".to_string());
-    }
-    if path_str.contains("program files") {
-        return Some("Windows Program Files directory".to_string());
-    }
-    if path_str.contains("\\programdata") || path_str.contains("/programdata") {
-        return Some("Windows ProgramData directory".to_string());
-    }
-    if path_str.contains("\\appdata\\") || path_str.contains("/appdata/") {
-        return Some("Windows AppData directory".to_string());
-    }
-    if path_str.contains("\\recovery") || path_str.contains("/recovery") {
-        // But not if it's in a project name
-        if path_str.starts_with("c:\\recovery")
-            || path_str.starts_with("d:\\recovery")
-            || path_str.contains(":\\recovery")
-        {
-            return Some("Windows Recovery partition".to_string());
+/// Check for OS-level system paths that should never be deleted
+/// Covers Linux (FHS), macOS (SIP-protected), and Windows system directories
+fn check_system_paths(parts: &PathParts) -> Option<String> {
+    let first = parts.parts.first()?;
+
+    let lookup = |rules: &[(&str, &str)], fold_case: bool| {
+        rules
+            .iter()
+            .find(|(name, _)| name_eq(first, name, fold_case))
+            .map(|(_, reason)| (*reason).to_string())
+    };
+
+    match parts.anchor {
+        Anchor::Root => {
+            lookup(MACOS_SYSTEM_ROOTS, true).or_else(|| lookup(UNIX_SYSTEM_ROOTS, HOST_FOLDS_CASE))
         }
+        Anchor::Drive => lookup(WINDOWS_SYSTEM_ROOTS, true),
+        Anchor::Relative => None,
     }
-    if path_str.contains("$recycle.bin") {
-        return Some("Windows Recycle Bin".to_string());
-    }
-    if path_str.contains("system volume information") {
-        return Some("Windows System Volume Information".to_string());
-    }
+}
 
-    // === Linux distribution specific ===
-    if path_str.starts_with("/snap/") {
-        return Some("Snap packages directory".to_string());
-    }
-    if path_str.starts_with("/flatpak/") {
-        return Some("Flatpak system directory".to_string());
-    }
+/// Everything under /root is off limits; checked after the home rules so their reasons win
+fn check_root_home(parts: &PathParts) -> Option<String> {
+    (parts.anchor == Anchor::Root && parts.parts.first()? == "root")
+        .then(|| "Root user home directory".to_string())
+}
+
+/// macOS `.app` bundles are a boundary wherever they live (e.g., ~/Downloads/Slack.app)
+fn check_app_bundle(parts: &PathParts) -> Option<String> {
+    let (idx, bundle) = parts.parts.iter().enumerate().find(|(_, p)| {
+        p.len() > 4
+            && p.get(p.len() - 4..)
+                .is_some_and(|ext| ext.eq_ignore_ascii_case(".app"))
+    })?;
+    let stem = &bundle[..bundle.len() - 4];
+
+    if let Some(app) = known_app(stem) {
+        return Some(format!("Part of {app}"));
+    }
+
+    // Unknown bundles only count when we're inside the bundle's Contents
+    parts
+        .parts
+        .get(idx + 1)
+        .filter(|next| next.as_str() == "Contents")
+        .map(|_| format!("Part of {bundle} app bundle"))
+}
 
-    None
+fn known_app(name: &str) -> Option<&'static str> {
+    KNOWN_APPS
+        .iter()
+        .find(|(dir, _)| dir.eq_ignore_ascii_case(name))
+        .map(|(_, app)| *app)
 }
 
-/// Check if path is inside ~/Library (macOS user Library)
-fn is_user_library_path(path_str: &str) -> bool {
-    // Match patterns like /users/username/library or /home/username/library
-    // The path is already lowercased
-    let parts: Vec<&str> = path_str.split('/').collect();
-
-    for (i, part) in parts.iter().enumerate() {
-        if *part == "library" {
-            // Check if this is a user's Library (preceded by a username directory)
-            // Pattern: /users/<username>/library or /home/<username>/library
-            if i >= 2 && (parts[i - 2] == "users" || parts[i - 2] == "home") {
-                return true;
+/// Rules for paths under a home directory (`home` holds the components below it)
+fn check_home_paths(home: &[String], fold_case: bool) -> Option<String> {
+    // Known apps in per-user app containers (~/.config/discord, ~/Library/Application Support/Slack)
+    for container in APP_CONTAINERS {
+        if starts_with(home, container, fold_case) {
+            if let Some(app) = home.get(container.len()).and_then(|name| known_app(name)) {
+                return Some(format!("Part of {app}"));
             }
         }
     }
-    false
-}
 
-/// Dotfolder rule: paths inside ~/.<dotfolder>/ are sensitive
-/// This catches tool installations, caches, and configs we haven't explicitly listed
-fn check_dotfolder_rule(path: &Path) -> Option<String> {
-    // Get home directory
-    let home = dirs::home_dir()?;
-    let home_str = home.to_string_lossy().to_lowercase();
-
-    let path_str = path.to_string_lossy().to_lowercase();
-
-    // Check if path is under home directory
-    let relative = path_str.strip_prefix(&home_str)?;
-
-    // Look for pattern: /.<dotfolder>/...
-    // The relative path starts with / so we check for /.<name>/
-    let parts: Vec<&str> = relative.split('/').collect();
-
-    // parts[0] is empty (leading /), parts[1] would be the first component
-    if parts.len() >= 2 {
-        let first_component = parts[1];
-        if first_component.starts_with('.') && !first_component.is_empty() {
-            // It's a dotfolder directly under home
-            // Special case: allow certain dotfolders that are typically user projects
-            let allowed_dotfolders = [".local/bin"]; // Could add more exceptions
-
-            for allowed in &allowed_dotfolders {
-                if relative.contains(allowed) {
-                    return None;
-                }
-            }
+    let first = home.first()?;
 
-            return Some(format!(
-                "Inside ~/.{} (tool/config directory)",
-                first_component.trim_start_matches('.')
-            ));
-        }
+    if name_eq(first, "Library", true) {
+        return Some("macOS user Library folder".to_string());
+    }
+    if name_eq(first, "Applications", true) {
+        return Some("macOS Applications directory".to_string());
+    }
+    if name_eq(first, "AppData", true) {
+        return Some("Windows AppData directory".to_string());
     }
 
-    None
+    // Dotfolder rule: anything under ~/.<folder>/ is sensitive
+    // This catches unknown tools, configs, and caches
+    if !first.starts_with('.') {
+        return None;
+    }
+    if HOME_ALLOWED_DOTFOLDERS
+        .iter()
+        .any(|allowed| starts_with(home, allowed, fold_case))
+    {
+        return None;
+    }
+    if let Some((_, tool)) = HOME_TOOL_DIRS
+        .iter()
+        .find(|(dir, _)| name_eq(first, dir, fold_case))
+    {
+        return Some(format!("Part of {tool}"));
+    }
+    Some(format!(
+        "Inside ~/.{} (tool/config directory)",
+        first.trim_start_matches('.')
+    ))
 }
 
 #[cfg(test)]
@@ -286,6 +419,43 @@ mod tests {
     use super::*;
     use std::path::PathBuf;
 
+    // === Project configs ===
+
+    #[test]
+    fn test_kept_project_is_sensitive() {
+        let mut result = ScanResult::new(PathBuf::from("/home/user/projects/app/node_modules"));
+        assert!(!analyze_scan_result(&result).is_sensitive);
+
+        result.project_mark = Some(ProjectMark::Kept {
+            config: PathBuf::from("/home/user/projects/app/.cache-sweep-keep"),
+            error: None,
+        });
+        let risk = analyze_scan_result(&result);
+        assert!(risk.is_sensitive);
+        assert!(risk.reason.unwrap().contains(".cache-sweep-keep"));
+    }
+
+    #[test]
+    fn test_invalid_project_config_reason_includes_error() {
+        let mut result = ScanResult::new(PathBuf::from("/home/user/projects/app/target"));
+        result.project_mark = Some(ProjectMark::Kept {
+            config: PathBuf::from("/home/user/projects/app/.cache-sweep.toml"),
+            error: Some("unknown field `exclud`".to_string()),
+        });
+        let reason = analyze_scan_result(&result).reason.unwrap();
+        assert!(reason.contains("invalid config"));
+        assert!(reason.contains("exclud"));
+    }
+
+    #[test]
+    fn test_added_target_uses_path_rules() {
+        let mut result = ScanResult::new(PathBuf::from("/home/user/projects/app/fixtures"));
+        result.project_mark = Some(ProjectMark::Added {
+            config: PathBuf::from("/home/user/projects/app/.cache-sweep.toml"),
+        });
+        assert!(!analyze_scan_result(&result).is_sensitive);
+    }
+
     // === System paths (OS-level) ===
 
     #[test]
@@ -336,6 +506,14 @@ mod tests {
         assert!(result.reason.unwrap().contains("/usr"));
     }
 
+    #[test]
+    fn test_system_path_root_home() {
+        let path = PathBuf::from("/root/projects/app/node_modules");
+        let result = analyze_risk(&path);
+        assert!(result.is_sensitive);
+        assert!(result.reason.unwrap().contains("Root user home"));
+    }
+
     #[test]
     fn test_system_path_etc() {
         let path = PathBuf::from("/etc/nginx/node_modules");
@@ -614,26 +792,177 @@ mod tests {
         assert!(!result.is_sensitive);
     }
 
-    // === Helper function tests ===
+    // === User Library ===
+
+    fn is_user_library(path: &str) -> bool {
+        analyze_risk(Path::new(path)).reason.as_deref() == Some("macOS user Library folder")
+    }
 
     #[test]
-    fn test_is_user_library_path_macos() {
-        assert!(is_user_library_path("/users/apozo/library/caches"));
-        assert!(is_user_library_path(
-            "/users/john/library/application support"
-        ));
+    fn test_user_library_lowercase_macos() {
+        assert!(is_user_library("/users/apozo/library/caches"));
+        assert!(is_user_library("/users/john/library/application support"));
     }
 
     #[test]
-    fn test_is_user_library_path_linux() {
-        assert!(is_user_library_path("/home/user/library/something"));
+    fn test_user_library_under_linux_home() {
+        assert!(is_user_library("/home/user/library/something"));
     }
 
     #[test]
-    fn test_is_user_library_path_not_user() {
-        // System Library should not match
-        assert!(!is_user_library_path("/library/something"));
+    fn test_user_library_needs_a_home() {
+        // System Library is protected, but as the system one
+        assert!(!is_user_library("/library/something"));
         // Library in project name should not match
-        assert!(!is_user_library_path("/users/dev/projects/library/src"));
+        assert!(!analyze_risk(Path::new("/users/dev/projects/library/src")).is_sensitive);
+    }
+
+    // === Regression corpus ===
+    // Paths the old substring matcher got wrong. Each entry documents why.
+
+    /// Must NOT be sensitive (previously flagged by a substring hit)
+    const FALSE_POSITIVE_CORPUS: &[&str] = &[
+        // Project named after an app ("/slack/", "/atom/", "/discord/" substrings)
+        "/home/user/work/slack/node_modules",
+        "/home/user/dev/atom/node_modules",
+        "/Users/dev/code/discord/bot/node_modules",
+        // Project folders named like OS directories
+        "/home/user/code/flutter_app/windows/build",
+        "/home/user/src/applications/web/node_modules",
+        "/home/user/code/recovery-tool/node_modules",
+        "/home/user/code/recovery/node_modules",
+        "/Users/dev/projects/program files/app/node_modules",
+        "/home/user/projects/appdata/node_modules",
+        "/home/user/code/system32-emulator/node_modules",
+        "/home/user/code/dotnet/windows/target",
+        // Project-local tool folders that only matter under home
+        "/home/user/projects/my-app/.cargo/registry",
+        "/home/user/projects/app/.yarn/cache",
+        "/home/user/projects/app/.vscode/node_modules",
+        "/home/user/projects/app/.config/cache",
+        "/home/user/projects/app/.local/share/data",
+        "/Users/dev/projects/site/.idea/libraries",
+        // Library folder inside a project, not ~/Library
+        "/Users/dev/projects/library/node_modules",
+        // .app suffix that isn't a bundle
+        "/home/user/projects/todo.app/node_modules",
+    ];
+
+    /// Must be sensitive (previously missed, or only matched by accident)
+    const FALSE_NEGATIVE_CORPUS: &[&str] = &[
+        // Root directories without a trailing component
+        "/usr",
+        "/etc",
+        "/private",
+        "/System",
+        // Windows paths written with forward slashes or on other drives
+        "C:/Recovery/node_modules",
+        "D:/Program Files (x86)/App/node_modules",
+        "E:/Users/dev/AppData/Roaming/App/node_modules",
+        // Windows drives mounted under WSL / Cygwin
+        "/mnt/c/Windows/System32/node_modules",
+        "/mnt/c/Program Files/App/resources/node_modules",
+        "/cygdrive/d/ProgramData/App/cache",
+        // App bundles outside /Applications
+        "/Users/dev/Downloads/Slack.app/Contents/Resources/app/node_modules",
+        "/Users/dev/Downloads/SomeTool.app/Contents/Resources/node_modules",
+        // Known apps in per-user app containers
+        "/Users/dev/Library/Application Support/Code/node_modules",
+        "/home/user/.config/Code/node_modules",
+        // Other users' homes
+        "/root/.cache/pip",
+        "/home/other/.nvm/versions/node/v20.0.0/lib/node_modules",
+        // User config and app data: protected under any home, not as loose substrings
+        "/home/other/.config/app/node_modules",
+        "/home/other/.local/share/app/node_modules",
+        "/Users/other/.config/app/cache",
+        // Windows system folders at the root of any drive
+        "C:/Windows/System32/node_modules",
+        "D:/Windows/SysWOW64/cache",
+        "/mnt/d/Windows/System32/drivers",
+    ];
+
+    #[test]
+    fn test_false_positive_corpus() {
+        let flagged: Vec<_> = FALSE_POSITIVE_CORPUS
+            .iter()
+            .filter_map(|p| {
+                let risk = analyze_risk(Path::new(p));
+                risk.is_sensitive
+                    .then(|| format!("{p} ({:?})", risk.reason))
+            })
+            .collect();
+        assert!(flagged.is_empty(), "wrongly flagged: {flagged:#?}");
+    }
+
+    #[test]
+    fn test_false_negative_corpus() {
+        let missed: Vec<_> = FALSE_NEGATIVE_CORPUS
+            .iter()
+            .filter(|p| !analyze_risk(Path::new(p)).is_sensitive)
+            .collect();
+        assert!(missed.is_empty(), "wrongly allowed: {missed:#?}");
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_linux_system_paths_are_case_sensitive() {
+        // /ETC is not /etc on Linux, so it's just a user folder
+        assert!(!analyze_risk(Path::new("/ETC/node_modules")).is_sensitive);
+        assert!(!analyze_risk(Path::new("/Usr/project/node_modules")).is_sensitive);
+    }
+
+    #[test]
+    fn test_known_app_in_config_container() {
+        let result = analyze_risk(Path::new("/home/user/.config/discord/node_modules"));
+        assert!(result.is_sensitive);
+        assert!(result.reason.unwrap().contains("Discord"));
+    }
+
+    #[test]
+    fn test_app_bundle_outside_applications() {
+        let result = analyze_risk(Path::new(
+            "/Users/dev/Downloads/Slack.app/Contents/node_modules",
+        ));
+        assert!(result.is_sensitive);
+        assert!(result.reason.unwrap().contains("Slack"));
+    }
+
+    #[test]
+    fn test_local_bin_allowed() {
+        let result = analyze_risk(Path::new("/home/user/.local/bin/node_modules"));
+        assert!(!result.is_sensitive);
+    }
+
+    #[test]
+    fn test_relative_path_resolved_from_cwd() {
+        let cwd = std::env::current_dir().unwrap();
+        let absolute = analyze_risk(&cwd.join("project/node_modules"));
+        let relative = analyze_risk(Path::new("./project/node_modules"));
+        assert_eq!(absolute.is_sensitive, relative.is_sensitive);
+        assert_eq!(absolute.reason, relative.reason);
+    }
+
+    // === PathParts tests ===
+
+    #[test]
+    fn test_path_parts_windows_drive() {
+        let parts = PathParts::parse("C:\\Users\\dev\\AppData");
+        assert_eq!(parts.anchor, Anchor::Drive);
+        assert_eq!(parts.parts, vec!["Users", "dev", "AppData"]);
+        assert_eq!(parts.home_relative().unwrap(), ["AppData".to_string()]);
+    }
+
+    #[test]
+    fn test_path_parts_wsl_mount() {
+        let parts = PathParts::parse("/mnt/c/Windows");
+        assert_eq!(parts.anchor, Anchor::Drive);
+        assert_eq!(parts.parts, vec!["Windows"]);
+    }
+
+    #[test]
+    fn test_path_parts_resolves_dot_dot() {
+        let parts = PathParts::parse("/home/user/projects/../.cargo/bin");
+        assert_eq!(parts.parts, vec!["home", "user", ".cargo", "bin"]);
     }
 }
@@ -1,3 +1,3 @@
 mod analysis;
 
-pub use analysis::{analyze_risk, RiskAnalysis};
+pub use analysis::{analyze_scan_result, RiskAnalysis};
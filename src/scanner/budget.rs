@@ -0,0 +1,194 @@
+use std::time::{Duration, SystemTime};
+
+use super::ScanResult;
+use crate::profiles::{rebuild_cost, RebuildCost};
+
+/// A folder the space planner may pick
+#[derive(Debug, Clone, Copy)]
+pub struct BudgetItem {
+    pub size: u64,
+    /// Time since anything inside changed (`None` if unknown)
+    pub idle: Option<Duration>,
+    pub cost: RebuildCost,
+    /// Only picked once every other folder is used up (e.g., a build may be writing to it)
+    pub risky: bool,
+}
+
+impl BudgetItem {
+    /// A sized scan result, idle since its newest change as of `now`
+    pub fn for_result(result: &ScanResult, risky: bool, now: SystemTime) -> Self {
+        let target = result
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        Self {
+            size: result.size.unwrap_or(0),
+            idle: result
+                .modified
+                .max(result.newest_modified)
+                .map(|time| now.duration_since(time).unwrap_or(Duration::ZERO)),
+            cost: rebuild_cost(&target),
+            risky,
+        }
+    }
+
+    /// Higher is better to delete: stale folders first, discounted by how hard they are to rebuild
+    fn score(&self) -> u64 {
+        let weight = match self.cost {
+            RebuildCost::Cheap => 1,
+            RebuildCost::Moderate => 2,
+            RebuildCost::Expensive => 4,
+        };
+        self.idle.unwrap_or(Duration::ZERO).as_secs() / weight
+    }
+}
+
+/// Folders picked to free `target` bytes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BudgetPlan {
+    pub target: u64,
+    /// Picked items (indices into the planner's input) with the running total after each
+    pub steps: Vec<(usize, u64)>,
+}
+
+impl BudgetPlan {
+    pub fn total(&self) -> u64 {
+        self.steps.last().map_or(0, |&(_, total)| total)
+    }
+
+    pub fn reached(&self) -> bool {
+        self.total() >= self.target
+    }
+
+    pub fn indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.steps.iter().map(|&(index, _)| index)
+    }
+}
+
+/// Picks folders until `target` bytes would be freed, best candidates first, then drops
+/// any pick the target doesn't need. Picks everything if the target can't be reached.
+pub fn plan_to_free(items: &[BudgetItem], target: u64) -> BudgetPlan {
+    let mut order: Vec<usize> = (0..items.len()).filter(|&i| items[i].size > 0).collect();
+    order.sort_by(|&a, &b| {
+        let (x, y) = (&items[a], &items[b]);
+        x.risky
+            .cmp(&y.risky)
+            .then(y.score().cmp(&x.score()))
+            .then(y.size.cmp(&x.size))
+            .then(a.cmp(&b))
+    });
+
+    let mut picked = Vec::new();
+    let mut total = 0u64;
+    for index in order {
+        if total >= target {
+            break;
+        }
+        picked.push(index);
+        total += items[index].size;
+    }
+    // A big late pick can make earlier small ones unnecessary; drop the least preferred
+    for position in (0..picked.len()).rev() {
+        let size = items[picked[position]].size;
+        if total - size >= target {
+            total -= size;
+            picked.remove(position);
+        }
+    }
+
+    let mut running = 0;
+    let steps = picked
+        .into_iter()
+        .map(|index| {
+            running += items[index].size;
+            (index, running)
+        })
+        .collect();
+    BudgetPlan { target, steps }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn item(size: u64, idle_days: u64, cost: RebuildCost) -> BudgetItem {
+        BudgetItem {
+            size,
+            idle: Some(Duration::from_secs(idle_days * DAY)),
+            cost,
+            risky: false,
+        }
+    }
+
+    #[test]
+    fn test_prefers_stale_and_cheap() {
+        let items = [
+            item(100, 10, RebuildCost::Moderate),
+            item(100, 200, RebuildCost::Moderate),
+            item(100, 200, RebuildCost::Expensive),
+            item(100, 150, RebuildCost::Cheap),
+        ];
+        let plan = plan_to_free(&items, 200);
+
+        assert_eq!(plan.steps, vec![(3, 100), (1, 200)]);
+        assert!(plan.reached());
+    }
+
+    #[test]
+    fn test_drops_picks_made_unnecessary() {
+        let items = [
+            item(10, 300, RebuildCost::Cheap),
+            item(20, 200, RebuildCost::Cheap),
+            item(1000, 100, RebuildCost::Cheap),
+        ];
+        let plan = plan_to_free(&items, 500);
+
+        assert_eq!(plan.indices().collect::<Vec<_>>(), vec![2]);
+        assert_eq!(plan.total(), 1000);
+    }
+
+    #[test]
+    fn test_risky_items_come_last() {
+        let mut busy = item(500, 400, RebuildCost::Cheap);
+        busy.risky = true;
+        let items = [busy, item(100, 1, RebuildCost::Expensive)];
+
+        assert_eq!(plan_to_free(&items, 100).steps, vec![(1, 100)]);
+        // Once the risky folder is needed, the small one no longer is
+        assert_eq!(plan_to_free(&items, 300).steps, vec![(0, 500)]);
+    }
+
+    #[test]
+    fn test_item_for_result_uses_target_and_newest_change() {
+        let now = SystemTime::now();
+        let mut result = ScanResult::new("/nonexistent/app/target".into());
+        result.size = Some(300);
+        result.modified = Some(now - Duration::from_secs(90 * DAY));
+        result.newest_modified = Some(now - Duration::from_secs(3 * DAY));
+
+        let item = BudgetItem::for_result(&result, true, now);
+
+        assert_eq!(item.size, 300);
+        assert_eq!(item.idle, Some(Duration::from_secs(3 * DAY)));
+        assert_eq!(item.cost, RebuildCost::Expensive);
+        assert!(item.risky);
+    }
+
+    #[test]
+    fn test_unreachable_target_picks_everything() {
+        let items = [
+            item(100, 1, RebuildCost::Cheap),
+            item(0, 1, RebuildCost::Cheap),
+            item(50, 1, RebuildCost::Cheap),
+        ];
+        let plan = plan_to_free(&items, 1000);
+
+        assert_eq!(plan.total(), 150);
+        assert_eq!(plan.steps.len(), 2);
+        assert!(!plan.reached());
+        assert!(plan_to_free(&items, 0).steps.is_empty());
+    }
+}
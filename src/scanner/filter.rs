@@ -0,0 +1,221 @@
+use std::time::{Duration, SystemTime};
+
+use bytesize::ByteSize;
+
+use super::ScanResult;
+
+const MINUTE: u64 = 60;
+const HOUR: u64 = 60 * MINUTE;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+const MONTH: u64 = 30 * DAY;
+const YEAR: u64 = 365 * DAY;
+
+/// Size and age bounds a result must satisfy to be shown (all optional)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResultFilter {
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    /// Nothing inside changed for at least this long
+    pub older_than: Option<Duration>,
+    /// Something inside changed within this long
+    pub newer_than: Option<Duration>,
+}
+
+impl ResultFilter {
+    pub const fn is_empty(&self) -> bool {
+        self.min_size.is_none()
+            && self.max_size.is_none()
+            && self.older_than.is_none()
+            && self.newer_than.is_none()
+    }
+
+    /// Results whose size or age isn't known yet never match a bound on it
+    pub fn matches(&self, result: &ScanResult, now: SystemTime) -> bool {
+        let size_ok = |bound: Option<u64>, ok: fn(u64, u64) -> bool| {
+            bound.is_none_or(|bound| result.size.is_some_and(|size| ok(size, bound)))
+        };
+        let idle = result
+            .modified
+            .max(result.newest_modified)
+            .map(|time| now.duration_since(time).unwrap_or(Duration::ZERO));
+        let idle_ok = |bound: Option<Duration>, ok: fn(Duration, Duration) -> bool| {
+            bound.is_none_or(|bound| idle.is_some_and(|idle| ok(idle, bound)))
+        };
+
+        size_ok(self.min_size, |size, min| size >= min)
+            && size_ok(self.max_size, |size, max| size <= max)
+            && idle_ok(self.older_than, |idle, age| idle >= age)
+            && idle_ok(self.newer_than, |idle, age| idle < age)
+    }
+
+    /// Short summary for the TUI header (e.g., "min 100.0 MiB, older than 3mo")
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(size) = self.min_size {
+            parts.push(format!("min {}", ByteSize::b(size)));
+        }
+        if let Some(size) = self.max_size {
+            parts.push(format!("max {}", ByteSize::b(size)));
+        }
+        if let Some(age) = self.older_than {
+            parts.push(format!("older than {}", format_age(age)));
+        }
+        if let Some(age) = self.newer_than {
+            parts.push(format!("newer than {}", format_age(age)));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Parses a human size like `500MB`, `1.5G` or `200KiB`
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    value
+        .trim()
+        .parse::<ByteSize>()
+        .map(|size| size.as_u64())
+        .map_err(|_| format!("invalid size {value:?} (expected e.g. 500MB, 1.5G, 200KiB)"))
+}
+
+/// Parses a human age like `90d`, `3mo` or `12h` (a month is 30 days, a year 365)
+pub fn parse_age(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid age {value:?} (expected e.g. 12h, 90d, 2w, 3mo, 1y)");
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+    let unit = match unit.trim().to_ascii_lowercase().as_str() {
+        "s" | "sec" | "secs" => 1,
+        "m" | "min" | "mins" => MINUTE,
+        "h" | "hr" | "hrs" | "hour" | "hours" => HOUR,
+        "d" | "day" | "days" => DAY,
+        "w" | "week" | "weeks" => WEEK,
+        "mo" | "month" | "months" => MONTH,
+        "y" | "year" | "years" => YEAR,
+        _ => return Err(invalid()),
+    };
+    number
+        .checked_mul(unit)
+        .map(Duration::from_secs)
+        .ok_or_else(invalid)
+}
+
+/// Formats an age in the largest unit that divides it evenly (inverse of `parse_age`)
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    let units = [
+        (YEAR, "y"),
+        (MONTH, "mo"),
+        (WEEK, "w"),
+        (DAY, "d"),
+        (HOUR, "h"),
+        (MINUTE, "m"),
+    ];
+    units
+        .iter()
+        .find(|(unit, _)| secs > 0 && secs.is_multiple_of(*unit))
+        .map_or_else(
+            || format!("{secs}s"),
+            |(unit, suffix)| format!("{}{suffix}", secs / unit),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn result(size: Option<u64>, idle: Option<Duration>, now: SystemTime) -> ScanResult {
+        let mut result = ScanResult::new(PathBuf::from("/code/app/node_modules"));
+        result.size = size;
+        result.modified = idle.map(|idle| now - idle);
+        result
+    }
+
+    #[test]
+    fn test_parse_size_units() {
+        assert_eq!(parse_size("500MB"), Ok(500_000_000));
+        assert_eq!(parse_size("1.5G"), Ok(1_500_000_000));
+        assert_eq!(parse_size("2KiB"), Ok(2048));
+        assert_eq!(parse_size("100"), Ok(100));
+        assert!(parse_size("lots").is_err());
+    }
+
+    #[test]
+    fn test_parse_age_units() {
+        assert_eq!(parse_age("90d"), Ok(Duration::from_secs(90 * DAY)));
+        assert_eq!(parse_age("3mo"), Ok(Duration::from_secs(3 * MONTH)));
+        assert_eq!(parse_age("12h"), Ok(Duration::from_hours(12)));
+        assert_eq!(parse_age("2 weeks"), Ok(Duration::from_secs(2 * WEEK)));
+        assert_eq!(parse_age("1y"), Ok(Duration::from_secs(YEAR)));
+        assert!(parse_age("90").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("3 fortnights").is_err());
+    }
+
+    #[test]
+    fn test_format_age_round_trips() {
+        for age in ["45d", "3mo", "2w", "1y", "12h", "45m", "7s"] {
+            assert_eq!(format_age(parse_age(age).unwrap()), age);
+        }
+    }
+
+    #[test]
+    fn test_empty_filter_matches_everything() {
+        let now = SystemTime::now();
+        let filter = ResultFilter::default();
+        assert!(filter.is_empty());
+        assert!(filter.matches(&result(None, None, now), now));
+    }
+
+    #[test]
+    fn test_size_bounds_are_inclusive() {
+        let now = SystemTime::now();
+        let filter = ResultFilter {
+            min_size: Some(100),
+            max_size: Some(200),
+            ..ResultFilter::default()
+        };
+        assert!(filter.matches(&result(Some(100), None, now), now));
+        assert!(filter.matches(&result(Some(200), None, now), now));
+        assert!(!filter.matches(&result(Some(99), None, now), now));
+        assert!(!filter.matches(&result(Some(201), None, now), now));
+        // Not sized yet
+        assert!(!filter.matches(&result(None, None, now), now));
+    }
+
+    #[test]
+    fn test_age_bounds_use_last_activity() {
+        let now = SystemTime::now();
+        let older = ResultFilter {
+            older_than: Some(Duration::from_secs(90 * DAY)),
+            ..ResultFilter::default()
+        };
+        let stale = result(Some(1), Some(Duration::from_secs(100 * DAY)), now);
+        assert!(older.matches(&stale, now));
+
+        // A file changed inside the folder yesterday makes it recent
+        let mut touched = stale.clone();
+        touched.newest_modified = Some(now - Duration::from_secs(DAY));
+        assert!(!older.matches(&touched, now));
+
+        let newer = ResultFilter {
+            newer_than: Some(Duration::from_secs(WEEK)),
+            ..ResultFilter::default()
+        };
+        assert!(newer.matches(&touched, now));
+        assert!(!newer.matches(&stale, now));
+    }
+
+    #[test]
+    fn test_describe_lists_active_bounds() {
+        let filter = ResultFilter {
+            min_size: Some(100 * 1024 * 1024),
+            older_than: Some(Duration::from_secs(3 * MONTH)),
+            ..ResultFilter::default()
+        };
+        assert_eq!(filter.describe(), "min 100.0 MiB, older than 3mo");
+    }
+}
@@ -1,6 +1,14 @@
 mod batcher;
+mod budget;
+mod filter;
+mod project;
+mod query;
 mod size;
 mod walker;
 
-pub use size::calculate_size;
-pub use walker::{start_scan, ScanResult};
+pub use budget::{plan_to_free, BudgetItem, BudgetPlan};
+pub use filter::{parse_age, parse_size, ResultFilter};
+pub use project::{project_name, ProjectConfigs, ProjectMark};
+pub use query::Query;
+pub use size::{calculate_size, DirStats};
+pub use walker::{start_scan, ScanErrors, ScanResult};
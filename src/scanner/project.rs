@@ -0,0 +1,478 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use serde::Deserialize;
+
+/// Project config file, read from a project's root directory
+pub const CONFIG_FILE: &str = ".cache-sweep.toml";
+/// Bare marker file: same as a config with `keep = true`
+pub const KEEP_MARKER: &str = ".cache-sweep-keep";
+
+/// Per-project settings from `.cache-sweep.toml`.
+///
+/// ```toml
+/// keep = false                          # true = never clean anything in this project
+/// targets = ["fixtures", "models/cache"] # extra folders to report (relative to project root)
+/// exclude = ["docs/node_modules"]       # subpaths never reported (relative to project root)
+/// ```
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    pub keep: bool,
+    pub targets: Vec<String>,
+    pub exclude: Vec<String>,
+    /// Parse error, if the file couldn't be read (such projects are kept to be safe)
+    #[serde(skip)]
+    pub error: Option<String>,
+}
+
+impl ProjectConfig {
+    /// Loads the config for `dir`, if it has a config file or keep marker.
+    pub fn load(dir: &Path) -> Option<Self> {
+        let config_path = dir.join(CONFIG_FILE);
+        match std::fs::read_to_string(&config_path) {
+            Ok(content) => return Some(Self::parse(&content)),
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Some(Self::invalid(e.to_string()));
+            }
+            Err(_) => {}
+        }
+
+        dir.join(KEEP_MARKER).exists().then(|| Self {
+            keep: true,
+            ..Self::default()
+        })
+    }
+
+    pub fn parse(content: &str) -> Self {
+        toml::from_str(content)
+            .unwrap_or_else(|e: toml::de::Error| Self::invalid(e.message().to_string()))
+    }
+
+    fn invalid(error: String) -> Self {
+        Self {
+            keep: true,
+            error: Some(error),
+            ..Self::default()
+        }
+    }
+
+    fn lists(entries: &[String], relative: &Path) -> bool {
+        entries
+            .iter()
+            .any(|entry| Path::new(entry.trim_end_matches(['/', '\\'])) == relative)
+    }
+}
+
+/// Why a result's handling was changed by a project config
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProjectMark {
+    /// The project is marked never-clean by this config or keep marker
+    Kept {
+        config: PathBuf,
+        /// Set when the config was unreadable and the project is kept to be safe
+        error: Option<String>,
+    },
+    /// Not a global target; reported because this project config lists it
+    Added { config: PathBuf },
+}
+
+/// What the project configs above a directory say about it
+#[derive(Debug, Default)]
+pub struct ProjectLookup {
+    pub excluded: bool,
+    pub mark: Option<ProjectMark>,
+}
+
+/// Project configs discovered during a walk, keyed by absolute project root.
+///
+/// The walker loads a directory's config before descending into it, so every
+/// descendant sees the configs of all its ancestors.
+#[derive(Debug)]
+pub struct ProjectConfigs {
+    root: PathBuf,
+    absolute_root: PathBuf,
+    configs: RwLock<HashMap<PathBuf, Arc<ProjectConfig>>>,
+}
+
+impl ProjectConfigs {
+    /// Creates a registry for a scan of `root`, preloaded with configs above
+    /// it so scanning a subfolder of a configured project still honors them.
+    pub fn for_root(root: &Path) -> Self {
+        let absolute_root = std::path::absolute(root).unwrap_or_else(|_| root.to_path_buf());
+        let configs = Self {
+            root: root.to_path_buf(),
+            absolute_root,
+            configs: RwLock::default(),
+        };
+        for ancestor in configs.absolute_root.ancestors().skip(1) {
+            if let Some(config) = ProjectConfig::load(ancestor) {
+                configs.insert(ancestor.to_path_buf(), config);
+            }
+        }
+        configs
+    }
+
+    /// Walker paths are relative to the scan root as given (e.g., `./app/node_modules`)
+    fn absolute(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(&self.root).map_or_else(
+            |_| path.to_path_buf(),
+            |relative| self.absolute_root.join(relative),
+        )
+    }
+
+    /// Records `dir`'s config if it has one (call before descending into `dir`)
+    pub fn load(&self, dir: &Path) {
+        if let Some(config) = ProjectConfig::load(dir) {
+            self.insert(self.absolute(dir), config);
+        }
+    }
+
+    fn insert(&self, dir: PathBuf, config: ProjectConfig) {
+        self.configs
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .insert(dir, Arc::new(config));
+    }
+
+    /// Applies every config above `path` (nearest first)
+    pub fn lookup(&self, path: &Path) -> ProjectLookup {
+        let path = self.absolute(path);
+        let configs = self
+            .configs
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let mut lookup = ProjectLookup::default();
+        if configs.is_empty() {
+            return lookup;
+        }
+
+        let mut added = None;
+        for root in path.ancestors().skip(1) {
+            let Some(config) = configs.get(root) else {
+                continue;
+            };
+            let Ok(relative) = path.strip_prefix(root) else {
+                continue;
+            };
+
+            if ProjectConfig::lists(&config.exclude, relative) {
+                lookup.excluded = true;
+                return lookup;
+            }
+            if config.keep && lookup.mark.is_none() {
+                lookup.mark = Some(ProjectMark::Kept {
+                    config: Self::config_path(root),
+                    error: config.error.clone(),
+                });
+            }
+            if added.is_none() && ProjectConfig::lists(&config.targets, relative) {
+                added = Some(ProjectMark::Added {
+                    config: root.join(CONFIG_FILE),
+                });
+            }
+        }
+        drop(configs);
+
+        if lookup.mark.is_none() {
+            lookup.mark = added;
+        }
+        lookup
+    }
+
+    fn config_path(root: &Path) -> PathBuf {
+        let config = root.join(CONFIG_FILE);
+        if config.exists() {
+            config
+        } else {
+            root.join(KEEP_MARKER)
+        }
+    }
+}
+
+/// Name of the project in `root`, from its manifest (`package.json`, `Cargo.toml`,
+/// `pyproject.toml`), falling back to the directory name
+pub fn project_name(root: &Path) -> Option<String> {
+    // Check for package.json (Node.js)
+    let pkg_json = root.join("package.json");
+    if pkg_json.exists() {
+        if let Ok(content) = std::fs::read_to_string(&pkg_json) {
+            if let Some(name) = extract_json_field(&content, "name") {
+                return Some(name);
+            }
+        }
+    }
+
+    // Check for Cargo.toml (Rust)
+    let cargo_toml = root.join("Cargo.toml");
+    if cargo_toml.exists() {
+        if let Ok(content) = std::fs::read_to_string(&cargo_toml) {
+            if let Some(name) = extract_toml_name(&content) {
+                return Some(name);
+            }
+        }
+    }
+
+    // Check for pyproject.toml (Python)
+    let pyproject = root.join("pyproject.toml");
+    if pyproject.exists() {
+        if let Ok(content) = std::fs::read_to_string(&pyproject) {
+            if let Some(name) = extract_toml_name(&content) {
+                return Some(name);
+            }
+        }
+    }
+
+    // Fallback: use the directory name
+    root.file_name().map(|n| n.to_string_lossy().to_string())
+}
+
+fn extract_json_field(content: &str, field: &str) -> Option<String> {
+    // Simple extraction without full JSON parsing
+    let pattern = format!("\"{field}\"");
+    let idx = content.find(&pattern)?;
+    let rest = &content[idx + pattern.len()..];
+    let colon = rest.find(':')?;
+    let after_colon = rest[colon + 1..].trim_start();
+    if let Some(stripped) = after_colon.strip_prefix('"') {
+        let value_end = stripped.find('"')?;
+        return Some(stripped[..value_end].to_string());
+    }
+    None
+}
+
+fn extract_toml_name(content: &str) -> Option<String> {
+    // Simple extraction: look for name = "value" under [package] or [project]
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with("name") && line.contains('=') {
+            let parts: Vec<&str> = line.splitn(2, '=').collect();
+            if parts.len() == 2 {
+                let value = parts[1].trim().trim_matches('"').trim_matches('\'');
+                if !value.is_empty() {
+                    return Some(value.to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_full_config() {
+        let config = ProjectConfig::parse(
+            r#"
+            keep = false
+            targets = ["fixtures/", "models/cache"]
+            exclude = ["docs/node_modules"]
+            "#,
+        );
+        assert!(!config.keep);
+        assert_eq!(config.targets, vec!["fixtures/", "models/cache"]);
+        assert_eq!(config.exclude, vec!["docs/node_modules"]);
+        assert!(config.error.is_none());
+    }
+
+    #[test]
+    fn test_parse_invalid_config_keeps_project() {
+        let config = ProjectConfig::parse("keep = maybe");
+        assert!(config.keep);
+        assert!(config.error.is_some());
+    }
+
+    #[test]
+    fn test_parse_unknown_field_keeps_project() {
+        // A typo like `exclud` must not silently allow cleaning
+        let config = ProjectConfig::parse("exclud = [\"x\"]");
+        assert!(config.keep);
+        assert!(config.error.is_some());
+    }
+
+    #[test]
+    fn test_load_keep_marker() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(KEEP_MARKER), "").unwrap();
+
+        let config = ProjectConfig::load(dir.path()).unwrap();
+        assert!(config.keep);
+    }
+
+    #[test]
+    fn test_load_without_config() {
+        let dir = tempdir().unwrap();
+        assert!(ProjectConfig::load(dir.path()).is_none());
+    }
+
+    #[test]
+    fn test_lookup_kept_project() {
+        let dir = tempdir().unwrap();
+        let project = dir.path().join("app");
+        fs::create_dir(&project).unwrap();
+        fs::write(project.join(KEEP_MARKER), "").unwrap();
+
+        let configs = ProjectConfigs::for_root(dir.path());
+        configs.load(&project);
+
+        let lookup = configs.lookup(&project.join("node_modules"));
+        assert!(!lookup.excluded);
+        assert_eq!(
+            lookup.mark,
+            Some(ProjectMark::Kept {
+                config: project.join(KEEP_MARKER),
+                error: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_lookup_added_and_excluded_paths() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join(CONFIG_FILE),
+            "targets = [\"models/cache\"]\nexclude = [\"docs/node_modules\"]",
+        )
+        .unwrap();
+
+        let configs = ProjectConfigs::for_root(dir.path());
+        configs.load(dir.path());
+
+        let added = configs.lookup(&dir.path().join("models/cache"));
+        assert_eq!(
+            added.mark,
+            Some(ProjectMark::Added {
+                config: dir.path().join(CONFIG_FILE),
+            })
+        );
+        assert!(
+            configs
+                .lookup(&dir.path().join("docs/node_modules"))
+                .excluded
+        );
+        assert!(configs.lookup(&dir.path().join("models")).mark.is_none());
+    }
+
+    #[test]
+    fn test_keep_wins_over_nested_targets() {
+        let dir = tempdir().unwrap();
+        let nested = dir.path().join("packages/web");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(dir.path().join(KEEP_MARKER), "").unwrap();
+        fs::write(nested.join(CONFIG_FILE), "targets = [\"fixtures\"]").unwrap();
+
+        let configs = ProjectConfigs::for_root(dir.path());
+        configs.load(dir.path());
+        configs.load(&nested);
+
+        let lookup = configs.lookup(&nested.join("fixtures"));
+        assert!(matches!(lookup.mark, Some(ProjectMark::Kept { .. })));
+    }
+
+    #[test]
+    fn test_lookup_with_relative_scan_root() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(CONFIG_FILE), "targets = [\"fixtures\"]").unwrap();
+
+        // Same paths the walker yields when scanning a relative root
+        let cwd = std::env::current_dir().unwrap();
+        let relative_root = pathdiff(dir.path(), &cwd);
+        let configs = ProjectConfigs::for_root(&relative_root);
+        configs.load(&relative_root);
+
+        let lookup = configs.lookup(&relative_root.join("fixtures"));
+        assert!(matches!(lookup.mark, Some(ProjectMark::Added { .. })));
+    }
+
+    /// Builds a `../..`-style path from `base` to `target`
+    fn pathdiff(target: &Path, base: &Path) -> PathBuf {
+        let mut up = PathBuf::new();
+        let mut common = base;
+        while !target.starts_with(common) {
+            up.push("..");
+            common = common.parent().unwrap();
+        }
+        up.join(target.strip_prefix(common).unwrap())
+    }
+
+    #[test]
+    fn test_for_root_loads_configs_above_root() {
+        let dir = tempdir().unwrap();
+        let sub = dir.path().join("packages");
+        fs::create_dir(&sub).unwrap();
+        fs::write(dir.path().join(KEEP_MARKER), "").unwrap();
+
+        let configs = ProjectConfigs::for_root(&sub);
+        let lookup = configs.lookup(&sub.join("node_modules"));
+        assert!(matches!(lookup.mark, Some(ProjectMark::Kept { .. })));
+    }
+
+    // extract_json_field tests
+    #[test]
+    fn test_extract_json_field_basic() {
+        let json = r#"{"name": "my-project", "version": "1.0.0"}"#;
+        assert_eq!(
+            extract_json_field(json, "name"),
+            Some("my-project".to_string())
+        );
+    }
+
+    #[test]
+    fn test_extract_json_field_with_whitespace() {
+        let json = r#"{
+            "name"  :  "spaced-project",
+            "version": "1.0.0"
+        }"#;
+        assert_eq!(
+            extract_json_field(json, "name"),
+            Some("spaced-project".to_string())
+        );
+    }
+
+    #[test]
+    fn test_extract_json_field_missing() {
+        let json = r#"{"version": "1.0.0"}"#;
+        assert_eq!(extract_json_field(json, "name"), None);
+    }
+
+    // extract_toml_name tests
+    #[test]
+    fn test_extract_toml_name_cargo() {
+        let toml = r#"
+[package]
+name = "my-rust-project"
+version = "0.1.0"
+"#;
+        assert_eq!(extract_toml_name(toml), Some("my-rust-project".to_string()));
+    }
+
+    #[test]
+    fn test_extract_toml_name_pyproject() {
+        let toml = r#"
+[project]
+name = "my-python-project"
+version = "1.0.0"
+"#;
+        assert_eq!(
+            extract_toml_name(toml),
+            Some("my-python-project".to_string())
+        );
+    }
+
+    #[test]
+    fn test_extract_toml_name_single_quotes() {
+        let toml = "name = 'single-quoted'";
+        assert_eq!(extract_toml_name(toml), Some("single-quoted".to_string()));
+    }
+
+    #[test]
+    fn test_extract_toml_name_missing() {
+        let toml = "[package]\nversion = \"1.0.0\"";
+        assert_eq!(extract_toml_name(toml), None);
+    }
+}
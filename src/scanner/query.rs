@@ -0,0 +1,395 @@
+use std::time::{Duration, SystemTime};
+
+use super::{parse_age, parse_size, ScanResult};
+use crate::profiles::{profiles_for_target, PROFILES};
+
+const FIELDS: &str = "size, age, profile, target, path or risk";
+
+/// A parsed filter expression, e.g. `size>1G age>90d profile:node !path:work/ risk:safe`.
+/// Terms are separated by spaces and must all match; `!` negates one. A bare word matches
+/// the path (case-insensitive), and quotes keep spaces or `:<>` in a value.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    source: String,
+    terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Term {
+    negated: bool,
+    test: Test,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Test {
+    Size(Compare, u64),
+    /// Time since anything inside changed
+    Age(Compare, Duration),
+    Profile(String),
+    Target(String),
+    /// Lowercased text the path must contain
+    Path(String),
+    Risk(Risk),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compare {
+    Less,
+    AtMost,
+    More,
+    AtLeast,
+}
+
+impl Compare {
+    fn holds<T: Ord>(self, value: &T, bound: &T) -> bool {
+        match self {
+            Self::Less => value < bound,
+            Self::AtMost => value <= bound,
+            Self::More => value > bound,
+            Self::AtLeast => value >= bound,
+        }
+    }
+}
+
+/// The `risk` column of `--format csv`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Risk {
+    Safe,
+    Active,
+    Sensitive,
+}
+
+impl Query {
+    /// Parses an expression; the error names the offending term
+    pub fn parse(source: &str) -> Result<Self, String> {
+        Self::parse_with(source, false)
+    }
+
+    /// Parses what was typed in the TUI's search bar. Unknown fields are path text there,
+    /// so `C:`, `foo:bar` or `a<b` find paths instead of being errors.
+    pub fn parse_search(source: &str) -> Result<Self, String> {
+        Self::parse_with(source, true)
+    }
+
+    fn parse_with(source: &str, lenient: bool) -> Result<Self, String> {
+        let terms = tokenize(source)?
+            .iter()
+            .map(|token| parse_term(token, lenient))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            source: source.to_string(),
+            terms,
+        })
+    }
+
+    /// The expression as it was typed
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Results whose size or age isn't known yet never match a bound on it (but do match
+    /// its negation). `active_window` decides `risk:active`, as with `--active-window`.
+    pub fn matches(
+        &self,
+        result: &ScanResult,
+        now: SystemTime,
+        active_window: Option<Duration>,
+    ) -> bool {
+        self.terms
+            .iter()
+            .all(|term| term.test.matches(result, now, active_window) != term.negated)
+    }
+}
+
+impl Test {
+    fn matches(
+        &self,
+        result: &ScanResult,
+        now: SystemTime,
+        active_window: Option<Duration>,
+    ) -> bool {
+        let idle = || {
+            result
+                .modified
+                .max(result.newest_modified)
+                .map(|time| now.duration_since(time).unwrap_or(Duration::ZERO))
+        };
+        let target = || {
+            result
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        };
+        match self {
+            Self::Size(compare, bound) => {
+                result.size.is_some_and(|size| compare.holds(&size, bound))
+            }
+            Self::Age(compare, bound) => idle().is_some_and(|idle| compare.holds(&idle, bound)),
+            Self::Profile(name) => profiles_for_target(&target()).contains(&name.as_str()),
+            Self::Target(name) => target() == *name,
+            Self::Path(text) => result.path.to_string_lossy().to_lowercase().contains(text),
+            Self::Risk(risk) => {
+                let active = active_window
+                    .zip(idle())
+                    .is_some_and(|(window, idle)| idle < window);
+                *risk
+                    == match (result.is_sensitive, active) {
+                        (true, _) => Risk::Sensitive,
+                        (false, true) => Risk::Active,
+                        (false, false) => Risk::Safe,
+                    }
+            }
+        }
+    }
+}
+
+/// Splits on spaces outside double quotes; quotes are kept so `parse_term` can tell
+/// `"size>1"` (text) from `size>1` (a bound)
+fn tokenize(source: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut quoted = false;
+    for c in source.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                token.push(c);
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            c => token.push(c),
+        }
+    }
+    if quoted {
+        return Err(format!("unclosed quote in {token}"));
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// With `lenient`, a term with an unknown field is text to find in the path
+fn parse_term(token: &str, lenient: bool) -> Result<Term, String> {
+    let (negated, body) = token
+        .strip_prefix('!')
+        .map_or((false, token), |body| (true, body));
+    if body.is_empty() {
+        return Err("`!` needs a term after it (e.g. !path:work/)".to_string());
+    }
+
+    // `field` followed by `:`, `<`, `>` or `=`; anything else is text to find in the path
+    let split = body
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .filter(|&i| i > 0 && body[i..].starts_with([':', '<', '>', '=']));
+    let Some(split) = split else {
+        return Ok(Term {
+            negated,
+            test: Test::Path(unquote(body).to_lowercase()),
+        });
+    };
+    let (field, rest) = body.split_at(split);
+    let field = field.to_ascii_lowercase();
+
+    let test = match field.as_str() {
+        "size" => {
+            let (compare, value) = comparison(&field, rest, "size>1G")?;
+            Test::Size(compare, parse_size(value)?)
+        }
+        "age" => {
+            let (compare, value) = comparison(&field, rest, "age>90d")?;
+            Test::Age(compare, parse_age(value)?)
+        }
+        "profile" => {
+            let name = value(&field, rest, "profile:node")?;
+            if !PROFILES.contains_key(name.as_str()) {
+                return Err(format!("unknown profile {name:?}"));
+            }
+            Test::Profile(name)
+        }
+        "target" => Test::Target(value(&field, rest, "target:node_modules")?),
+        "path" => Test::Path(value(&field, rest, "path:work/")?.to_lowercase()),
+        "risk" => match value(&field, rest, "risk:safe")?
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "safe" => Test::Risk(Risk::Safe),
+            "active" => Test::Risk(Risk::Active),
+            "sensitive" | "protected" => Test::Risk(Risk::Sensitive),
+            other => {
+                return Err(format!(
+                    "unknown risk {other:?} (use safe, active or sensitive)"
+                ))
+            }
+        },
+        _ if lenient => Test::Path(body.to_lowercase()),
+        _ => return Err(format!("unknown field {field:?} (use {FIELDS})")),
+    };
+    Ok(Term { negated, test })
+}
+
+/// The bound of `size>1G`-style terms
+fn comparison<'a>(field: &str, rest: &'a str, example: &str) -> Result<(Compare, &'a str), String> {
+    let (compare, value) = if let Some(value) = rest.strip_prefix(">=") {
+        (Compare::AtLeast, value)
+    } else if let Some(value) = rest.strip_prefix("<=") {
+        (Compare::AtMost, value)
+    } else if let Some(value) = rest.strip_prefix('>') {
+        (Compare::More, value)
+    } else if let Some(value) = rest.strip_prefix('<') {
+        (Compare::Less, value)
+    } else {
+        return Err(format!("{field} needs <, <=, > or >= (e.g. {example})"));
+    };
+    if value.is_empty() {
+        return Err(format!("{field}{rest} needs a value (e.g. {example})"));
+    }
+    Ok((compare, value))
+}
+
+/// The value of `profile:node`-style terms
+fn value(field: &str, rest: &str, example: &str) -> Result<String, String> {
+    let value = rest
+        .strip_prefix(':')
+        .ok_or_else(|| format!("{field} takes {field}:VALUE (e.g. {example})"))?;
+    let value = unquote(value);
+    if value.is_empty() {
+        return Err(format!("{field}: needs a value (e.g. {example})"));
+    }
+    Ok(value.to_string())
+}
+
+fn unquote(text: &str) -> &str {
+    text.strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+        .unwrap_or(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const DAY: Duration = Duration::from_hours(24);
+
+    fn result(path: &str, size: Option<u64>, idle: Duration, now: SystemTime) -> ScanResult {
+        let mut result = ScanResult::new(PathBuf::from(path));
+        result.size = size;
+        result.modified = Some(now - idle);
+        result
+    }
+
+    fn matches(query: &str, result: &ScanResult, now: SystemTime) -> bool {
+        Query::parse(query)
+            .unwrap()
+            .matches(result, now, Some(Duration::from_mins(10)))
+    }
+
+    #[test]
+    fn test_example_query() {
+        let now = SystemTime::now();
+        let query = "size>1G age>90d profile:node !path:work/ risk:safe";
+        let old = result(
+            "/code/app/node_modules",
+            Some(2_000_000_000),
+            100 * DAY,
+            now,
+        );
+
+        assert!(matches(query, &old, now));
+        let mut at_work = old.clone();
+        at_work.path = PathBuf::from("/code/Work/app/node_modules");
+        assert!(!matches(query, &at_work, now));
+        let mut small = old.clone();
+        small.size = Some(1_000_000_000);
+        assert!(!matches(query, &small, now));
+        let mut rust = old;
+        rust.path = PathBuf::from("/code/app/target");
+        assert!(!matches(query, &rust, now));
+    }
+
+    #[test]
+    fn test_comparisons_and_unknown_sizes() {
+        let now = SystemTime::now();
+        let sized = result("/a/node_modules", Some(1000), 5 * DAY, now);
+
+        assert!(matches("size>=1000 size<=1KB", &sized, now));
+        assert!(!matches("size<1000", &sized, now));
+        assert!(matches("age<1w age>4d", &sized, now));
+
+        let unknown = result("/a/node_modules", None, DAY, now);
+        assert!(!matches("size>0", &unknown, now));
+        assert!(matches("!size>0", &unknown, now));
+    }
+
+    #[test]
+    fn test_words_are_path_text() {
+        let now = SystemTime::now();
+        let item = result("/code/My App/node_modules", Some(1), DAY, now);
+
+        assert!(matches("", &item, now));
+        assert!(matches("my node_", &item, now));
+        assert!(matches("\"my app\" target:node_modules", &item, now));
+        assert!(matches("path:\"/my app/\"", &item, now));
+        assert!(!matches("\"size>1\"", &item, now));
+        assert!(!matches("target:node", &item, now));
+    }
+
+    #[test]
+    fn test_risk() {
+        let now = SystemTime::now();
+        let busy = result("/a/target", Some(1), Duration::from_mins(1), now);
+        let mut protected = result("/usr/lib/node_modules", Some(1), 9 * DAY, now);
+        protected.is_sensitive = true;
+
+        assert!(matches("risk:active", &busy, now));
+        assert!(matches("!risk:safe", &busy, now));
+        assert!(matches("risk:sensitive", &protected, now));
+        assert!(Query::parse("risk:safe").unwrap().matches(&busy, now, None));
+    }
+
+    #[test]
+    fn test_parse_errors_name_the_term() {
+        let error = |query: &str| Query::parse(query).unwrap_err();
+
+        assert_eq!(
+            error("colour:red"),
+            format!("unknown field \"colour\" (use {FIELDS})")
+        );
+        assert!(error("size>").contains("needs a value"));
+        assert!(error("size:1G").contains("needs <, <=, > or >="));
+        assert!(error("size>huge").starts_with("invalid size \"huge\""));
+        assert!(error("age>soon").starts_with("invalid age \"soon\""));
+        assert!(error("profile:cobol").contains("unknown profile"));
+        assert!(error("risk:low").contains("unknown risk"));
+        assert!(error("path:").contains("needs a value"));
+        assert!(error("!").contains("needs a term"));
+        assert!(error("path:\"my app").contains("unclosed quote"));
+        assert!(Query::parse("  ").unwrap().terms.is_empty());
+    }
+
+    #[test]
+    fn test_search_treats_unknown_fields_as_path_text() {
+        let now = SystemTime::now();
+        let windows = result("C:/Code/foo:bar/a<b/node_modules", Some(1), DAY, now);
+        let search = |query: &str| {
+            Query::parse_search(query)
+                .unwrap()
+                .matches(&windows, now, None)
+        };
+
+        assert!(search("foo:bar"));
+        assert!(search("c:"));
+        assert!(search("a<b"));
+        assert!(!search("!foo:bar"));
+        assert!(!search("foo:baz"));
+        // Known fields still parse as filters, and their mistakes are still errors
+        assert!(search("size<2 target:node_modules"));
+        assert!(Query::parse_search("size>huge").is_err());
+        assert!(Query::parse("foo:bar").is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use std::path::Path;
+use std::time::SystemTime;
 
 use tokio::sync::Semaphore;
 
@@ -10,20 +11,40 @@ use tokio::sync::Semaphore;
 /// 32 is a safe default for modern systems.
 static SIZE_SEMAPHORE: Semaphore = Semaphore::const_new(32);
 
-/// Returns `(total_size, file_count)`.
-pub async fn calculate_size(path: &Path) -> (u64, u64) {
+/// Totals gathered by a single deep walk of a directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirStats {
+    pub size: u64,
+    pub file_count: u64,
+    /// Most recent mtime of any file or subdirectory inside the walked tree
+    pub newest_modified: Option<SystemTime>,
+}
+
+impl DirStats {
+    fn merge(&mut self, other: Self) {
+        self.size += other.size;
+        self.file_count += other.file_count;
+        self.touch(other.newest_modified);
+    }
+
+    fn touch(&mut self, modified: Option<SystemTime>) {
+        if modified > self.newest_modified {
+            self.newest_modified = modified;
+        }
+    }
+}
+
+pub async fn calculate_size(path: &Path) -> DirStats {
     let _permit = SIZE_SEMAPHORE.acquire().await.ok();
     let path = path.to_path_buf();
 
     tokio::task::spawn_blocking(move || calculate_dir_size(&path))
         .await
-        .unwrap_or((0, 0))
+        .unwrap_or_default()
 }
 
-/// Returns `(total_size, file_count)`.
-fn calculate_dir_size(path: &Path) -> (u64, u64) {
-    let mut total_size = 0u64;
-    let mut file_count = 0u64;
+fn calculate_dir_size(path: &Path) -> DirStats {
+    let mut stats = DirStats::default();
 
     if let Ok(entries) = std::fs::read_dir(path) {
         for entry in entries.flatten() {
@@ -32,17 +53,18 @@ fn calculate_dir_size(path: &Path) -> (u64, u64) {
             };
 
             if metadata.is_file() {
-                total_size += metadata.len();
-                file_count += 1;
+                stats.size += metadata.len();
+                stats.file_count += 1;
+                stats.touch(metadata.modified().ok());
             } else if metadata.is_dir() {
-                let (sub_size, sub_count) = calculate_dir_size(&entry.path());
-                total_size += sub_size;
-                file_count += sub_count;
+                // A directory's mtime changes when entries are created or removed in it
+                stats.touch(metadata.modified().ok());
+                stats.merge(calculate_dir_size(&entry.path()));
             }
         }
     }
 
-    (total_size, file_count)
+    stats
 }
 
 #[cfg(test)]
@@ -56,9 +78,10 @@ mod tests {
     #[test]
     fn test_calculate_dir_size_empty() {
         let dir = tempdir().unwrap();
-        let (size, count) = calculate_dir_size(dir.path());
-        assert_eq!(size, 0);
-        assert_eq!(count, 0);
+        let stats = calculate_dir_size(dir.path());
+        assert_eq!(stats.size, 0);
+        assert_eq!(stats.file_count, 0);
+        assert_eq!(stats.newest_modified, None);
     }
 
     #[test]
@@ -68,9 +91,9 @@ mod tests {
         let mut file = File::create(&file_path).unwrap();
         file.write_all(b"hello").unwrap();
 
-        let (size, count) = calculate_dir_size(dir.path());
-        assert_eq!(size, 5);
-        assert_eq!(count, 1);
+        let stats = calculate_dir_size(dir.path());
+        assert_eq!(stats.size, 5);
+        assert_eq!(stats.file_count, 1);
     }
 
     #[test]
@@ -83,9 +106,9 @@ mod tests {
         let mut f2 = File::create(dir.path().join("b.txt")).unwrap();
         f2.write_all(b"bbbbb").unwrap();
 
-        let (size, count) = calculate_dir_size(dir.path());
-        assert_eq!(size, 8); // 3 + 5
-        assert_eq!(count, 2);
+        let stats = calculate_dir_size(dir.path());
+        assert_eq!(stats.size, 8); // 3 + 5
+        assert_eq!(stats.file_count, 2);
     }
 
     #[test]
@@ -102,15 +125,30 @@ mod tests {
         let mut f2 = File::create(sub.join("nested.txt")).unwrap();
         f2.write_all(b"nested").unwrap();
 
-        let (size, count) = calculate_dir_size(dir.path());
-        assert_eq!(size, 10); // 4 + 6
-        assert_eq!(count, 2);
+        let stats = calculate_dir_size(dir.path());
+        assert_eq!(stats.size, 10); // 4 + 6
+        assert_eq!(stats.file_count, 2);
+    }
+
+    #[test]
+    fn test_calculate_dir_size_tracks_newest_modified() {
+        let dir = tempdir().unwrap();
+        let sub = dir.path().join("subdir");
+        fs::create_dir(&sub).unwrap();
+
+        let old = File::create(dir.path().join("old.txt")).unwrap();
+        old.set_modified(SystemTime::UNIX_EPOCH).unwrap();
+        let fresh = File::create(sub.join("fresh.txt")).unwrap();
+        let fresh_time = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(2_000_000_000);
+        fresh.set_modified(fresh_time).unwrap();
+
+        let stats = calculate_dir_size(dir.path());
+        assert_eq!(stats.newest_modified, Some(fresh_time));
     }
 
     #[test]
     fn test_calculate_dir_size_nonexistent() {
-        let (size, count) = calculate_dir_size(Path::new("/nonexistent/path/12345"));
-        assert_eq!(size, 0);
-        assert_eq!(count, 0);
+        let stats = calculate_dir_size(Path::new("/nonexistent/path/12345"));
+        assert_eq!(stats, DirStats::default());
     }
 }
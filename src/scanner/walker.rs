@@ -1,4 +1,5 @@
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::SystemTime;
 
@@ -7,6 +8,8 @@ use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 
 use super::batcher::ResultBatcher;
+use super::project::{ProjectConfigs, ProjectMark};
+use crate::delete::{DirIdentity, Leftover};
 
 #[derive(Debug, Clone)]
 pub struct ScanResult {
@@ -14,7 +17,13 @@ pub struct ScanResult {
     pub size: Option<u64>,
     pub file_count: Option<u64>,
     pub modified: Option<SystemTime>,
+    /// Newest mtime found inside the directory (filled in by size calculation)
+    pub newest_modified: Option<SystemTime>,
     pub is_sensitive: bool,
+    /// Set when a project's `.cache-sweep.toml` or keep marker changed how this result is handled
+    pub project_mark: Option<ProjectMark>,
+    /// Which directory was found, so deletion can check the path wasn't swapped out since
+    pub identity: Option<DirIdentity>,
 }
 
 impl ScanResult {
@@ -22,23 +31,44 @@ impl ScanResult {
         let modified = std::fs::metadata(&path)
             .ok()
             .and_then(|m| m.modified().ok());
+        let identity = std::fs::symlink_metadata(&path)
+            .ok()
+            .and_then(|m| DirIdentity::of(&m));
 
         Self {
             path,
             size: None,
             file_count: None,
             modified,
+            newest_modified: None,
             is_sensitive: false,
+            project_mark: None,
+            identity,
         }
     }
 }
 
+/// Counts directories the walk couldn't read (e.g., permission denied); cheap to clone
+#[derive(Debug, Clone, Default)]
+pub struct ScanErrors(Arc<AtomicUsize>);
+
+impl ScanErrors {
+    pub fn count(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn record(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
 pub fn start_scan(
     root: PathBuf,
     targets: Vec<String>,
     excludes: Vec<String>,
     follow_links: bool,
     respect_ignore: bool,
+    errors: ScanErrors,
     cancel_token: CancellationToken,
 ) -> mpsc::UnboundedReceiver<Vec<ScanResult>> {
     let (tx, rx) = mpsc::unbounded_channel();
@@ -47,6 +77,7 @@ pub fn start_scan(
         let targets = Arc::new(targets);
         let excludes = Arc::new(excludes);
         let batcher = Arc::new(std::sync::Mutex::new(ResultBatcher::new(tx.clone())));
+        let projects = Arc::new(ProjectConfigs::for_root(&root));
 
         WalkBuilder::new(&root)
             .hidden(false) // Scan hidden dirs (.pnpm-store, .yarn)
@@ -60,6 +91,8 @@ pub fn start_scan(
                 let targets = Arc::clone(&targets);
                 let excludes = Arc::clone(&excludes);
                 let batcher = Arc::clone(&batcher);
+                let projects = Arc::clone(&projects);
+                let errors = errors.clone();
                 let cancel = cancel_token.clone();
 
                 Box::new(move |result| {
@@ -67,30 +100,55 @@ pub fn start_scan(
                         return WalkState::Quit;
                     }
 
-                    if let Ok(entry) = result {
-                        if !entry.file_type().is_some_and(|ft| ft.is_dir()) {
-                            return WalkState::Continue;
-                        }
-
-                        let file_name = entry.file_name().to_string_lossy();
-
-                        // Check if excluded
-                        if excludes.iter().any(|e| file_name == *e) {
-                            return WalkState::Skip;
-                        }
-
-                        // Check if target
-                        if targets.iter().any(|t| file_name == *t) {
-                            let result = ScanResult::new(entry.path().to_path_buf());
-                            batcher
-                                .lock()
-                                .unwrap_or_else(std::sync::PoisonError::into_inner)
-                                .add(result);
-                            // Don't descend into matched directory
-                            return WalkState::Skip;
-                        }
+                    let Ok(entry) = result else {
+                        errors.record();
+                        return WalkState::Continue;
+                    };
+
+                    if !entry.file_type().is_some_and(|ft| ft.is_dir()) {
+                        return WalkState::Continue;
                     }
 
+                    let file_name = entry.file_name().to_string_lossy();
+
+                    // Check if excluded
+                    if excludes.iter().any(|e| file_name == *e) {
+                        return WalkState::Skip;
+                    }
+
+                    // Apply project configs from ancestor directories
+                    let project = projects.lookup(entry.path());
+                    if project.excluded {
+                        return WalkState::Skip;
+                    }
+
+                    // Check if target (globally, or added by the project config)
+                    let is_added = matches!(project.mark, Some(ProjectMark::Added { .. }));
+                    let is_target = |name: &str| is_added || targets.iter().any(|t| name == t);
+                    // Folders deleted with undo: a running cache-sweep still owns them, but
+                    // those of a killed run would never be purged, so they're offered again
+                    let leftover = Leftover::parse(&file_name);
+                    let is_match = leftover.map_or_else(
+                        || is_target(&file_name),
+                        |leftover| leftover.is_orphaned() && is_target(leftover.original),
+                    );
+                    if is_match {
+                        let mut result = ScanResult::new(entry.path().to_path_buf());
+                        result.project_mark = project.mark;
+                        batcher
+                            .lock()
+                            .unwrap_or_else(std::sync::PoisonError::into_inner)
+                            .add(result);
+                        // Don't descend into matched directory
+                        return WalkState::Skip;
+                    }
+                    if leftover.is_some() {
+                        return WalkState::Skip;
+                    }
+
+                    // Descending: pick up this directory's project config first
+                    projects.load(entry.path());
+
                     WalkState::Continue
                 })
             });
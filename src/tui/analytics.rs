@@ -130,7 +130,8 @@ impl AnalyticsData {
         if let Some(existing) = self.top_largest.iter_mut().find(|item| item.path == path) {
             existing.size = size;
             // Re-sort after updating
-            self.top_largest.sort_by(|a, b| b.size.cmp(&a.size));
+            self.top_largest
+                .sort_by_key(|item| std::cmp::Reverse(item.size));
             return;
         }
 
@@ -156,7 +157,7 @@ impl AnalyticsData {
 
     /// Reverse lookup: find which profile contains this target
     /// For ambiguous targets (target, out), checks parent dir for marker files
-    fn profile_for_target(target: &str, path: &Path) -> String {
+    pub fn profile_for_target(target: &str, path: &Path) -> String {
         // Fast path: disambiguate `target` and `out` folders via parent markers
         if target == "target" {
             return Self::disambiguate_target_folder(path);
@@ -260,14 +261,14 @@ impl AnalyticsData {
     /// Get targets sorted by size (descending)
     pub fn targets_by_size(&self) -> Vec<&TargetStats> {
         let mut targets: Vec<_> = self.by_target.values().collect();
-        targets.sort_by(|a, b| b.total_size.cmp(&a.total_size));
+        targets.sort_by_key(|t| std::cmp::Reverse(t.total_size));
         targets
     }
 
     /// Get profiles sorted by size (descending)
     pub fn profiles_by_size(&self) -> Vec<&ProfileStats> {
         let mut profiles: Vec<_> = self.by_profile.values().collect();
-        profiles.sort_by(|a, b| b.total_size.cmp(&a.total_size));
+        profiles.sort_by_key(|p| std::cmp::Reverse(p.total_size));
         profiles
     }
 
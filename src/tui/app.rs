@@ -1,11 +1,16 @@
-use std::collections::{HashMap, HashSet};
-use std::path::Path;
-use std::time::SystemTime;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
-use crate::risk::{analyze_risk, RiskAnalysis};
-use crate::scanner::ScanResult;
+use crate::delete::{
+    estimated_compressed_size, Archive, AuditEntry, AuditLog, DeleteFailure, DeleteResult,
+    DeleteStrategy, Quarantined, RiskLevel,
+};
+use crate::risk::{analyze_scan_result, RiskAnalysis};
+use crate::scanner::{plan_to_free, BudgetItem, BudgetPlan, Query, ResultFilter, ScanResult};
 
 use super::analytics::AnalyticsData;
+use super::queue::{DeleteJob, DeleteQueue, JobEvent, JobId, JobKind, JobOutcome, JobStatus};
 
 /// Extracts the target folder name from a path (e.g., `node_modules` from `/foo/bar/node_modules`)
 fn extract_target_name(path: &Path) -> String {
@@ -28,6 +33,8 @@ pub enum Panel {
     Results,
     Info,
     Analytics,
+    Queue,
+    Errors,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -37,6 +44,9 @@ pub enum Mode {
     MultiSelect,
     Confirm,
     SensitiveBlocked, // Modal shown when user tries to delete a sensitive directory
+    ActiveConfirm,    // Modal shown when user tries to delete a folder a build is writing to
+    FreePrompt,       // Asking how much space to free
+    FreePlan,         // Reviewing the folders picked to free it
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -55,6 +65,26 @@ impl SortOrder {
             _ => Self::Size,
         }
     }
+
+    /// Name accepted by `--sort`
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Size => "size",
+            Self::Path => "path",
+            Self::Age => "age",
+        }
+    }
+}
+
+/// A deletion that `u` can still take back
+#[derive(Debug, Clone)]
+pub struct UndoEntry {
+    pub index: usize,
+    pub size: u64,
+    /// Where the folder is parked until purged (`None` in dry-run mode, where nothing moved)
+    pub quarantined: Option<Quarantined>,
+    /// Logged once the purge says how it went
+    pub audit: Option<AuditEntry>,
 }
 
 #[derive(Debug, Clone)]
@@ -68,7 +98,7 @@ pub struct ResultItem {
 
 impl ResultItem {
     pub fn from_scan_result(mut result: ScanResult) -> Self {
-        let risk = analyze_risk(&result.path);
+        let risk = analyze_scan_result(&result);
         result.is_sensitive = risk.is_sensitive;
         Self {
             scan_result: result,
@@ -78,6 +108,19 @@ impl ResultItem {
             is_deleted: false,
         }
     }
+
+    /// Most recent modification seen on the folder itself or anything inside it
+    pub fn last_activity(&self) -> Option<SystemTime> {
+        self.scan_result
+            .modified
+            .max(self.scan_result.newest_modified)
+    }
+
+    /// True if something inside changed within `window` of `now` (e.g., a running build)
+    pub fn is_active(&self, window: Duration, now: SystemTime) -> bool {
+        self.last_activity()
+            .is_some_and(|t| now.duration_since(t).unwrap_or(Duration::ZERO) < window)
+    }
 }
 
 #[allow(clippy::struct_excessive_bools)] // TUI state naturally tracks multiple boolean flags
@@ -90,12 +133,17 @@ pub struct App {
     pub mode: Mode,
     pub sort_order: SortOrder,
     pub search_query: String,
+    pub search: Query,                // last valid parse of `search_query`
+    pub search_error: Option<String>, // why `search_query` doesn't parse (shown in the search bar)
+    pub filter: ResultFilter,         // size/age bounds from the command line
     pub needs_filter: bool,
     pub needs_sort: bool,
     pub scanning: bool,
     pub total_size: u64,
     pub freed_size: u64,
     pub errors: Vec<String>,
+    pub delete_failures: Vec<DeleteFailure>, // paths left behind by partial deletes
+    pub errors_scroll: usize,
     pub selected_indices: HashSet<usize>,
     pub visible_height: usize,
     pub show_protected: bool,
@@ -114,6 +162,30 @@ pub struct App {
     pub sensitive_blocked_count: usize, // count of sensitive items in selection (for modal display)
     // Dry run mode - deletions are simulated, items stay visible
     pub dry_run: bool,
+    // Active-build guard: items modified within this window need confirmation (None = disabled)
+    pub active_window: Option<Duration>,
+    pub pending_active_delete: Option<usize>, // item awaiting ActiveConfirm
+    pub pending_active_archive: bool,         // ...to be archived rather than deleted
+    // Trash mode: removed folders still take space until the trash is emptied
+    pub delete_strategy: DeleteStrategy,
+    // Undo: most recent deletion last; older ones are purged once `undo_depth` is exceeded
+    pub undo_stack: VecDeque<UndoEntry>,
+    pub undo_depth: usize,
+    // Deletions run in the background; this tracks their progress
+    pub delete_queue: DeleteQueue,
+    pub finishing: bool, // quitting: waiting for deletions and undo purges to finish
+    // Undo entries whose folders are being purged, by purge job
+    purges: HashMap<JobId, UndoEntry>,
+    // Where finished deletions are recorded (None disables logging)
+    pub audit: Option<AuditLog>,
+    // Where `A` writes tarballs (None disables archiving)
+    pub archive: Option<Archive>,
+    // Space planner: a target waiting for sizes (from `--free`), the `F` prompt, and the plan
+    pub free_target: Option<u64>,
+    pub free_input: String,
+    pub free_error: Option<String>,
+    pub budget: Option<BudgetPlan>, // steps hold indices into `results`
+    pub budget_scroll: usize,
 }
 
 impl App {
@@ -127,12 +199,17 @@ impl App {
             mode: Mode::Normal,
             sort_order,
             search_query: String::new(),
+            search: Query::default(),
+            search_error: None,
+            filter: ResultFilter::default(),
             needs_filter: false,
             needs_sort: false,
             scanning: true,
             total_size: 0,
             freed_size: 0,
             errors: Vec::new(),
+            delete_failures: Vec::new(),
+            errors_scroll: 0,
             selected_indices: HashSet::new(),
             visible_height: 20,
             show_protected,
@@ -147,9 +224,43 @@ impl App {
             visible_group_count: 5, // default, updated by UI on render
             sensitive_blocked_count: 0,
             dry_run,
+            active_window: None,
+            pending_active_delete: None,
+            pending_active_archive: false,
+            delete_strategy: DeleteStrategy::Delete,
+            undo_stack: VecDeque::new(),
+            undo_depth: 0,
+            delete_queue: DeleteQueue::default(),
+            finishing: false,
+            purges: HashMap::new(),
+            audit: None,
+            archive: None,
+            free_target: None,
+            free_input: String::new(),
+            free_error: None,
+            budget: None,
+            budget_scroll: 0,
         }
     }
 
+    /// Whether the item at `index` looks like a build is still writing to it
+    pub fn is_active(&self, index: usize) -> bool {
+        let Some(window) = self.active_window else {
+            return false;
+        };
+        self.results
+            .get(index)
+            .is_some_and(|item| item.is_active(window, SystemTime::now()))
+    }
+
+    /// Count how many selected items are active (skipped by batch delete)
+    pub fn count_active_in_selection(&self) -> usize {
+        self.selected_indices
+            .iter()
+            .filter(|&&idx| self.is_active(idx))
+            .count()
+    }
+
     /// Count how many selected items are sensitive (for multi-select blocking)
     pub fn count_sensitive_in_selection(&self) -> usize {
         self.selected_indices
@@ -196,6 +307,12 @@ impl App {
         }
     }
 
+    pub fn update_newest_modified(&mut self, index: usize, newest: Option<SystemTime>) {
+        if let Some(item) = self.results.get_mut(index) {
+            item.scan_result.newest_modified = newest;
+        }
+    }
+
     pub fn on_tick(&mut self) {
         self.spinner_tick = self.spinner_tick.wrapping_add(1);
         self.sort_flash = self.sort_flash.saturating_sub(1);
@@ -211,6 +328,13 @@ impl App {
             self.rebuild_display_indices();
             self.needs_filter = false;
         }
+
+        // Plan a pending `--free` once every size is known
+        if self.mode == Mode::Normal && !self.scanning && !self.is_calculating_sizes() {
+            if let Some(target) = self.free_target.take() {
+                self.plan_free_space(target);
+            }
+        }
     }
 
     /// Rebuilds target groups for tab filtering.
@@ -238,7 +362,7 @@ impl App {
 
         // Sort groups by total size descending
         let mut groups: Vec<TargetGroup> = groups.into_values().collect();
-        groups.sort_by(|a, b| b.total_size.cmp(&a.total_size));
+        groups.sort_by_key(|g| std::cmp::Reverse(g.total_size));
 
         self.target_groups = groups;
 
@@ -266,19 +390,15 @@ impl App {
             self.target_groups[self.active_tab - 1].indices.clone()
         };
 
-        // Step 2: Apply search filter
-        let query = self.search_query.to_lowercase();
+        // Step 2: Apply search and size/age filters (unsized items reappear once sized)
+        let now = SystemTime::now();
         self.filtered_indices = base_indices
             .into_iter()
             .filter(|&i| {
                 let item = &self.results[i];
-                self.search_query.is_empty()
-                    || item
-                        .scan_result
-                        .path
-                        .to_string_lossy()
-                        .to_lowercase()
-                        .contains(&query)
+                self.search
+                    .matches(&item.scan_result, now, self.active_window)
+                    && self.filter.matches(&item.scan_result, now)
             })
             .collect();
 
@@ -483,6 +603,354 @@ impl App {
         // Grouped display will be rebuilt via needs_filter -> rebuild_display_indices
     }
 
+    /// Queues a result for deletion; `None` if it's already gone or on its way
+    pub fn queue_delete(&mut self, index: usize) -> Option<DeleteJob> {
+        self.queue_job(index, JobKind::Delete)
+    }
+
+    pub fn queue_archive(&mut self, index: usize) -> Option<DeleteJob> {
+        if self.archive.is_none() {
+            self.add_error("Archiving is unavailable: no archive directory".to_string());
+            return None;
+        }
+        self.queue_job(index, JobKind::Archive)
+    }
+
+    /// Where `A` would archive the item at `index`
+    pub fn archive_path(&self, index: usize) -> Option<PathBuf> {
+        let item = self.results.get(index)?;
+        Some(self.archive.as_ref()?.archive_path(&item.scan_result.path))
+    }
+
+    fn queue_job(&mut self, index: usize, kind: JobKind) -> Option<DeleteJob> {
+        let item = self.results.get(index)?;
+        if item.is_deleted || item.is_deleting {
+            return None;
+        }
+        let path = item.scan_result.path.clone();
+        let size = item.scan_result.size.unwrap_or(0);
+        let identity = item.scan_result.identity;
+        self.mark_deleting(index);
+        Some(self.delete_queue.enqueue(index, path, size, identity, kind))
+    }
+
+    /// Queues the purge of a folder that can no longer be undone; `None` if nothing was
+    /// moved aside (dry runs)
+    pub fn queue_purge(&mut self, entry: UndoEntry) -> Option<DeleteJob> {
+        let path = entry.quarantined.as_ref()?.quarantined.clone();
+        let job = self
+            .delete_queue
+            .enqueue(entry.index, path, entry.size, None, JobKind::Purge);
+        self.purges.insert(job.id, entry);
+        Some(job)
+    }
+
+    /// Clears the in-progress marker on rows whose jobs were cancelled
+    pub fn unmark_deleting(&mut self, indices: &[usize]) {
+        for &index in indices {
+            if let Some(item) = self.results.get_mut(index) {
+                item.is_deleting = false;
+            }
+        }
+    }
+
+    /// Applies a worker update. Returns the purge of a quarantined folder that fell
+    /// off the undo stack, for the workers to run.
+    pub fn apply_job_event(&mut self, event: JobEvent) -> Option<DeleteJob> {
+        match event {
+            JobEvent::Started(id) => {
+                if let Some(job) = self.delete_queue.get_mut(id) {
+                    job.status = JobStatus::Running;
+                }
+                None
+            }
+            JobEvent::Progress(id, progress) => {
+                if let Some(job) = self.delete_queue.get_mut(id) {
+                    job.progress = progress;
+                }
+                None
+            }
+            JobEvent::Finished(id, outcome) => self.finish_job(id, outcome),
+        }
+    }
+
+    fn finish_job(&mut self, id: JobId, outcome: JobOutcome) -> Option<DeleteJob> {
+        let job = self.delete_queue.get_mut(id)?;
+        let (index, size, kind) = (job.index, job.size, job.kind);
+        job.status = match &outcome {
+            JobOutcome::Removed(_) | JobOutcome::Quarantined(_) | JobOutcome::Archived(..) => {
+                JobStatus::Done
+            }
+            JobOutcome::Failed(result) => {
+                JobStatus::Failed(result.error.clone().unwrap_or_default())
+            }
+            JobOutcome::Cancelled => JobStatus::Cancelled,
+        };
+        let path = job.path.clone();
+
+        if kind == JobKind::Purge {
+            let entry = self.purges.remove(&id)?;
+            if let JobOutcome::Removed(result) | JobOutcome::Failed(result) = outcome {
+                self.settle_purge(&entry, result);
+            }
+            return None;
+        }
+
+        match outcome {
+            JobOutcome::Removed(result) if kind == JobKind::Archive => {
+                // Only dry runs get here; count what archiving would save
+                let compressed = estimated_compressed_size(size);
+                if let Some(archive) = self.archive_path(index) {
+                    self.log_archive(index, size, &result, &archive, compressed);
+                }
+                self.mark_deleted(index, size - compressed);
+                None
+            }
+            JobOutcome::Removed(result) => {
+                self.log_deletion(index, size, &result);
+                // Nothing is freed yet by a dry run or a trash move; count what would be
+                let freed = if self.dry_run || self.delete_strategy == DeleteStrategy::Trash {
+                    size
+                } else {
+                    result.size_freed
+                };
+                self.mark_deleted(index, freed);
+                if self.dry_run && self.delete_strategy == DeleteStrategy::Delete {
+                    // Nothing moved; undo only reverts the display
+                    self.push_undo(UndoEntry {
+                        index,
+                        size: freed,
+                        quarantined: None,
+                        audit: None,
+                    });
+                }
+                None
+            }
+            JobOutcome::Quarantined(quarantined) => {
+                // Counted at the scanned size until the purge reports what it freed
+                self.mark_deleted(index, size);
+                let audit = self.audit_entry(index, size);
+                self.push_undo(UndoEntry {
+                    index,
+                    size,
+                    quarantined: Some(quarantined),
+                    audit,
+                })
+                .and_then(|evicted| self.queue_purge(evicted))
+            }
+            JobOutcome::Archived(result, item) => {
+                self.log_archive(index, size, &result, &item.archive, item.compressed_size);
+                // The tarball still takes up space
+                self.mark_deleted(
+                    index,
+                    result.size_freed.saturating_sub(item.compressed_size),
+                );
+                None
+            }
+            JobOutcome::Failed(result) => {
+                self.log_deletion(index, size, &result);
+                // Whatever did get removed is still freed
+                self.freed_size += result.size_freed;
+                self.unmark_deleting(&[index]);
+                self.add_error(format!(
+                    "{}: {}",
+                    path.display(),
+                    result.error.unwrap_or_default()
+                ));
+                self.delete_failures.extend(result.failures);
+                None
+            }
+            JobOutcome::Cancelled => {
+                self.unmark_deleting(&[index]);
+                None
+            }
+        }
+    }
+
+    /// Swaps the scanned size counted for a quarantined folder for what its purge actually freed
+    pub fn settle_purge(&mut self, entry: &UndoEntry, result: DeleteResult) {
+        self.freed_size = self.freed_size.saturating_sub(entry.size) + result.size_freed;
+        if let Some(audit) = &entry.audit {
+            self.append_audit(&audit.clone().finish(&result));
+        }
+        if let (Some(err), Some(quarantined)) = (result.error, &entry.quarantined) {
+            self.add_error(format!(
+                "{}: purge failed: {err}",
+                quarantined.quarantined.display()
+            ));
+            self.delete_failures.extend(result.failures);
+        }
+    }
+
+    /// What the audit log needs to know about a result (`None` when logging is off)
+    fn audit_entry(&self, index: usize, size: u64) -> Option<AuditEntry> {
+        self.audit.as_ref()?;
+        let item = self.results.get(index)?;
+        let risk = if item.risk.is_sensitive {
+            RiskLevel::Sensitive
+        } else if self.is_active(index) {
+            RiskLevel::Active
+        } else {
+            RiskLevel::Safe
+        };
+        Some(AuditEntry::new(
+            &item.scan_result.path,
+            size,
+            risk,
+            self.dry_run,
+            self.delete_strategy,
+        ))
+    }
+
+    fn log_deletion(&mut self, index: usize, size: u64, result: &DeleteResult) {
+        if let Some(entry) = self.audit_entry(index, size) {
+            self.append_audit(&entry.finish(result));
+        }
+    }
+
+    fn log_archive(
+        &mut self,
+        index: usize,
+        size: u64,
+        result: &DeleteResult,
+        archive: &Path,
+        compressed_size: u64,
+    ) {
+        if let Some(entry) = self.audit_entry(index, size) {
+            self.append_audit(&entry.finish(result).archived_to(archive, compressed_size));
+        }
+    }
+
+    fn append_audit(&mut self, entry: &AuditEntry) {
+        if let Some(log) = &self.audit {
+            if let Err(e) = log.append(entry) {
+                let message = format!("{}: {e}", log.path().display());
+                self.add_error(message);
+            }
+        }
+    }
+
+    /// Reverts `mark_deleted` after an undo
+    pub fn mark_restored(&mut self, index: usize, size_freed: u64) {
+        if let Some(item) = self.results.get_mut(index) {
+            if item.is_deleted {
+                item.is_deleted = false;
+                self.freed_size = self.freed_size.saturating_sub(size_freed);
+            }
+        }
+        self.needs_filter = true;
+    }
+
+    /// Records an undoable deletion; returns the oldest entry once there are more than `undo_depth`
+    pub fn push_undo(&mut self, entry: UndoEntry) -> Option<UndoEntry> {
+        self.undo_stack.push_back(entry);
+        if self.undo_stack.len() > self.undo_depth {
+            self.undo_stack.pop_front()
+        } else {
+            None
+        }
+    }
+
+    /// Replaces the selection with `indices` (e.g., from an edited selection plan)
+    pub fn select_only(&mut self, indices: &[usize]) {
+        self.deselect_all();
+        for &idx in indices {
+            if let Some(item) = self.results.get_mut(idx) {
+                item.is_selected = true;
+                self.selected_indices.insert(idx);
+            }
+        }
+    }
+
+    /// Re-parses `search_query` after an edit. While it doesn't parse, the list keeps
+    /// the last valid search and the error is shown instead.
+    pub fn update_search(&mut self) {
+        match Query::parse_search(&self.search_query) {
+            Ok(search) => {
+                self.search = search;
+                self.search_error = None;
+            }
+            Err(e) => self.search_error = Some(e),
+        }
+        self.needs_filter = true;
+    }
+
+    /// Plans freeing `target` bytes now, or once scanning and sizing finish
+    pub fn request_free(&mut self, target: u64) {
+        self.mode = Mode::Normal;
+        if self.scanning || self.is_calculating_sizes() {
+            self.free_target = Some(target);
+        } else {
+            self.plan_free_space(target);
+        }
+    }
+
+    /// Picks folders in the current list that free `target` bytes, stalest and cheapest to
+    /// rebuild first, and shows the plan for review. Protected and active folders are never
+    /// picked, since deleting the selection would skip them anyway.
+    pub fn plan_free_space(&mut self, target: u64) {
+        let now = SystemTime::now();
+        let candidates: Vec<usize> = self
+            .filtered_indices
+            .iter()
+            .copied()
+            .filter(|&idx| {
+                let item = &self.results[idx];
+                item.scan_result.size.is_some()
+                    && !item.is_deleted
+                    && !item.is_deleting
+                    && !item.risk.is_sensitive
+                    && !self.is_active(idx)
+            })
+            .collect();
+        let items: Vec<BudgetItem> = candidates
+            .iter()
+            .map(|&idx| BudgetItem::for_result(&self.results[idx].scan_result, false, now))
+            .collect();
+
+        let plan = plan_to_free(&items, target);
+        self.budget = Some(BudgetPlan {
+            target,
+            steps: plan
+                .steps
+                .iter()
+                .map(|&(i, total)| (candidates[i], total))
+                .collect(),
+        });
+        self.budget_scroll = 0;
+        self.mode = Mode::FreePlan;
+    }
+
+    /// Moves the reviewed plan into the multi-select, where it can be adjusted and confirmed
+    pub fn accept_budget(&mut self) {
+        if let Some(plan) = self.budget.take() {
+            self.select_only(&plan.indices().collect::<Vec<_>>());
+            self.mode = Mode::MultiSelect;
+        }
+    }
+
+    /// Asks to confirm deleting the selection, or explains why protected items block it
+    pub fn confirm_selection(&mut self) {
+        if self.selected_indices.is_empty() {
+            return;
+        }
+        let sensitive_count = self.count_sensitive_in_selection();
+        if sensitive_count > 0 {
+            self.sensitive_blocked_count = sensitive_count;
+            self.mode = Mode::SensitiveBlocked;
+        } else {
+            self.mode = Mode::Confirm;
+        }
+    }
+
+    /// Removes an item from the selection without touching the rest
+    pub fn deselect(&mut self, index: usize) {
+        self.selected_indices.remove(&index);
+        if let Some(item) = self.results.get_mut(index) {
+            item.is_selected = false;
+        }
+    }
+
     pub fn mark_deleting(&mut self, index: usize) {
         if let Some(item) = self.results.get_mut(index) {
             item.is_deleting = true;
@@ -542,7 +1010,7 @@ impl App {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
+    use crate::delete::{ArchivedItem, DeleteError, DeleteProgress};
 
     // Helper to create an App with N items for cursor tests
     fn app_with_items(item_count: usize, visible_height: usize) -> App {
@@ -763,7 +1231,10 @@ mod tests {
             size,
             file_count: None,
             modified: None,
+            newest_modified: None,
             is_sensitive: false,
+            project_mark: None,
+            identity: None,
         }
     }
 
@@ -952,6 +1423,22 @@ mod tests {
         assert_eq!(app.active_tab, 0); // Back to All
     }
 
+    #[test]
+    fn test_size_filter_hides_items_until_sized() {
+        let mut app = App::new(false, SortOrder::Size, false);
+        app.filter.min_size = Some(100);
+        app.add_results(vec![
+            make_scan_result("/small", Some(10)),
+            make_scan_result("/big", Some(500)),
+            make_scan_result("/pending", None),
+        ]);
+        assert_eq!(app.filtered_indices, vec![1]);
+
+        app.update_size(2, 200, 1);
+        app.on_tick();
+        assert_eq!(app.filtered_indices, vec![1, 2]);
+    }
+
     #[test]
     fn test_tab_filters_to_target_type() {
         let mut app = App::new(false, SortOrder::Size, false);
@@ -1096,7 +1583,10 @@ mod tests {
             size: Some(100),
             file_count: None,
             modified: None,
-            is_sensitive: true, // This is just metadata; actual sensitivity is determined by analyze_risk
+            newest_modified: None,
+            is_sensitive: true, // This is just metadata; actual sensitivity is determined by analyze_scan_result
+            project_mark: None,
+            identity: None,
         }
     }
 
@@ -1111,7 +1601,10 @@ mod tests {
             size: Some(100),
             file_count: None,
             modified: None,
+            newest_modified: None,
             is_sensitive: false,
+            project_mark: None,
+            identity: None,
         }]);
 
         // Should be hidden (not added to results)
@@ -1129,7 +1622,10 @@ mod tests {
             size: Some(100),
             file_count: None,
             modified: None,
+            newest_modified: None,
             is_sensitive: false,
+            project_mark: None,
+            identity: None,
         }]);
 
         // Should be visible
@@ -1147,7 +1643,10 @@ mod tests {
             size: Some(100),
             file_count: None,
             modified: None,
+            newest_modified: None,
             is_sensitive: false,
+            project_mark: None,
+            identity: None,
         }]);
 
         // Should be visible (not sensitive)
@@ -1174,21 +1673,30 @@ mod tests {
                 size: Some(100),
                 file_count: None,
                 modified: None,
+                newest_modified: None,
                 is_sensitive: false,
+                project_mark: None,
+                identity: None,
             },
             ScanResult {
                 path: PathBuf::from("/usr/lib/node_modules"),
                 size: Some(200),
                 file_count: None,
                 modified: None,
+                newest_modified: None,
                 is_sensitive: false,
+                project_mark: None,
+                identity: None,
             },
             ScanResult {
                 path: PathBuf::from("/var/cache/something"),
                 size: Some(300),
                 file_count: None,
                 modified: None,
+                newest_modified: None,
                 is_sensitive: false,
+                project_mark: None,
+                identity: None,
             },
         ]);
 
@@ -1199,6 +1707,74 @@ mod tests {
         assert_eq!(app.count_sensitive_in_selection(), 2);
     }
 
+    #[test]
+    fn test_select_only_replaces_selection_and_confirms() {
+        let mut app = App::new(true, SortOrder::Size, false);
+        app.add_results(vec![
+            ScanResult::new(PathBuf::from("/home/user/a/node_modules")),
+            ScanResult::new(PathBuf::from("/home/user/b/node_modules")),
+            ScanResult::new(PathBuf::from("/usr/lib/node_modules")),
+        ]);
+        app.select_all();
+
+        app.select_only(&[1, 7]);
+        assert_eq!(app.selected_indices, HashSet::from([1]));
+        assert!(!app.results[0].is_selected && app.results[1].is_selected);
+        app.confirm_selection();
+        assert_eq!(app.mode, Mode::Confirm);
+
+        // Protected folders still block the batch
+        app.mode = Mode::MultiSelect;
+        app.select_only(&[1, 2]);
+        app.confirm_selection();
+        assert_eq!(app.mode, Mode::SensitiveBlocked);
+        assert_eq!(app.sensitive_blocked_count, 1);
+    }
+
+    #[test]
+    fn test_plan_free_space_skips_protected_and_active() {
+        let mut app = App::new(true, SortOrder::Path, false);
+        app.active_window = Some(Duration::from_mins(10));
+        let old = SystemTime::UNIX_EPOCH;
+        let mut results = vec![
+            make_modified_scan_result("/home/user/a/node_modules", Some(old)),
+            make_modified_scan_result("/home/user/b/node_modules", Some(SystemTime::now())),
+            make_modified_scan_result("/home/user/c/__pycache__", Some(old)),
+            make_modified_scan_result("/usr/lib/node_modules", Some(old)),
+        ];
+        results[3].size = Some(10_000);
+        app.add_results(results);
+
+        app.plan_free_space(150);
+        assert_eq!(app.mode, Mode::FreePlan);
+        let plan = app.budget.clone().unwrap();
+        // Equally stale, so the cheaper cache goes first
+        assert_eq!(plan.steps, vec![(2, 100), (0, 200)]);
+
+        app.accept_budget();
+        assert_eq!(app.mode, Mode::MultiSelect);
+        assert_eq!(app.selected_indices, HashSet::from([0, 2]));
+        assert!(app.budget.is_none());
+    }
+
+    #[test]
+    fn test_request_free_waits_for_sizes() {
+        let mut app = App::new(false, SortOrder::Size, false);
+        app.add_results(vec![make_scan_result("/home/user/a/node_modules", None)]);
+
+        app.request_free(50);
+        assert_eq!(app.mode, Mode::Normal);
+        app.on_tick();
+        assert!(app.budget.is_none());
+
+        app.scan_complete();
+        app.update_size(0, 100, 1);
+        app.on_tick();
+        assert_eq!(app.mode, Mode::FreePlan);
+        assert!(app.budget.as_ref().is_some_and(BudgetPlan::reached));
+        assert!(app.free_target.is_none());
+    }
+
     #[test]
     fn test_count_sensitive_in_selection_none_sensitive() {
         let mut app = App::new(true, SortOrder::Size, false);
@@ -1209,14 +1785,20 @@ mod tests {
                 size: Some(100),
                 file_count: None,
                 modified: None,
+                newest_modified: None,
                 is_sensitive: false,
+                project_mark: None,
+                identity: None,
             },
             ScanResult {
                 path: PathBuf::from("/home/user/project2/node_modules"),
                 size: Some(200),
                 file_count: None,
                 modified: None,
+                newest_modified: None,
                 is_sensitive: false,
+                project_mark: None,
+                identity: None,
             },
         ]);
 
@@ -1226,4 +1808,378 @@ mod tests {
 
         assert_eq!(app.count_sensitive_in_selection(), 0);
     }
+
+    // === Active-build guard tests ===
+
+    fn make_modified_scan_result(path: &str, newest_modified: Option<SystemTime>) -> ScanResult {
+        ScanResult {
+            path: PathBuf::from(path),
+            size: Some(100),
+            file_count: None,
+            modified: Some(SystemTime::UNIX_EPOCH),
+            newest_modified,
+            is_sensitive: false,
+            project_mark: None,
+            identity: None,
+        }
+    }
+
+    #[test]
+    fn test_item_active_when_recently_modified() {
+        let now = SystemTime::now();
+        let item = ResultItem::from_scan_result(make_modified_scan_result(
+            "/home/user/projects/app/target",
+            Some(now - Duration::from_mins(1)),
+        ));
+
+        assert!(item.is_active(Duration::from_mins(10), now));
+        assert!(!item.is_active(Duration::from_secs(30), now));
+    }
+
+    #[test]
+    fn test_item_not_active_when_newest_unknown() {
+        let item = ResultItem::from_scan_result(make_modified_scan_result(
+            "/home/user/projects/app/target",
+            None,
+        ));
+
+        assert!(!item.is_active(Duration::from_mins(10), SystemTime::now()));
+    }
+
+    #[test]
+    fn test_app_is_active_disabled_without_window() {
+        let mut app = App::new(false, SortOrder::Size, false);
+        app.add_results(vec![make_modified_scan_result(
+            "/home/user/projects/app/.next",
+            Some(SystemTime::now()),
+        )]);
+
+        assert!(!app.is_active(0));
+
+        app.active_window = Some(Duration::from_mins(10));
+        assert!(app.is_active(0));
+    }
+
+    #[test]
+    fn test_count_active_in_selection() {
+        let mut app = App::new(false, SortOrder::Size, false);
+        app.active_window = Some(Duration::from_mins(10));
+        app.add_results(vec![
+            make_modified_scan_result("/home/user/a/target", Some(SystemTime::now())),
+            make_modified_scan_result("/home/user/b/target", Some(SystemTime::UNIX_EPOCH)),
+        ]);
+        app.selected_indices.insert(0);
+        app.selected_indices.insert(1);
+
+        assert_eq!(app.count_active_in_selection(), 1);
+    }
+
+    #[test]
+    fn test_update_newest_modified_sets_field() {
+        let mut app = App::new(false, SortOrder::Size, false);
+        app.add_results(vec![make_scan_result("/a", None)]);
+        let now = SystemTime::now();
+
+        app.update_newest_modified(0, Some(now));
+
+        assert_eq!(app.results[0].scan_result.newest_modified, Some(now));
+    }
+
+    // === Undo ===
+
+    fn undo_entry(index: usize, size: u64) -> UndoEntry {
+        UndoEntry {
+            index,
+            size,
+            quarantined: None,
+            audit: None,
+        }
+    }
+
+    #[test]
+    fn test_mark_restored_reverts_deletion() {
+        let mut app = App::new(false, SortOrder::Size, false);
+        app.add_results(vec![make_scan_result("/a/node_modules", Some(100))]);
+        app.mark_deleted(0, 100);
+
+        app.mark_restored(0, 100);
+
+        assert!(!app.results[0].is_deleted);
+        assert_eq!(app.freed_size, 0);
+    }
+
+    #[test]
+    fn test_mark_restored_ignores_live_item() {
+        let mut app = App::new(false, SortOrder::Size, false);
+        app.add_results(vec![
+            make_scan_result("/a/node_modules", Some(100)),
+            make_scan_result("/b/node_modules", Some(200)),
+        ]);
+        app.mark_deleted(1, 200);
+
+        app.mark_restored(0, 100);
+
+        assert_eq!(app.freed_size, 200);
+    }
+
+    #[test]
+    fn test_push_undo_evicts_oldest_beyond_depth() {
+        let mut app = App::new(false, SortOrder::Size, false);
+        app.undo_depth = 2;
+
+        assert!(app.push_undo(undo_entry(0, 10)).is_none());
+        assert!(app.push_undo(undo_entry(1, 20)).is_none());
+        let evicted = app.push_undo(undo_entry(2, 30)).unwrap();
+
+        assert_eq!(evicted.index, 0);
+        let remaining: Vec<usize> = app.undo_stack.iter().map(|e| e.index).collect();
+        assert_eq!(remaining, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_push_undo_with_zero_depth_returns_entry() {
+        let mut app = App::new(false, SortOrder::Size, false);
+        let evicted = app.push_undo(undo_entry(0, 10)).unwrap();
+        assert_eq!(evicted.index, 0);
+        assert!(app.undo_stack.is_empty());
+    }
+
+    // === Deletion queue ===
+
+    #[test]
+    fn test_queue_delete_marks_deleting_once() {
+        let mut app = App::new(false, SortOrder::Size, false);
+        app.add_results(vec![make_scan_result("/a/node_modules", Some(100))]);
+
+        assert!(app.queue_delete(0).is_some());
+        assert!(app.results[0].is_deleting);
+        assert!(app.queue_delete(0).is_none(), "already queued");
+    }
+
+    #[test]
+    fn test_job_quarantined_marks_deleted_and_undoable() {
+        let mut app = App::new(false, SortOrder::Size, false);
+        app.undo_depth = 5;
+        app.add_results(vec![make_scan_result("/a/node_modules", Some(100))]);
+        let job = app.queue_delete(0).unwrap();
+
+        app.apply_job_event(JobEvent::Started(job.id));
+        assert_eq!(app.delete_queue.jobs[0].status, JobStatus::Running);
+
+        let quarantined = Quarantined {
+            original: PathBuf::from("/a/node_modules"),
+            quarantined: PathBuf::from("/a/.cache-sweep-deleted.1.1.node_modules"),
+        };
+        let evicted = app.apply_job_event(JobEvent::Finished(
+            job.id,
+            JobOutcome::Quarantined(quarantined),
+        ));
+
+        assert!(evicted.is_none());
+        assert!(app.results[0].is_deleted);
+        assert_eq!(app.freed_size, 100);
+        assert_eq!(app.undo_stack.len(), 1);
+        assert_eq!(app.delete_queue.jobs[0].status, JobStatus::Done);
+    }
+
+    #[test]
+    fn test_evicted_undo_entry_is_purged_through_the_queue() {
+        let mut app = App::new(false, SortOrder::Size, false);
+        app.undo_depth = 0;
+        app.add_results(vec![make_scan_result("/a/node_modules", Some(100))]);
+        let job = app.queue_delete(0).unwrap();
+        let quarantined = Quarantined {
+            original: PathBuf::from("/a/node_modules"),
+            quarantined: PathBuf::from("/a/.cache-sweep-deleted.1.1.node_modules"),
+        };
+
+        let purge = app
+            .apply_job_event(JobEvent::Finished(
+                job.id,
+                JobOutcome::Quarantined(quarantined.clone()),
+            ))
+            .expect("nothing to undo with, so it's purged right away");
+        assert_eq!(purge.kind, JobKind::Purge);
+        assert_eq!(purge.path, quarantined.quarantined);
+        assert!(
+            app.delete_queue.has_pending(),
+            "the purge shows in the queue"
+        );
+        assert!(app.undo_stack.is_empty());
+
+        app.apply_job_event(JobEvent::Started(purge.id));
+        let result = DeleteResult {
+            success: true,
+            size_freed: 60,
+            error: None,
+            failures: Vec::new(),
+        };
+        app.apply_job_event(JobEvent::Finished(purge.id, JobOutcome::Removed(result)));
+
+        assert!(!app.delete_queue.has_pending());
+        assert_eq!(app.freed_size, 60, "counted at what the purge freed");
+        assert!(app.results[0].is_deleted);
+        assert!(app.errors.is_empty());
+    }
+
+    #[test]
+    fn test_job_failed_records_error_and_unmarks() {
+        let mut app = App::new(false, SortOrder::Size, false);
+        app.add_results(vec![make_scan_result("/a/node_modules", Some(100))]);
+        let job = app.queue_delete(0).unwrap();
+
+        app.apply_job_event(JobEvent::Finished(
+            job.id,
+            JobOutcome::Failed(DeleteResult::failed("Permission denied".to_string())),
+        ));
+
+        assert!(!app.results[0].is_deleting);
+        assert!(!app.results[0].is_deleted);
+        assert_eq!(app.errors.len(), 1);
+        assert!(matches!(
+            app.delete_queue.jobs[0].status,
+            JobStatus::Failed(_)
+        ));
+    }
+
+    #[test]
+    fn test_job_failed_keeps_paths_left_behind() {
+        let mut app = App::new(false, SortOrder::Size, false);
+        app.add_results(vec![make_scan_result("/a/node_modules", Some(100))]);
+        let job = app.queue_delete(0).unwrap();
+        let left = DeleteFailure {
+            path: PathBuf::from("/a/node_modules/.bin"),
+            error: DeleteError::PermissionDenied("/a/node_modules/.bin".to_string()),
+        };
+
+        app.apply_job_event(JobEvent::Finished(
+            job.id,
+            JobOutcome::Failed(DeleteResult {
+                success: false,
+                size_freed: 60,
+                error: Some("Permission denied".to_string()),
+                failures: vec![left.clone()],
+            }),
+        ));
+
+        assert_eq!(app.delete_failures, vec![left]);
+        assert_eq!(app.errors.len(), 1);
+        assert_eq!(app.freed_size, 60, "the part that was removed still counts");
+    }
+
+    #[test]
+    fn test_job_removed_counts_measured_bytes() {
+        let mut app = App::new(false, SortOrder::Size, false);
+        app.add_results(vec![make_scan_result("/a/node_modules", Some(100))]);
+        let job = app.queue_delete(0).unwrap();
+
+        let mut result = DeleteResult::failed(String::new());
+        (result.success, result.error, result.size_freed) = (true, None, 70);
+        app.apply_job_event(JobEvent::Finished(job.id, JobOutcome::Removed(result)));
+
+        assert!(app.results[0].is_deleted);
+        assert_eq!(app.freed_size, 70);
+    }
+
+    fn with_archive(mut app: App) -> App {
+        app.archive = Some(Archive::new(
+            PathBuf::from("/archives"),
+            PathBuf::from("/state/archives.jsonl"),
+        ));
+        app
+    }
+
+    #[test]
+    fn test_job_archived_counts_net_savings() {
+        let mut app = with_archive(App::new(false, SortOrder::Size, false));
+        app.add_results(vec![make_scan_result("/a/.venv", Some(100))]);
+        let job = app.queue_archive(0).unwrap();
+        assert_eq!(job.kind, JobKind::Archive);
+
+        let mut result = DeleteResult::failed(String::new());
+        (result.success, result.error, result.size_freed) = (true, None, 100);
+        let item = ArchivedItem {
+            archive: PathBuf::from("/archives/a-.venv-1.tar.gz"),
+            original_path: PathBuf::from("/a/.venv"),
+            archived_at: 0,
+            entries: 3,
+            size: 100,
+            compressed_size: 30,
+        };
+        app.apply_job_event(JobEvent::Finished(
+            job.id,
+            JobOutcome::Archived(result, item),
+        ));
+
+        assert!(app.results[0].is_deleted);
+        assert_eq!(app.freed_size, 70, "the tarball still takes space");
+        assert!(
+            app.undo_stack.is_empty(),
+            "restore, not undo, brings it back"
+        );
+        assert_eq!(app.delete_queue.jobs[0].status, JobStatus::Done);
+    }
+
+    #[test]
+    fn test_dry_run_archive_counts_estimated_savings() {
+        let mut app = with_archive(App::new(false, SortOrder::Size, true));
+        app.add_results(vec![make_scan_result("/a/.venv", Some(1000))]);
+        let job = app.queue_archive(0).unwrap();
+
+        let mut result = DeleteResult::failed(String::new());
+        (result.success, result.error) = (true, None);
+        app.apply_job_event(JobEvent::Finished(job.id, JobOutcome::Removed(result)));
+
+        assert!(app.results[0].is_deleted);
+        assert_eq!(app.freed_size, 650);
+    }
+
+    #[test]
+    fn test_queue_archive_needs_archive_dir() {
+        let mut app = App::new(false, SortOrder::Size, false);
+        app.add_results(vec![make_scan_result("/a/.venv", Some(100))]);
+
+        assert!(app.queue_archive(0).is_none());
+        assert!(app.archive_path(0).is_none());
+        assert_eq!(app.errors.len(), 1);
+        assert!(!app.results[0].is_deleting);
+    }
+
+    #[test]
+    fn test_settle_purge_replaces_estimate() {
+        let mut app = App::new(false, SortOrder::Size, false);
+        app.add_results(vec![make_scan_result("/a/node_modules", Some(100))]);
+        app.mark_deleted(0, 100);
+        let entry = UndoEntry {
+            index: 0,
+            size: 100,
+            quarantined: Some(Quarantined {
+                original: PathBuf::from("/a/node_modules"),
+                quarantined: PathBuf::from("/a/.cache-sweep-deleted.1.1.node_modules"),
+            }),
+            audit: None,
+        };
+        let mut result = DeleteResult::failed("Permission denied: /a/x".to_string());
+        result.size_freed = 40;
+
+        app.settle_purge(&entry, result);
+
+        assert_eq!(app.freed_size, 40);
+        assert_eq!(app.errors.len(), 1);
+    }
+
+    #[test]
+    fn test_job_progress_updates_queue() {
+        let mut app = App::new(false, SortOrder::Size, false);
+        app.add_results(vec![make_scan_result("/a/node_modules", Some(100))]);
+        let job = app.queue_delete(0).unwrap();
+        let progress = DeleteProgress {
+            files: 3,
+            bytes: 40,
+        };
+
+        app.apply_job_event(JobEvent::Progress(job.id, progress));
+
+        assert_eq!(app.delete_queue.jobs[0].progress, progress);
+    }
 }
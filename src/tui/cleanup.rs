@@ -21,6 +21,20 @@ impl TerminalCleanupGuard {
     }
 }
 
+/// Hands the terminal to `run` (e.g., an editor), then takes it back and redraws from scratch
+pub fn suspend<T>(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    run: impl FnOnce() -> T,
+) -> io::Result<T> {
+    disable_raw_mode()?;
+    execute!(stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+    let result = run();
+    enable_raw_mode()?;
+    execute!(stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+    terminal.clear()?;
+    Ok(result)
+}
+
 impl Drop for TerminalCleanupGuard {
     fn drop(&mut self) {
         // Always restore terminal, even on panic
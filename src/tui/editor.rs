@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Write as _};
+use std::path::Path;
+use std::process::Command;
+
+use bytesize::ByteSize;
+
+use super::app::App;
+
+const HEADER: &str = "\
+# cache-sweep selection: one folder per line, `keep` or `delete` (k/d for short).
+# Lines are `<action> [<size>] <path>`; only the action needs changing.
+# Removed lines are kept. Save and quit to review the selection, or exit the
+# editor with an error (`:cq` in vim) to leave the selection as it was.
+";
+
+/// The folders an edit can choose from: the current (filtered) list, minus what's
+/// already deleted or being deleted
+fn candidates(app: &App) -> impl Iterator<Item = usize> + '_ {
+    app.filtered_indices.iter().copied().filter(|&idx| {
+        app.results
+            .get(idx)
+            .is_some_and(|item| !item.is_deleted && !item.is_deleting)
+    })
+}
+
+/// Each candidate with the name its plan line uses. Folders whose displayed path has a
+/// line break, or displays the same as another's (non-UTF-8 names), get `None`: a line
+/// can't name them unambiguously, so they're always kept.
+fn plan_names(app: &App) -> Vec<(usize, Option<String>)> {
+    let names: Vec<(usize, String)> = candidates(app)
+        .map(|idx| (idx, app.results[idx].scan_result.path.display().to_string()))
+        .collect();
+    let mut uses: HashMap<&str, usize> = HashMap::new();
+    for (_, name) in &names {
+        *uses.entry(name).or_default() += 1;
+    }
+    names
+        .iter()
+        .map(|(idx, name)| {
+            let usable = uses[name.as_str()] == 1 && !name.contains(['\n', '\r']);
+            (*idx, usable.then(|| name.clone()))
+        })
+        .collect()
+}
+
+/// Writes the current list as a `git rebase -i` style plan (selected folders start as `delete`)
+#[allow(clippy::unnecessary_debug_formatting)] // Unusable names are shown escaped on purpose
+pub fn write_plan(app: &App) -> String {
+    let mut plan = HEADER.to_string();
+    plan.push('\n');
+    for (idx, name) in plan_names(app) {
+        let item = &app.results[idx];
+        let action = if app.selected_indices.contains(&idx) {
+            "delete"
+        } else {
+            "keep  "
+        };
+        let size = item
+            .scan_result
+            .size
+            .map_or_else(|| "?".to_string(), |size| ByteSize::b(size).to_string());
+        let _ = match name {
+            Some(name) => write!(plan, "{action} [{size:>10}] {name}"),
+            None => write!(
+                plan,
+                "# keep [{size:>10}] {:?} (its name can't be edited here)",
+                item.scan_result.path
+            ),
+        };
+        if let Some(reason) = item.risk.reason.as_ref().filter(|_| item.risk.is_sensitive) {
+            let _ = write!(plan, "\n# ^ protected: {reason}");
+        }
+        plan.push('\n');
+    }
+    plan
+}
+
+/// Reads an edited plan back; returns the folders marked `delete`
+pub fn parse_plan(text: &str, app: &App) -> Result<Vec<usize>, String> {
+    let by_path: HashMap<String, usize> = plan_names(app)
+        .into_iter()
+        .filter_map(|(idx, name)| Some((name?, idx)))
+        .collect();
+
+    let mut delete = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let at = |message: String| format!("line {}: {message}", number + 1);
+
+        let (action, rest) = line.trim_start().split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim_start();
+        // The size column is informational; allow it to be left out
+        let path = rest
+            .strip_prefix('[')
+            .map_or(rest, |sized| {
+                sized.split_once(']').map_or(sized, |(_, path)| path)
+            })
+            .trim_start();
+        let idx = *by_path
+            .get(path)
+            .ok_or_else(|| at(format!("{path:?} is not in the list")))?;
+
+        match action {
+            "keep" | "k" => {}
+            "delete" | "d" => delete.push(idx),
+            _ => {
+                return Err(at(format!(
+                    "unknown action {action:?} (use keep or delete)"
+                )))
+            }
+        }
+    }
+    Ok(delete)
+}
+
+/// Opens `plan` in the user's editor and returns the saved text. Blocks until the editor exits.
+pub fn edit(plan: &str) -> io::Result<String> {
+    let path =
+        std::env::temp_dir().join(format!("cache-sweep-selection-{}.txt", std::process::id()));
+    let _ = fs::remove_file(&path); // Left over from a crash; never follow what's there
+    create_private(&path)?.write_all(plan.as_bytes())?;
+
+    let edited = run_editor(&path).and_then(|()| fs::read_to_string(&path));
+    let _ = fs::remove_file(&path);
+    edited
+}
+
+/// `$VISUAL`, then `$EDITOR`, then the platform's basic editor
+fn editor() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| {
+            if cfg!(windows) {
+                "notepad".to_string()
+            } else {
+                "vi".to_string()
+            }
+        })
+}
+
+/// Runs the editor through the shell, like git does, so `EDITOR="code --wait"` works
+#[cfg(unix)]
+fn run_editor(path: &Path) -> io::Result<()> {
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor()))
+        .arg("sh")
+        .arg(path)
+        .status()?;
+    check_status(status)
+}
+
+#[cfg(not(unix))]
+fn run_editor(path: &Path) -> io::Result<()> {
+    let editor = editor();
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("notepad");
+    let status = Command::new(program).args(words).arg(path).status()?;
+    check_status(status)
+}
+
+fn check_status(status: std::process::ExitStatus) -> io::Result<()> {
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("editor exited with {status}")))
+    }
+}
+
+/// Creates a new file only the current user can read (the plan lists their folders)
+fn create_private(path: &Path) -> io::Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::ScanResult;
+    use crate::tui::app::SortOrder;
+    use std::path::PathBuf;
+
+    fn app_with(paths: &[&str]) -> App {
+        let mut app = App::new(true, SortOrder::Path, false);
+        app.add_results(
+            paths
+                .iter()
+                .map(|path| {
+                    let mut result = ScanResult::new(PathBuf::from(path));
+                    result.size = Some(2048);
+                    result
+                })
+                .collect(),
+        );
+        app.filtered_indices = (0..paths.len()).collect();
+        app
+    }
+
+    #[test]
+    fn test_plan_marks_selected_folders_delete() {
+        let mut app = app_with(&["/code/a/node_modules", "/code/b b/target"]);
+        app.selected_indices.insert(1);
+
+        let plan = write_plan(&app);
+
+        assert!(plan.starts_with("# cache-sweep selection"));
+        assert!(plan.contains("\nkeep   [   2.0 KiB] /code/a/node_modules\n"));
+        assert!(plan.contains("\ndelete [   2.0 KiB] /code/b b/target\n"));
+    }
+
+    #[test]
+    fn test_parse_round_trips_and_accepts_edits() {
+        let app = app_with(&["/code/a/node_modules", "/code/b b/target", "/code/c/.venv"]);
+        let plan = write_plan(&app)
+            .replace(
+                "keep   [   2.0 KiB] /code/b b/target",
+                "d [2.0 KiB] /code/b b/target",
+            )
+            .replace(
+                "keep   [   2.0 KiB] /code/c/.venv",
+                "delete /code/c/.venv\r",
+            );
+
+        assert_eq!(parse_plan(&write_plan(&app), &app), Ok(vec![]));
+        assert_eq!(parse_plan(&plan, &app), Ok(vec![1, 2]));
+        // Dropping a line keeps the folder
+        assert_eq!(parse_plan("delete /code/c/.venv", &app), Ok(vec![2]));
+    }
+
+    #[test]
+    fn test_parse_reports_bad_lines() {
+        let app = app_with(&["/code/a/node_modules"]);
+
+        assert_eq!(
+            parse_plan("# header\n\nremove /code/a/node_modules", &app),
+            Err("line 3: unknown action \"remove\" (use keep or delete)".to_string())
+        );
+        assert_eq!(
+            parse_plan("delete /code/z/node_modules", &app),
+            Err("line 1: \"/code/z/node_modules\" is not in the list".to_string())
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_names_are_always_kept() {
+        let mut app = app_with(&["/code/a/node_modules", "/code/b\nc/node_modules"]);
+        app.selected_indices.insert(1);
+
+        let plan = write_plan(&app);
+
+        assert!(plan.contains(
+            "\n# keep [   2.0 KiB] \"/code/b\\nc/node_modules\" (its name can't be edited here)\n"
+        ));
+        assert!(!plan.contains("\ndelete "));
+        assert_eq!(parse_plan(&plan, &app), Ok(vec![]));
+        // Neither half of the split name refers to it
+        assert!(parse_plan("delete /code/b", &app).is_err());
+        assert!(parse_plan("delete c/node_modules", &app).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_names_that_display_alike_are_always_kept() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut app = app_with(&["/code/a/node_modules"]);
+        app.add_results(
+            [&b"/code/\xff/node_modules"[..], b"/code/\xfe/node_modules"]
+                .iter()
+                .map(|bytes| ScanResult::new(PathBuf::from(OsStr::from_bytes(bytes))))
+                .collect(),
+        );
+        app.filtered_indices = vec![0, 1, 2];
+
+        let plan = write_plan(&app);
+
+        assert!(plan.contains("\\xFF/node_modules\" (its name can't be edited here)"));
+        assert!(plan.contains("\\xFE/node_modules\" (its name can't be edited here)"));
+        assert!(parse_plan("delete /code/\u{FFFD}/node_modules", &app).is_err());
+        assert_eq!(parse_plan("delete /code/a/node_modules", &app), Ok(vec![0]));
+    }
+
+    #[test]
+    fn test_deleted_folders_are_left_out() {
+        let mut app = app_with(&["/code/a/node_modules", "/code/b/node_modules"]);
+        app.results[0].is_deleted = true;
+
+        assert!(!write_plan(&app).contains("/code/a/"));
+        assert!(parse_plan("delete /code/a/node_modules", &app).is_err());
+    }
+}
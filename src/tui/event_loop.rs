@@ -1,56 +1,89 @@
+use std::io;
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Result;
-use crossterm::event::{Event, EventStream};
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyModifiers};
 use futures::StreamExt;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, Semaphore};
 use tokio_util::sync::CancellationToken;
 
-use super::app::{App, Mode, SortOrder};
-use super::cleanup::TerminalCleanupGuard;
+use super::app::{App, Mode, Panel, SortOrder, UndoEntry};
+use super::cleanup::{suspend, TerminalCleanupGuard};
+use super::editor;
 use super::input::{handle_key, Action};
+use super::queue::{DeleteJob, JobEvent, JobKind, JobOutcome};
 use super::ui;
 use crate::cli::Args;
-use crate::delete::delete_directory;
-use crate::scanner::{calculate_size, start_scan};
+use crate::delete::{
+    archive_directory, purge_directory, quarantine_directory, remove_directory, Archive, AuditLog,
+    DeleteResult, DeleteStrategy, DELETE_CONCURRENCY, QUARANTINE_PREFIX,
+};
+use crate::scanner::{calculate_size, start_scan, DirStats, ScanErrors};
 
 enum Command {
     Delete(usize),
     DeleteBatch(Vec<usize>),
+    Archive(usize),
 }
 
+/// Runs the TUI; returns the errors from finishing deletions and purges after quitting,
+/// which the user would otherwise never see
 #[allow(clippy::too_many_lines)] // Event loop is inherently complex; splitting would obscure flow
-pub async fn run(args: &Args, cancel_token: CancellationToken) -> Result<()> {
+pub async fn run(args: &Args, cancel_token: CancellationToken) -> Result<Vec<String>> {
     let (_guard, mut terminal) = TerminalCleanupGuard::new()?;
     let sort_order = SortOrder::from_str(&args.sort);
-    let mut app = App::new(args.show_protected, sort_order, args.dry_run);
+    let mut app = App::new(args.scan.show_protected, sort_order, args.dry_run);
+    app.active_window = args.scan.active_window();
+    app.filter = args.scan.filter();
+    if let Some(query) = &args.scan.query {
+        app.search_query = query.source().to_string();
+        app.search = query.clone();
+    }
+    app.delete_strategy = args.strategy.delete_strategy();
+    app.undo_depth = args.undo_depth;
+    app.audit = AuditLog::from_env().ok();
+    app.archive = Archive::from_env(args.archive_dir.as_deref()).ok();
+    app.free_target = args.free;
 
     // Set visible height based on terminal
     app.visible_height = terminal.size()?.height.saturating_sub(8) as usize;
 
     // Start scanner
-    let root = args.effective_directory();
-    let targets = args.effective_targets();
-    let excludes = args.exclude.clone().unwrap_or_default();
+    let root = args.scan.effective_directory();
+    let targets = args.scan.effective_targets();
+    let excludes = args.scan.exclude.clone().unwrap_or_default();
     let mut result_rx = start_scan(
         root,
         targets,
         excludes,
-        args.follow_links,
-        args.respect_ignore,
+        args.scan.follow_links,
+        args.scan.respect_ignore,
+        ScanErrors::default(),
         cancel_token.clone(),
     );
 
     // Command channel for deletions
     let (cmd_tx, mut cmd_rx) = mpsc::channel::<Command>(10);
 
-    // Size calculation queue: (index, size, file_count)
-    let (size_tx, mut size_rx) = mpsc::unbounded_channel::<(usize, u64, u64)>();
+    // Size calculation queue: (index, stats)
+    let (size_tx, mut size_rx) = mpsc::unbounded_channel::<(usize, DirStats)>();
 
     let mut event_stream = EventStream::new();
     let mut tick = tokio::time::interval(Duration::from_millis(100));
 
-    let dry_run = args.dry_run;
+    // Deletion workers report back through this channel
+    let (job_tx, mut job_rx) = mpsc::unbounded_channel::<JobEvent>();
+    let workers = DeleteWorkers {
+        strategy: args.strategy.delete_strategy(),
+        dry_run: args.dry_run,
+        quarantine: args.undo_depth > 0,
+        archive: app.archive.clone(),
+        permits: Arc::new(Semaphore::new(DELETE_CONCURRENCY)),
+        events: job_tx,
+    };
+    // Set by a key; handled outside `select!` since the event stream must be dropped
+    let mut edit_selection = false;
 
     loop {
         terminal.draw(|f| ui::draw(f, &mut app))?;
@@ -65,18 +98,38 @@ pub async fn run(args: &Args, cancel_token: CancellationToken) -> Result<()> {
                     Some(Ok(Event::Key(key))) => {
                         match handle_key(key, &mut app) {
                             Action::Quit => break,
-                            Action::Delete => {
+                            action @ (Action::Delete | Action::Archive) => {
+                                let archive = action == Action::Archive;
                                 if let Some(idx) = app.current_index() {
                                     if let Some(item) = app.results.get(idx) {
                                         if item.risk.is_sensitive {
                                             // Block deletion of sensitive directories
                                             app.mode = Mode::SensitiveBlocked;
                                         } else if !item.is_deleted && !item.is_deleting {
-                                            cmd_tx.send(Command::Delete(idx)).await.ok();
+                                            if app.is_active(idx) {
+                                                // A build may still be writing here - ask first
+                                                app.pending_active_delete = Some(idx);
+                                                app.pending_active_archive = archive;
+                                                app.mode = Mode::ActiveConfirm;
+                                            } else if archive {
+                                                cmd_tx.send(Command::Archive(idx)).await.ok();
+                                            } else {
+                                                cmd_tx.send(Command::Delete(idx)).await.ok();
+                                            }
                                         }
                                     }
                                 }
                             }
+                            Action::DeleteActive => {
+                                if let Some(idx) = app.pending_active_delete.take() {
+                                    let command = if std::mem::take(&mut app.pending_active_archive) {
+                                        Command::Archive(idx)
+                                    } else {
+                                        Command::Delete(idx)
+                                    };
+                                    cmd_tx.send(command).await.ok();
+                                }
+                            }
                             Action::DeleteSelected => {
                                 let indices: Vec<usize> = app.selected_indices.iter().copied().collect();
                                 // Check if any selected items are sensitive
@@ -85,8 +138,21 @@ pub async fn run(args: &Args, cancel_token: CancellationToken) -> Result<()> {
                                 });
                                 if has_sensitive {
                                     app.mode = Mode::SensitiveBlocked;
-                                } else if !indices.is_empty() {
-                                    cmd_tx.send(Command::DeleteBatch(indices)).await.ok();
+                                } else {
+                                    // Batch delete never touches active folders; drop them from the selection
+                                    let (active, indices): (Vec<usize>, Vec<usize>) =
+                                        indices.into_iter().partition(|&idx| app.is_active(idx));
+                                    for idx in active {
+                                        app.deselect(idx);
+                                    }
+                                    if !indices.is_empty() {
+                                        cmd_tx.send(Command::DeleteBatch(indices)).await.ok();
+                                    }
+                                }
+                            }
+                            Action::Undo => {
+                                if let Some(entry) = app.undo_stack.pop_back() {
+                                    undo_delete(&mut app, entry, &workers).await;
                                 }
                             }
                             Action::OpenInExplorer => {
@@ -102,6 +168,7 @@ pub async fn run(args: &Args, cancel_token: CancellationToken) -> Result<()> {
                                     }
                                 }
                             }
+                            Action::EditSelection => edit_selection = true,
                             Action::Continue => {}
                         }
                     }
@@ -113,47 +180,33 @@ pub async fn run(args: &Args, cancel_token: CancellationToken) -> Result<()> {
             }
 
             // Size updates
-            Some((idx, size, file_count)) = size_rx.recv() => {
-                app.update_size(idx, size, file_count);
+            Some((idx, stats)) = size_rx.recv() => {
+                app.update_size(idx, stats.size, stats.file_count);
+                app.update_newest_modified(idx, stats.newest_modified);
                 // Check if all sizes are now calculated
                 if !app.scanning && !app.is_calculating_sizes() {
                     app.sizes_complete();
                 }
             }
 
-            // Deletion commands
+            // Deletion commands: queue them and return to the UI right away
             Some(cmd) = cmd_rx.recv() => {
-                match cmd {
-                    Command::Delete(idx) => {
-                        if let Some(item) = app.results.get(idx) {
-                            let path = item.scan_result.path.clone();
-                            let size = item.scan_result.size.unwrap_or(0);
-                            app.mark_deleting(idx);
-
-                            let result = delete_directory(&path, dry_run).await;
-                            if result.success {
-                                app.mark_deleted(idx, size);
-                            } else if let Some(err) = result.error {
-                                app.add_error(format!("{}: {}", path.display(), err));
-                            }
-                        }
-                    }
+                let jobs = match cmd {
+                    Command::Delete(idx) => vec![app.queue_delete(idx)],
                     Command::DeleteBatch(indices) => {
-                        for idx in indices {
-                            if let Some(item) = app.results.get(idx) {
-                                let path = item.scan_result.path.clone();
-                                let size = item.scan_result.size.unwrap_or(0);
-                                app.mark_deleting(idx);
-
-                                let result = delete_directory(&path, dry_run).await;
-                                if result.success {
-                                    app.mark_deleted(idx, size);
-                                } else if let Some(err) = result.error {
-                                    app.add_error(format!("{}: {}", path.display(), err));
-                                }
-                            }
-                        }
+                        indices.into_iter().map(|idx| app.queue_delete(idx)).collect()
                     }
+                    Command::Archive(idx) => vec![app.queue_archive(idx)],
+                };
+                for job in jobs.into_iter().flatten() {
+                    workers.spawn(job);
+                }
+            }
+
+            // Deletion progress and results
+            Some(event) = job_rx.recv() => {
+                if let Some(purge) = app.apply_job_event(event) {
+                    workers.spawn(purge);
                 }
             }
 
@@ -170,8 +223,8 @@ pub async fn run(args: &Args, cancel_token: CancellationToken) -> Result<()> {
                             let path = app.results[idx].scan_result.path.clone();
                             let tx = size_tx.clone();
                             tokio::spawn(async move {
-                                let (size, file_count) = calculate_size(&path).await;
-                                tx.send((idx, size, file_count)).ok();
+                                let stats = calculate_size(&path).await;
+                                tx.send((idx, stats)).ok();
                             });
                         }
 
@@ -184,8 +237,8 @@ pub async fn run(args: &Args, cancel_token: CancellationToken) -> Result<()> {
                                 let path = app.results[idx].scan_result.path.clone();
                                 let tx = size_tx.clone();
                                 tokio::spawn(async move {
-                                    let (size, file_count) = calculate_size(&path).await;
-                                    tx.send((idx, size, file_count)).ok();
+                                    let stats = calculate_size(&path).await;
+                                    tx.send((idx, stats)).ok();
                                 });
                             }
                         }
@@ -203,11 +256,235 @@ pub async fn run(args: &Args, cancel_token: CancellationToken) -> Result<()> {
             }
         }
 
+        if std::mem::take(&mut edit_selection) {
+            // The event stream reads stdin in the background and would steal the editor's keys
+            drop(event_stream);
+            let plan = editor::write_plan(&app);
+            let edited = suspend(&mut terminal, || editor::edit(&plan))?;
+            event_stream = EventStream::new();
+            match edited
+                .map_err(|e| e.to_string())
+                .and_then(|text| editor::parse_plan(&text, &app))
+            {
+                Ok(indices) => {
+                    app.select_only(&indices);
+                    app.mode = Mode::MultiSelect;
+                    app.confirm_selection();
+                }
+                Err(e) => app.add_error(format!("selection plan not applied: {e}")),
+            }
+        }
+
         // Check cancellation
         if cancel_token.is_cancelled() {
             break;
         }
     }
 
-    Ok(())
+    // Deletions that haven't started are dropped; running ones can't be interrupted
+    // safely. Whatever is still undoable gets purged now that the session is over.
+    // Keep the queue on screen until all of it is finished, or until Ctrl+C, `q` or
+    // Esc stops the purges: their folders stay quarantined for the next scan to offer.
+    let cancelled = app.delete_queue.cancel_all_queued();
+    app.unmark_deleting(&cancelled);
+    if app.delete_queue.has_pending() || !app.undo_stack.is_empty() {
+        app.panel = Panel::Queue;
+        app.finishing = true;
+    }
+    let reported = app.errors.len();
+    let mut stopping = false;
+    let mut stopped = 0;
+    loop {
+        // Deletions still finishing may add to the undo stack
+        let undoable: Vec<UndoEntry> = app.undo_stack.drain(..).collect();
+        for entry in undoable {
+            if let Some(purge) = app.queue_purge(entry) {
+                workers.spawn(purge);
+            }
+        }
+        if stopping {
+            stopped += app.delete_queue.stop_purges();
+        }
+        if !app.delete_queue.has_pending() {
+            break;
+        }
+        terminal.draw(|f| ui::draw(f, &mut app))?;
+
+        tokio::select! {
+            maybe_event = event_stream.next(), if !stopping => {
+                if let Some(Ok(Event::Key(key))) = maybe_event {
+                    stopping = is_stop_key(key);
+                }
+            }
+            () = cancel_token.cancelled(), if !stopping => {
+                stopping = true;
+            }
+            event = job_rx.recv() => {
+                let Some(event) = event else {
+                    break;
+                };
+                if let Some(purge) = app.apply_job_event(event) {
+                    workers.spawn(purge);
+                }
+            }
+        }
+    }
+
+    let mut errors = app.errors.split_off(reported);
+    if stopped > 0 {
+        errors.push(format!(
+            "Stopped purging folders kept for undo; they're left as hidden {QUARANTINE_PREFIX}* \
+             folders, which the next scan lists"
+        ));
+    }
+    Ok(errors)
+}
+
+/// Keys that stop waiting for purges after quitting
+const fn is_stop_key(key: KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Char('c') => key.modifiers.contains(KeyModifiers::CONTROL),
+        KeyCode::Char('q') | KeyCode::Esc => true,
+        _ => false,
+    }
+}
+
+/// Runs deletion jobs in the background, a few at a time
+struct DeleteWorkers {
+    strategy: DeleteStrategy,
+    dry_run: bool,
+    quarantine: bool, // undo enabled: rename aside instead of deleting
+    archive: Option<Archive>,
+    permits: Arc<Semaphore>,
+    events: mpsc::UnboundedSender<JobEvent>,
+}
+
+impl DeleteWorkers {
+    fn spawn(&self, job: DeleteJob) {
+        let permits = Arc::clone(&self.permits);
+        let events = self.events.clone();
+        let (strategy, dry_run) = (self.strategy, self.dry_run);
+        let quarantine = self.quarantine && strategy == DeleteStrategy::Delete && !dry_run;
+        let archive = self.archive.clone();
+
+        tokio::spawn(async move {
+            // Wait for a slot, unless the job is cancelled while queued
+            let permit = tokio::select! {
+                permit = permits.acquire_owned() => permit.ok(),
+                () = job.cancel.cancelled() => None,
+            };
+            if permit.is_none() || job.cancel.is_cancelled() {
+                events
+                    .send(JobEvent::Finished(job.id, JobOutcome::Cancelled))
+                    .ok();
+                return;
+            }
+            events.send(JobEvent::Started(job.id)).ok();
+
+            let outcome = match (job.kind, archive) {
+                (JobKind::Purge, _) => run_purge_job(&job, &events).await,
+                (JobKind::Archive, Some(archive)) if !dry_run => {
+                    run_archive_job(&job, archive, &events).await
+                }
+                _ => run_job(&job, strategy, dry_run, quarantine, &events).await,
+            };
+            drop(permit); // Free the slot before reporting back
+            events.send(JobEvent::Finished(job.id, outcome)).ok();
+        });
+    }
+}
+
+async fn run_job(
+    job: &DeleteJob,
+    strategy: DeleteStrategy,
+    dry_run: bool,
+    quarantine: bool,
+    events: &mpsc::UnboundedSender<JobEvent>,
+) -> JobOutcome {
+    if quarantine {
+        return match quarantine_directory(&job.path, job.identity).await {
+            Ok(quarantined) => JobOutcome::Quarantined(quarantined),
+            Err(e) => JobOutcome::Failed(DeleteResult::from_error(&job.path, e)),
+        };
+    }
+
+    let events = events.clone();
+    let id = job.id;
+    let result = remove_directory(
+        &job.path,
+        job.identity,
+        strategy,
+        dry_run,
+        move |progress| {
+            events.send(JobEvent::Progress(id, progress)).ok();
+        },
+    )
+    .await;
+    if result.success {
+        JobOutcome::Removed(result)
+    } else {
+        JobOutcome::Failed(result)
+    }
+}
+
+/// Deletes a quarantined folder for good (never a dry run: those don't move anything)
+async fn run_purge_job(job: &DeleteJob, events: &mpsc::UnboundedSender<JobEvent>) -> JobOutcome {
+    let events = events.clone();
+    let id = job.id;
+    let result = purge_directory(&job.path, job.cancel.clone(), move |progress| {
+        events.send(JobEvent::Progress(id, progress)).ok();
+    })
+    .await;
+    if result.success {
+        JobOutcome::Removed(result)
+    } else if job.cancel.is_cancelled() {
+        // Left for the next scan to offer
+        JobOutcome::Cancelled
+    } else {
+        JobOutcome::Failed(result)
+    }
+}
+
+/// Archives a folder, then deletes it (dry runs go through `run_job`, which only simulates)
+async fn run_archive_job(
+    job: &DeleteJob,
+    archive: Archive,
+    events: &mpsc::UnboundedSender<JobEvent>,
+) -> JobOutcome {
+    let events = events.clone();
+    let id = job.id;
+    let (result, item) = archive_directory(archive, &job.path, job.identity, move |progress| {
+        events.send(JobEvent::Progress(id, progress)).ok();
+    })
+    .await;
+    match item {
+        Some(item) if result.success => JobOutcome::Archived(result, item),
+        _ => JobOutcome::Failed(result),
+    }
+}
+
+/// Moves the most recently deleted folder back
+async fn undo_delete(app: &mut App, entry: UndoEntry, workers: &DeleteWorkers) {
+    let Some(quarantined) = entry.quarantined.clone() else {
+        app.mark_restored(entry.index, entry.size);
+        return;
+    };
+
+    let restore = quarantined.clone();
+    let result = tokio::task::spawn_blocking(move || restore.restore())
+        .await
+        .unwrap_or_else(|e| Err(io::Error::other(e)));
+    match result {
+        Ok(()) => app.mark_restored(entry.index, entry.size),
+        Err(e) => {
+            // Something took its place (or the rename failed); the folder stays deleted
+            app.add_error(format!(
+                "{}: undo failed: {e}",
+                quarantined.original.display()
+            ));
+            if let Some(purge) = app.queue_purge(entry) {
+                workers.spawn(purge);
+            }
+        }
+    }
 }
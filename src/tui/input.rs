@@ -2,6 +2,7 @@ use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
 use super::app::{App, Mode, Panel, SortOrder};
 use super::panels;
+use crate::scanner::parse_size;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Action {
@@ -9,7 +10,12 @@ pub enum Action {
     Quit,
     Delete,
     DeleteSelected,
+    DeleteActive,
+    Archive,
+    Undo,
     OpenInExplorer,
+    /// Write the list to a keep/delete file and open it in `$EDITOR`
+    EditSelection,
 }
 
 pub fn handle_key(key: KeyEvent, app: &mut App) -> Action {
@@ -17,6 +23,12 @@ pub fn handle_key(key: KeyEvent, app: &mut App) -> Action {
     if app.panel == Panel::Analytics {
         return handle_analytics_key(key, app);
     }
+    if app.panel == Panel::Queue {
+        return handle_queue_key(key, app);
+    }
+    if app.panel == Panel::Errors {
+        return handle_errors_key(key, app);
+    }
 
     match app.mode {
         Mode::Search => handle_search_key(key, app),
@@ -24,6 +36,68 @@ pub fn handle_key(key: KeyEvent, app: &mut App) -> Action {
         Mode::Normal => handle_normal_key(key, app),
         Mode::Confirm => handle_confirm_key(key, app),
         Mode::SensitiveBlocked => handle_sensitive_blocked_key(key, app),
+        Mode::ActiveConfirm => handle_active_confirm_key(key, app),
+        Mode::FreePrompt => handle_free_prompt_key(key, app),
+        Mode::FreePlan => handle_free_plan_key(key, app),
+    }
+}
+
+/// Typing how much space to free (e.g., `20GB`)
+fn handle_free_prompt_key(key: KeyEvent, app: &mut App) -> Action {
+    match key.code {
+        KeyCode::Esc => app.mode = Mode::Normal,
+        KeyCode::Enter => match parse_size(&app.free_input) {
+            Ok(target) => app.request_free(target),
+            Err(e) => app.free_error = Some(e),
+        },
+        KeyCode::Backspace => {
+            app.free_input.pop();
+            app.free_error = None;
+        }
+        KeyCode::Char(c) => {
+            app.free_input.push(c);
+            app.free_error = None;
+        }
+        _ => {}
+    }
+    Action::Continue
+}
+
+/// Reviewing the space plan: accept it into the multi-select or drop it
+fn handle_free_plan_key(key: KeyEvent, app: &mut App) -> Action {
+    match key.code {
+        KeyCode::Char('y' | 'Y') | KeyCode::Enter => app.accept_budget(),
+        KeyCode::Char('n' | 'N') | KeyCode::Esc => {
+            app.budget = None;
+            app.mode = Mode::Normal;
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.budget_scroll = app.budget_scroll.saturating_sub(1);
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            let steps = app.budget.as_ref().map_or(0, |plan| plan.steps.len());
+            app.budget_scroll = (app.budget_scroll + 1).min(steps.saturating_sub(1));
+        }
+        _ => {}
+    }
+    Action::Continue
+}
+
+/// Handle the "folder is in use by a build" confirmation for single deletes
+#[allow(clippy::missing_const_for_fn)] // &mut self methods can't be const
+fn handle_active_confirm_key(key: KeyEvent, app: &mut App) -> Action {
+    match key.code {
+        KeyCode::Char('y' | 'Y') => {
+            app.mode = Mode::Normal;
+            Action::DeleteActive
+        }
+        KeyCode::Char('n' | 'N') | KeyCode::Esc | KeyCode::Enter => {
+            app.mode = Mode::Normal;
+            app.pending_active_delete = None;
+            app.pending_active_archive = false;
+            Action::Continue
+        }
+        _ => Action::Continue,
     }
 }
 
@@ -60,6 +134,70 @@ fn handle_sensitive_blocked_key(key: KeyEvent, app: &mut App) -> Action {
     }
 }
 
+/// Deletion queue: cancel jobs that haven't started yet
+fn handle_queue_key(key: KeyEvent, app: &mut App) -> Action {
+    match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Quit,
+        KeyCode::Char('D') | KeyCode::Esc => {
+            app.panel = Panel::Results;
+            Action::Continue
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.delete_queue.move_cursor(-1);
+            Action::Continue
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            app.delete_queue.move_cursor(1);
+            Action::Continue
+        }
+        KeyCode::Char('c' | ' ') | KeyCode::Delete => {
+            if let Some(index) = app.delete_queue.cancel_at_cursor() {
+                app.unmark_deleting(&[index]);
+            }
+            Action::Continue
+        }
+        KeyCode::Char('C') => {
+            let cancelled = app.delete_queue.cancel_all_queued();
+            app.unmark_deleting(&cancelled);
+            Action::Continue
+        }
+        KeyCode::Char('x') => {
+            app.delete_queue.clear_finished();
+            Action::Continue
+        }
+        KeyCode::Char('q') => Action::Quit,
+        _ => Action::Continue,
+    }
+}
+
+/// Error view: failed deletions and the paths they left behind
+fn handle_errors_key(key: KeyEvent, app: &mut App) -> Action {
+    match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Quit,
+        KeyCode::Char('e') | KeyCode::Esc => {
+            app.panel = Panel::Results;
+            Action::Continue
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.errors_scroll = app.errors_scroll.saturating_sub(1);
+            Action::Continue
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            let max_scroll = panels::error_line_count(app).saturating_sub(1);
+            app.errors_scroll = (app.errors_scroll + 1).min(max_scroll);
+            Action::Continue
+        }
+        KeyCode::Char('x') => {
+            app.errors.clear();
+            app.delete_failures.clear();
+            app.errors_scroll = 0;
+            Action::Continue
+        }
+        KeyCode::Char('q') => Action::Quit,
+        _ => Action::Continue,
+    }
+}
+
 fn handle_analytics_key(key: KeyEvent, app: &mut App) -> Action {
     match key.code {
         KeyCode::Char('a') | KeyCode::Esc => {
@@ -115,7 +253,11 @@ fn handle_normal_key(key: KeyEvent, app: &mut App) -> Action {
             app.move_cursor(1);
             Action::Continue
         }
-        KeyCode::PageUp | KeyCode::Char('u') => {
+        KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.move_cursor_by_page(false);
+            Action::Continue
+        }
+        KeyCode::PageUp => {
             app.move_cursor_by_page(false);
             Action::Continue
         }
@@ -142,14 +284,16 @@ fn handle_normal_key(key: KeyEvent, app: &mut App) -> Action {
         // Panel navigation
         KeyCode::Left | KeyCode::Char('h') => {
             app.panel = match app.panel {
-                Panel::Info | Panel::Analytics | Panel::Results => Panel::Results,
+                Panel::Info | Panel::Analytics | Panel::Queue | Panel::Errors | Panel::Results => {
+                    Panel::Results
+                }
             };
             Action::Continue
         }
         KeyCode::Right | KeyCode::Char('l') => {
             app.panel = match app.panel {
                 Panel::Results => Panel::Info,
-                Panel::Info | Panel::Analytics => Panel::Results,
+                Panel::Info | Panel::Analytics | Panel::Queue | Panel::Errors => Panel::Results,
             };
             Action::Continue
         }
@@ -163,15 +307,29 @@ fn handle_normal_key(key: KeyEvent, app: &mut App) -> Action {
 
         // Actions (disabled on Info panel)
         KeyCode::Char(' ') | KeyCode::Delete if app.panel != Panel::Info => Action::Delete,
+        KeyCode::Char('u') => Action::Undo,
+        KeyCode::Char('A') => Action::Archive,
+        KeyCode::Char('D') => {
+            app.panel = Panel::Queue;
+            Action::Continue
+        }
         KeyCode::Char('/') if app.panel != Panel::Info => {
             app.mode = Mode::Search;
             app.search_query.clear();
+            app.update_search();
             Action::Continue
         }
         KeyCode::Char('v') if app.panel != Panel::Info => {
             app.mode = Mode::MultiSelect;
             Action::Continue
         }
+        KeyCode::Char('E') if app.panel != Panel::Info => Action::EditSelection,
+        KeyCode::Char('F') if app.panel != Panel::Info => {
+            app.mode = Mode::FreePrompt;
+            app.free_input.clear();
+            app.free_error = None;
+            Action::Continue
+        }
         KeyCode::Char('s') if app.panel != Panel::Info => {
             app.sort_order = match app.sort_order {
                 SortOrder::Size => SortOrder::Path,
@@ -195,7 +353,8 @@ fn handle_normal_key(key: KeyEvent, app: &mut App) -> Action {
         KeyCode::Char('o') if app.panel == Panel::Info => Action::OpenInExplorer,
 
         KeyCode::Char('e') => {
-            // Toggle error display (handled in UI)
+            app.panel = Panel::Errors;
+            app.errors_scroll = 0;
             Action::Continue
         }
 
@@ -208,22 +367,23 @@ fn handle_search_key(key: KeyEvent, app: &mut App) -> Action {
         KeyCode::Esc => {
             app.mode = Mode::Normal;
             app.search_query.clear();
-            app.needs_filter = true;
+            app.update_search();
             Action::Continue
         }
-        KeyCode::Enter => {
+        // Stay in the search bar until the query parses
+        KeyCode::Enter if app.search_error.is_none() => {
             app.mode = Mode::Normal;
             app.needs_filter = true;
             Action::Continue
         }
         KeyCode::Backspace => {
             app.search_query.pop();
-            app.needs_filter = true;
+            app.update_search();
             Action::Continue
         }
         KeyCode::Char(c) => {
             app.search_query.push(c);
-            app.needs_filter = true;
+            app.update_search();
             Action::Continue
         }
         _ => Action::Continue,
@@ -271,18 +431,10 @@ fn handle_multi_select_key(key: KeyEvent, app: &mut App) -> Action {
             Action::Continue
         }
         KeyCode::Enter => {
-            if !app.selected_indices.is_empty() {
-                // Check if any selected items are sensitive
-                let sensitive_count = app.count_sensitive_in_selection();
-                if sensitive_count > 0 {
-                    app.sensitive_blocked_count = sensitive_count;
-                    app.mode = Mode::SensitiveBlocked;
-                } else {
-                    app.mode = Mode::Confirm;
-                }
-            }
+            app.confirm_selection();
             Action::Continue
         }
+        KeyCode::Char('E') => Action::EditSelection,
 
         _ => Action::Continue,
     }
@@ -291,6 +443,9 @@ fn handle_multi_select_key(key: KeyEvent, app: &mut App) -> Action {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::scanner::{BudgetPlan, ScanResult};
+    use crate::tui::queue::JobStatus;
+    use std::path::PathBuf;
 
     fn key(code: KeyCode) -> KeyEvent {
         KeyEvent::new(code, KeyModifiers::NONE)
@@ -452,6 +607,75 @@ mod tests {
         assert_eq!(app.panel, Panel::Info);
     }
 
+    // === Undo ===
+
+    #[test]
+    fn test_results_u_undoes() {
+        let mut app = app_in_results();
+        let action = handle_key(key(KeyCode::Char('u')), &mut app);
+        assert!(matches!(action, Action::Undo));
+    }
+
+    #[test]
+    fn test_results_ctrl_u_pages_up() {
+        let mut app = app_in_results();
+        let action = handle_key(key_ctrl(KeyCode::Char('u')), &mut app);
+        assert!(matches!(action, Action::Continue));
+    }
+
+    // === Deletion queue ===
+
+    fn app_in_queue() -> App {
+        use crate::scanner::ScanResult;
+        use std::path::PathBuf;
+
+        let mut app = App::new(false, SortOrder::Size, false);
+        app.add_results(vec![
+            ScanResult::new(PathBuf::from("/a/node_modules")),
+            ScanResult::new(PathBuf::from("/b/node_modules")),
+        ]);
+        app.queue_delete(0);
+        app.queue_delete(1);
+        app.panel = Panel::Queue;
+        app
+    }
+
+    #[test]
+    fn test_results_shift_d_opens_queue() {
+        let mut app = app_in_results();
+        handle_key(key(KeyCode::Char('D')), &mut app);
+        assert_eq!(app.panel, Panel::Queue);
+    }
+
+    #[test]
+    fn test_queue_c_cancels_job_at_cursor() {
+        let mut app = app_in_queue();
+        handle_key(key(KeyCode::Down), &mut app);
+        handle_key(key(KeyCode::Char('c')), &mut app);
+
+        assert_eq!(app.delete_queue.jobs[1].status, JobStatus::Cancelled);
+        assert!(!app.results[1].is_deleting);
+        assert!(app.results[0].is_deleting);
+    }
+
+    #[test]
+    fn test_queue_shift_c_cancels_all_queued() {
+        let mut app = app_in_queue();
+        app.delete_queue.jobs[0].status = JobStatus::Running;
+
+        handle_key(key(KeyCode::Char('C')), &mut app);
+
+        assert_eq!(app.delete_queue.jobs[0].status, JobStatus::Running);
+        assert_eq!(app.delete_queue.jobs[1].status, JobStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_queue_esc_returns_to_results() {
+        let mut app = app_in_queue();
+        handle_key(key(KeyCode::Esc), &mut app);
+        assert_eq!(app.panel, Panel::Results);
+    }
+
     // === Confirm mode key handling ===
 
     fn app_in_confirm() -> App {
@@ -469,6 +693,135 @@ mod tests {
         app
     }
 
+    #[test]
+    fn test_shift_e_edits_selection_from_list_or_multiselect() {
+        let mut app = app_in_results();
+        assert_eq!(
+            handle_key(key(KeyCode::Char('E')), &mut app),
+            Action::EditSelection
+        );
+
+        let mut app = app_in_multiselect_with_selections();
+        assert_eq!(
+            handle_key(key(KeyCode::Char('E')), &mut app),
+            Action::EditSelection
+        );
+        assert_eq!(app.mode, Mode::MultiSelect);
+
+        let mut app = app_in_results();
+        app.panel = Panel::Info;
+        assert_eq!(
+            handle_key(key(KeyCode::Char('E')), &mut app),
+            Action::Continue
+        );
+    }
+
+    #[test]
+    fn test_search_query_errors_show_until_fixed() {
+        let mut app = app_in_results();
+        let mut big = ScanResult::new(PathBuf::from("/code/a/node_modules"));
+        big.size = Some(2_000_000_000);
+        let mut small = ScanResult::new(PathBuf::from("/code/b/node_modules"));
+        small.size = Some(1000);
+        app.add_results(vec![big, small]);
+
+        handle_key(key(KeyCode::Char('/')), &mut app);
+        for c in "size>1x".chars() {
+            handle_key(key(KeyCode::Char(c)), &mut app);
+        }
+        assert!(app
+            .search_error
+            .as_ref()
+            .unwrap()
+            .starts_with("invalid size"));
+        // Enter doesn't leave the search bar with a broken query
+        handle_key(key(KeyCode::Enter), &mut app);
+        assert_eq!(app.mode, Mode::Search);
+
+        handle_key(key(KeyCode::Backspace), &mut app);
+        handle_key(key(KeyCode::Char('G')), &mut app);
+        assert!(app.search_error.is_none());
+        handle_key(key(KeyCode::Enter), &mut app);
+        assert_eq!(app.mode, Mode::Normal);
+        app.on_tick();
+        assert_eq!(app.filtered_indices, vec![0]);
+
+        // Esc clears the search
+        handle_key(key(KeyCode::Char('/')), &mut app);
+        handle_key(key(KeyCode::Char('!')), &mut app);
+        assert!(app.search_error.is_some());
+        handle_key(key(KeyCode::Esc), &mut app);
+        assert!(app.search_error.is_none());
+        app.on_tick();
+        assert_eq!(app.filtered_indices.len(), 2);
+    }
+
+    #[test]
+    fn test_search_bar_takes_unknown_fields_as_text() {
+        let mut app = app_in_results();
+        app.add_results(vec![
+            ScanResult::new(PathBuf::from("/code/foo:bar/node_modules")),
+            ScanResult::new(PathBuf::from("/code/foo/node_modules")),
+        ]);
+
+        handle_key(key(KeyCode::Char('/')), &mut app);
+        for c in "foo:bar".chars() {
+            handle_key(key(KeyCode::Char(c)), &mut app);
+        }
+        assert!(app.search_error.is_none());
+        handle_key(key(KeyCode::Enter), &mut app);
+        app.on_tick();
+        assert_eq!(app.filtered_indices, vec![0]);
+    }
+
+    #[test]
+    fn test_free_prompt_reports_bad_sizes_inline() {
+        let mut app = app_in_results();
+        app.scan_complete();
+        handle_key(key(KeyCode::Char('F')), &mut app);
+        assert_eq!(app.mode, Mode::FreePrompt);
+
+        for c in "20 parsecs".chars() {
+            handle_key(key(KeyCode::Char(c)), &mut app);
+        }
+        handle_key(key(KeyCode::Enter), &mut app);
+        assert_eq!(app.mode, Mode::FreePrompt);
+        assert!(app.free_error.as_ref().unwrap().contains("invalid size"));
+
+        // Editing clears the error; a valid size shows the plan
+        for _ in 0.."parsecs".len() {
+            handle_key(key(KeyCode::Backspace), &mut app);
+        }
+        handle_key(key(KeyCode::Char('G')), &mut app);
+        assert!(app.free_error.is_none());
+        handle_key(key(KeyCode::Enter), &mut app);
+        assert_eq!(app.mode, Mode::FreePlan);
+        assert_eq!(app.budget.as_ref().unwrap().target, 20_000_000_000);
+    }
+
+    #[test]
+    fn test_free_plan_accept_or_dismiss() {
+        let mut app = app_in_results();
+        app.add_results(vec![ScanResult::new(PathBuf::from("/a/node_modules"))]);
+        app.mode = Mode::FreePlan;
+        app.budget = Some(BudgetPlan {
+            target: 10,
+            steps: vec![(0, 10)],
+        });
+        handle_key(key(KeyCode::Esc), &mut app);
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.budget.is_none());
+
+        app.mode = Mode::FreePlan;
+        app.budget = Some(BudgetPlan {
+            target: 10,
+            steps: vec![(0, 10)],
+        });
+        handle_key(key(KeyCode::Enter), &mut app);
+        assert_eq!(app.mode, Mode::MultiSelect);
+        assert!(app.selected_indices.contains(&0));
+    }
+
     #[test]
     fn test_confirm_y_deletes_and_exits() {
         let mut app = app_in_confirm();
@@ -570,7 +923,10 @@ mod tests {
             size: Some(1000),
             file_count: Some(10),
             modified: None,
+            newest_modified: None,
             is_sensitive: false,
+            project_mark: None,
+            identity: None,
         }]);
 
         // Add a sensitive result (system path)
@@ -579,7 +935,10 @@ mod tests {
             size: Some(2000),
             file_count: Some(20),
             modified: None,
+            newest_modified: None,
             is_sensitive: true,
+            project_mark: None,
+            identity: None,
         }]);
 
         app.mode = Mode::MultiSelect;
@@ -612,6 +971,70 @@ mod tests {
         assert_eq!(app.sensitive_blocked_count, 0);
     }
 
+    // === Active-build confirmation ===
+
+    fn app_in_active_confirm() -> App {
+        let mut app = App::new(false, SortOrder::Size, false);
+        app.mode = Mode::ActiveConfirm;
+        app.pending_active_delete = Some(0);
+        app
+    }
+
+    #[test]
+    fn test_active_confirm_y_deletes() {
+        let mut app = app_in_active_confirm();
+        let action = handle_key(key(KeyCode::Char('y')), &mut app);
+
+        assert_eq!(action, Action::DeleteActive);
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.pending_active_delete, Some(0));
+    }
+
+    #[test]
+    fn test_active_confirm_enter_does_not_delete() {
+        // Enter is too easy to hit by reflex; require an explicit 'y'
+        let mut app = app_in_active_confirm();
+        let action = handle_key(key(KeyCode::Enter), &mut app);
+
+        assert_eq!(action, Action::Continue);
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.pending_active_delete, None);
+    }
+
+    #[test]
+    fn test_active_confirm_esc_cancels() {
+        let mut app = app_in_active_confirm();
+        let action = handle_key(key(KeyCode::Esc), &mut app);
+
+        assert_eq!(action, Action::Continue);
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.pending_active_delete, None);
+    }
+
+    #[test]
+    fn test_active_confirm_cancel_forgets_archive() {
+        let mut app = app_in_active_confirm();
+        app.pending_active_archive = true;
+        handle_key(key(KeyCode::Char('n')), &mut app);
+
+        assert!(!app.pending_active_archive);
+    }
+
+    #[test]
+    fn test_shift_a_archives_from_results_and_info() {
+        let mut app = App::new(false, SortOrder::Size, false);
+        assert_eq!(
+            handle_key(key(KeyCode::Char('A')), &mut app),
+            Action::Archive
+        );
+
+        app.panel = Panel::Info;
+        assert_eq!(
+            handle_key(key(KeyCode::Char('A')), &mut app),
+            Action::Archive
+        );
+    }
+
     #[test]
     fn test_single_sensitive_dismiss_returns_to_normal() {
         let mut app = App::new(false, SortOrder::Size, false);
@@ -623,4 +1046,31 @@ mod tests {
         assert_eq!(action, Action::Continue);
         assert_eq!(app.mode, Mode::Normal);
     }
+
+    // === Error view ===
+
+    #[test]
+    fn test_results_e_toggles_error_view() {
+        let mut app = app_in_results();
+        app.errors_scroll = 3;
+
+        handle_key(key(KeyCode::Char('e')), &mut app);
+        assert_eq!(app.panel, Panel::Errors);
+        assert_eq!(app.errors_scroll, 0);
+
+        handle_key(key(KeyCode::Char('e')), &mut app);
+        assert_eq!(app.panel, Panel::Results);
+    }
+
+    #[test]
+    fn test_errors_x_clears() {
+        let mut app = app_in_results();
+        app.add_error("/a/node_modules: Permission denied".to_string());
+        app.panel = Panel::Errors;
+
+        handle_key(key(KeyCode::Char('x')), &mut app);
+
+        assert!(app.errors.is_empty());
+        assert_eq!(app.panel, Panel::Errors);
+    }
 }
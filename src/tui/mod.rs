@@ -1,17 +1,26 @@
 mod analytics;
 mod app;
 mod cleanup;
+mod editor;
 mod event_loop;
 mod input;
 mod panels;
+mod queue;
 mod ui;
 mod widgets;
 
+pub use analytics::AnalyticsData;
+pub use app::SortOrder;
+
 use anyhow::Result;
 use tokio_util::sync::CancellationToken;
 
 use crate::cli::Args;
 
 pub async fn run(args: &Args, cancel_token: CancellationToken) -> Result<()> {
-    event_loop::run(args, cancel_token).await
+    // The terminal is restored by now, so failures while finishing up can be printed
+    for error in event_loop::run(args, cancel_token).await? {
+        eprintln!("{error}");
+    }
+    Ok(())
 }
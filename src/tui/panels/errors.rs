@@ -0,0 +1,69 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::tui::app::App;
+
+pub fn draw_errors(frame: &mut Frame, app: &App, area: Rect) {
+    let inner_height = area.height.saturating_sub(2) as usize;
+    let lines: Vec<Line> = build_lines(app)
+        .into_iter()
+        .skip(app.errors_scroll)
+        .take(inner_height)
+        .collect();
+
+    let block = Block::default()
+        .title(format!(" Errors ({}) ", app.errors.len()))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Red));
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// Used to clamp scrolling
+pub fn error_line_count(app: &App) -> usize {
+    build_lines(app).len()
+}
+
+fn build_lines(app: &App) -> Vec<Line<'static>> {
+    if app.errors.is_empty() && app.delete_failures.is_empty() {
+        return vec![Line::from(Span::styled(
+            "  No errors.",
+            Style::default().fg(Color::DarkGray),
+        ))];
+    }
+
+    let heading = |text: &'static str| {
+        Line::from(Span::styled(
+            text,
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ))
+    };
+
+    let mut lines = vec![heading("  FAILED")];
+    lines.extend(
+        app.errors
+            .iter()
+            .map(|error| Line::from(format!("  ✗ {error}"))),
+    );
+
+    if !app.delete_failures.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(heading("  LEFT BEHIND"));
+        lines.extend(app.delete_failures.iter().map(|failure| {
+            Line::from(vec![
+                Span::raw(format!("  {} ", failure.path.display())),
+                Span::styled(
+                    format!("({})", failure.error),
+                    Style::default().fg(Color::Red),
+                ),
+            ])
+        }));
+    }
+    lines
+}
@@ -9,6 +9,8 @@ use ratatui::{
 use std::path::Path;
 use std::time::{Duration, SystemTime};
 
+use crate::delete::estimated_compressed_size;
+use crate::scanner::{project_name, ProjectMark};
 use crate::tui::app::App;
 
 const MB: u64 = 1024 * 1024;
@@ -96,6 +98,28 @@ pub fn draw_info(frame: &mut Frame, app: &App, area: Rect) {
                 ]));
             }
 
+            // Active build warning (recent writes inside the folder)
+            if app.current_index().is_some_and(|idx| app.is_active(idx)) {
+                let mins = item.last_activity().map_or(0, |time| {
+                    SystemTime::now()
+                        .duration_since(time)
+                        .unwrap_or(Duration::ZERO)
+                        .as_secs()
+                        / 60
+                });
+                lines.push(Line::from(vec![
+                    Span::styled("Activity:  ", Style::default().fg(Color::DarkGray)),
+                    Span::styled(
+                        format!("⚡ changed {mins} min ago"),
+                        Style::default().fg(Color::Yellow),
+                    ),
+                    Span::styled(
+                        " (build in progress?)",
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]));
+            }
+
             // File count
             if let Some(count) = item.scan_result.file_count {
                 lines.push(Line::from(vec![
@@ -117,14 +141,44 @@ pub fn draw_info(frame: &mut Frame, app: &App, area: Rect) {
                 }
             }
 
+            // What `A` would write, and where
+            let archive = app.current_index().and_then(|idx| app.archive_path(idx));
+            if let (Some(size), Some(archive)) = (item.scan_result.size, archive) {
+                lines.push(Line::from(vec![
+                    Span::styled("Archive:   ", Style::default().fg(Color::DarkGray)),
+                    Span::raw(format!(
+                        "~{} ",
+                        ByteSize::b(estimated_compressed_size(size))
+                    )),
+                    Span::styled("(estimated) → ", Style::default().fg(Color::DarkGray)),
+                    Span::raw(archive.display().to_string()),
+                ]));
+            }
+
             // Project name
-            if let Some(project) = find_project_name(path) {
+            if let Some(project) = path.parent().and_then(project_name) {
                 lines.push(Line::from(vec![
                     Span::styled("Project:   ", Style::default().fg(Color::DarkGray)),
                     Span::raw(project),
                 ]));
             }
 
+            // Project config that changed how this folder is handled
+            match &item.scan_result.project_mark {
+                Some(ProjectMark::Added { config }) => lines.push(Line::from(vec![
+                    Span::styled("Config:    ", Style::default().fg(Color::DarkGray)),
+                    Span::raw(format!("added by {}", config.display())),
+                ])),
+                Some(ProjectMark::Kept { config, .. }) => lines.push(Line::from(vec![
+                    Span::styled("Config:    ", Style::default().fg(Color::DarkGray)),
+                    Span::styled(
+                        format!("kept by {}", config.display()),
+                        Style::default().fg(Color::Yellow),
+                    ),
+                ])),
+                None => {}
+            }
+
             // Sensitive warning
             if item.risk.is_sensitive {
                 lines.push(Line::from(""));
@@ -248,75 +302,6 @@ fn format_file_count(count: u64) -> String {
     }
 }
 
-fn find_project_name(target_path: &Path) -> Option<String> {
-    // Go up to parent directory (e.g., from node_modules to project root)
-    let parent = target_path.parent()?;
-
-    // Check for package.json (Node.js)
-    let pkg_json = parent.join("package.json");
-    if pkg_json.exists() {
-        if let Ok(content) = std::fs::read_to_string(&pkg_json) {
-            if let Some(name) = extract_json_field(&content, "name") {
-                return Some(name);
-            }
-        }
-    }
-
-    // Check for Cargo.toml (Rust)
-    let cargo_toml = parent.join("Cargo.toml");
-    if cargo_toml.exists() {
-        if let Ok(content) = std::fs::read_to_string(&cargo_toml) {
-            if let Some(name) = extract_toml_name(&content) {
-                return Some(name);
-            }
-        }
-    }
-
-    // Check for pyproject.toml (Python)
-    let pyproject = parent.join("pyproject.toml");
-    if pyproject.exists() {
-        if let Ok(content) = std::fs::read_to_string(&pyproject) {
-            if let Some(name) = extract_toml_name(&content) {
-                return Some(name);
-            }
-        }
-    }
-
-    // Fallback: use parent directory name
-    parent.file_name().map(|n| n.to_string_lossy().to_string())
-}
-
-fn extract_json_field(content: &str, field: &str) -> Option<String> {
-    // Simple extraction without full JSON parsing
-    let pattern = format!("\"{field}\"");
-    let idx = content.find(&pattern)?;
-    let rest = &content[idx + pattern.len()..];
-    let colon = rest.find(':')?;
-    let after_colon = rest[colon + 1..].trim_start();
-    if let Some(stripped) = after_colon.strip_prefix('"') {
-        let value_end = stripped.find('"')?;
-        return Some(stripped[..value_end].to_string());
-    }
-    None
-}
-
-fn extract_toml_name(content: &str) -> Option<String> {
-    // Simple extraction: look for name = "value" under [package] or [project]
-    for line in content.lines() {
-        let line = line.trim();
-        if line.starts_with("name") && line.contains('=') {
-            let parts: Vec<&str> = line.splitn(2, '=').collect();
-            if parts.len() == 2 {
-                let value = parts[1].trim().trim_matches('"').trim_matches('\'');
-                if !value.is_empty() {
-                    return Some(value.to_string());
-                }
-            }
-        }
-    }
-    None
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -435,68 +420,4 @@ mod tests {
         let bar = size_bar(512 * MB, 10); // ~half of 1GB
         assert!(bar.contains("█") && bar.contains("░"));
     }
-
-    // extract_json_field tests
-    #[test]
-    fn test_extract_json_field_basic() {
-        let json = r#"{"name": "my-project", "version": "1.0.0"}"#;
-        assert_eq!(
-            extract_json_field(json, "name"),
-            Some("my-project".to_string())
-        );
-    }
-
-    #[test]
-    fn test_extract_json_field_with_whitespace() {
-        let json = r#"{
-            "name"  :  "spaced-project",
-            "version": "1.0.0"
-        }"#;
-        assert_eq!(
-            extract_json_field(json, "name"),
-            Some("spaced-project".to_string())
-        );
-    }
-
-    #[test]
-    fn test_extract_json_field_missing() {
-        let json = r#"{"version": "1.0.0"}"#;
-        assert_eq!(extract_json_field(json, "name"), None);
-    }
-
-    // extract_toml_name tests
-    #[test]
-    fn test_extract_toml_name_cargo() {
-        let toml = r#"
-[package]
-name = "my-rust-project"
-version = "0.1.0"
-"#;
-        assert_eq!(extract_toml_name(toml), Some("my-rust-project".to_string()));
-    }
-
-    #[test]
-    fn test_extract_toml_name_pyproject() {
-        let toml = r#"
-[project]
-name = "my-python-project"
-version = "1.0.0"
-"#;
-        assert_eq!(
-            extract_toml_name(toml),
-            Some("my-python-project".to_string())
-        );
-    }
-
-    #[test]
-    fn test_extract_toml_name_single_quotes() {
-        let toml = "name = 'single-quoted'";
-        assert_eq!(extract_toml_name(toml), Some("single-quoted".to_string()));
-    }
-
-    #[test]
-    fn test_extract_toml_name_missing() {
-        let toml = "[package]\nversion = \"1.0.0\"";
-        assert_eq!(extract_toml_name(toml), None);
-    }
 }
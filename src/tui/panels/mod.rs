@@ -1,5 +1,9 @@
 mod analytics;
+mod errors;
 mod info;
+mod queue;
 
 pub use analytics::{content_line_count, draw_analytics};
+pub use errors::{draw_errors, error_line_count};
 pub use info::draw_info;
+pub use queue::{draw_queue, percent};
@@ -0,0 +1,106 @@
+use bytesize::ByteSize;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::tui::app::App;
+use crate::tui::queue::{DeleteJob, JobKind, JobStatus};
+
+pub fn draw_queue(frame: &mut Frame, app: &App, area: Rect) {
+    let queue = &app.delete_queue;
+    let title = format!(
+        " Deletion queue: {} running, {} queued, {} failed ",
+        queue.count(&JobStatus::Running),
+        queue.count(&JobStatus::Queued),
+        queue
+            .jobs
+            .iter()
+            .filter(|job| matches!(job.status, JobStatus::Failed(_)))
+            .count(),
+    );
+
+    // Keep the cursor row visible
+    let inner_height = area.height.saturating_sub(2) as usize;
+    let offset = queue.cursor.saturating_sub(inner_height.saturating_sub(1));
+
+    let lines: Vec<Line> = if queue.jobs.is_empty() {
+        vec![Line::from(Span::styled(
+            "  Nothing queued. Deletions show up here while they run.",
+            Style::default().fg(Color::DarkGray),
+        ))]
+    } else {
+        queue
+            .jobs
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(inner_height)
+            .map(|(i, job)| job_line(job, i == queue.cursor))
+            .collect()
+    };
+
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn job_line(job: &DeleteJob, is_cursor: bool) -> Line<'static> {
+    let (status, color) = match &job.status {
+        JobStatus::Queued => ("queued".to_string(), Color::DarkGray),
+        JobStatus::Running => (running_label(job), Color::Yellow),
+        JobStatus::Done if job.kind == JobKind::Archive => ("✓ archived".to_string(), Color::Green),
+        JobStatus::Done if job.kind == JobKind::Purge => ("✓ purged".to_string(), Color::Green),
+        JobStatus::Done => ("✓ done".to_string(), Color::Green),
+        JobStatus::Failed(err) => (format!("✗ {err}"), Color::Red),
+        JobStatus::Cancelled => ("cancelled".to_string(), Color::DarkGray),
+    };
+
+    let mut style = Style::default().fg(color);
+    if is_cursor {
+        style = style.add_modifier(Modifier::REVERSED);
+    }
+
+    Line::from(vec![
+        Span::styled(format!(" {status:<32} "), style),
+        Span::raw(job.path.to_string_lossy().to_string()),
+    ])
+}
+
+/// "42% · 1,204 files · 310 MB"
+pub fn running_label(job: &DeleteJob) -> String {
+    let progress = job.progress;
+    if progress.files == 0 {
+        return match job.kind {
+            JobKind::Delete => "deleting...",
+            JobKind::Archive => "archiving...",
+            JobKind::Purge => "purging...",
+        }
+        .to_string();
+    }
+    let counts = format!("{} files · {}", progress.files, ByteSize::b(progress.bytes));
+    percent(progress.bytes, job.size)
+        .map_or_else(|| counts.clone(), |pct| format!("{pct}% · {counts}"))
+}
+
+/// Share of the scanned size removed so far (sizes can drift, so it's capped at 99 until done)
+pub fn percent(done: u64, total: u64) -> Option<u64> {
+    (total > 0).then(|| (done.saturating_mul(100) / total).min(99))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent_caps_before_done() {
+        assert_eq!(percent(50, 200), Some(25));
+        assert_eq!(percent(300, 200), Some(99));
+        assert_eq!(percent(10, 0), None);
+    }
+}
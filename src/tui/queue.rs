@@ -0,0 +1,346 @@
+use std::path::PathBuf;
+
+use tokio_util::sync::CancellationToken;
+
+use crate::delete::{ArchivedItem, DeleteProgress, DeleteResult, DirIdentity, Quarantined};
+
+pub type JobId = u64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed(String),
+    Cancelled,
+}
+
+/// What happens to the folder
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JobKind {
+    #[default]
+    Delete,
+    /// Pack into a tarball first, then delete
+    Archive,
+    /// Finish deleting a quarantined folder that can no longer be undone (`path` is
+    /// where it was parked)
+    Purge,
+}
+
+/// One folder waiting for (or going through) deletion
+#[derive(Debug, Clone)]
+pub struct DeleteJob {
+    pub id: JobId,
+    pub index: usize, // into App.results
+    pub path: PathBuf,
+    pub size: u64, // from the scan, for progress display
+    pub identity: Option<DirIdentity>,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    pub progress: DeleteProgress,
+    pub cancel: CancellationToken,
+}
+
+impl DeleteJob {
+    pub const fn is_pending(&self) -> bool {
+        matches!(self.status, JobStatus::Queued | JobStatus::Running)
+    }
+}
+
+/// Messages from deletion workers back to the event loop
+#[derive(Debug)]
+pub enum JobEvent {
+    Started(JobId),
+    Progress(JobId, DeleteProgress),
+    Finished(JobId, JobOutcome),
+}
+
+#[derive(Debug)]
+pub enum JobOutcome {
+    Removed(DeleteResult),
+    /// Renamed aside; `u` can undo it until it's purged
+    Quarantined(Quarantined),
+    /// Archived and deleted; `cache-sweep restore` brings it back
+    Archived(DeleteResult, ArchivedItem),
+    /// Error summary, what was left behind, and what was freed before it stopped
+    Failed(DeleteResult),
+    Cancelled,
+}
+
+/// Deletion jobs for the session, oldest first
+#[derive(Debug, Default)]
+pub struct DeleteQueue {
+    pub jobs: Vec<DeleteJob>,
+    pub cursor: usize,
+    next_id: JobId,
+}
+
+impl DeleteQueue {
+    /// Adds a queued job and returns a copy for the worker
+    pub fn enqueue(
+        &mut self,
+        index: usize,
+        path: PathBuf,
+        size: u64,
+        identity: Option<DirIdentity>,
+        kind: JobKind,
+    ) -> DeleteJob {
+        self.next_id += 1;
+        let job = DeleteJob {
+            id: self.next_id,
+            index,
+            path,
+            size,
+            identity,
+            kind,
+            status: JobStatus::Queued,
+            progress: DeleteProgress::default(),
+            cancel: CancellationToken::new(),
+        };
+        self.jobs.push(job.clone());
+        job
+    }
+
+    pub fn get_mut(&mut self, id: JobId) -> Option<&mut DeleteJob> {
+        self.jobs.iter_mut().find(|job| job.id == id)
+    }
+
+    /// The newest job for a result row (a row can be re-queued after a failure)
+    pub fn job_for_index(&self, index: usize) -> Option<&DeleteJob> {
+        self.jobs.iter().rev().find(|job| job.index == index)
+    }
+
+    /// Cancels a job that hasn't started; running jobs can't be stopped halfway, and
+    /// purges always run (their folders are already gone from the user's point of view)
+    /// unless quitting stops them with [`Self::stop_purges`]
+    pub fn cancel(&mut self, id: JobId) -> bool {
+        match self.get_mut(id) {
+            Some(job) if job.status == JobStatus::Queued && job.kind != JobKind::Purge => {
+                job.cancel.cancel();
+                job.status = JobStatus::Cancelled;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn cancel_at_cursor(&mut self) -> Option<usize> {
+        let job = self.jobs.get(self.cursor)?;
+        let (id, index) = (job.id, job.index);
+        self.cancel(id).then_some(index)
+    }
+
+    /// Cancels every job that hasn't started; returns their result indices
+    pub fn cancel_all_queued(&mut self) -> Vec<usize> {
+        let queued: Vec<(JobId, usize)> = self
+            .jobs
+            .iter()
+            .filter(|job| job.status == JobStatus::Queued && job.kind != JobKind::Purge)
+            .map(|job| (job.id, job.index))
+            .collect();
+        for &(id, _) in &queued {
+            self.cancel(id);
+        }
+        queued.into_iter().map(|(_, index)| index).collect()
+    }
+
+    /// Stops every purge still to finish (when quitting without waiting for them);
+    /// returns how many there were. Running ones stop between files.
+    pub fn stop_purges(&mut self) -> usize {
+        let mut stopped = 0;
+        for job in &mut self.jobs {
+            if job.kind == JobKind::Purge && job.is_pending() {
+                job.cancel.cancel();
+                if job.status == JobStatus::Queued {
+                    job.status = JobStatus::Cancelled;
+                }
+                stopped += 1;
+            }
+        }
+        stopped
+    }
+
+    pub fn count(&self, status: &JobStatus) -> usize {
+        self.jobs.iter().filter(|job| job.status == *status).count()
+    }
+
+    pub fn has_pending(&self) -> bool {
+        self.jobs.iter().any(DeleteJob::is_pending)
+    }
+
+    pub fn move_cursor(&mut self, delta: i32) {
+        if self.jobs.is_empty() {
+            self.cursor = 0;
+            return;
+        }
+        let max = self.jobs.len() - 1;
+        self.cursor = self.cursor.saturating_add_signed(delta as isize).min(max);
+    }
+
+    /// Drops finished jobs from the view, keeping queued and running ones
+    pub fn clear_finished(&mut self) {
+        self.jobs.retain(DeleteJob::is_pending);
+        self.cursor = self.cursor.min(self.jobs.len().saturating_sub(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue_with(n: usize) -> DeleteQueue {
+        let mut queue = DeleteQueue::default();
+        for i in 0..n {
+            queue.enqueue(
+                i,
+                PathBuf::from(format!("/p{i}/node_modules")),
+                100,
+                None,
+                JobKind::Delete,
+            );
+        }
+        queue
+    }
+
+    #[test]
+    fn test_enqueue_assigns_unique_ids() {
+        let queue = queue_with(3);
+        let ids: Vec<JobId> = queue.jobs.iter().map(|job| job.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert!(queue.jobs.iter().all(|job| job.status == JobStatus::Queued));
+    }
+
+    #[test]
+    fn test_cancel_queued_job_signals_worker() {
+        let mut queue = queue_with(1);
+        let worker_copy = queue.jobs[0].clone();
+
+        assert!(queue.cancel(1));
+
+        assert_eq!(queue.jobs[0].status, JobStatus::Cancelled);
+        assert!(worker_copy.cancel.is_cancelled());
+    }
+
+    #[test]
+    fn test_cancel_running_job_is_refused() {
+        let mut queue = queue_with(1);
+        queue.jobs[0].status = JobStatus::Running;
+
+        assert!(!queue.cancel(1));
+        assert!(!queue.jobs[0].cancel.is_cancelled());
+    }
+
+    #[test]
+    fn test_purges_cannot_be_cancelled() {
+        let mut queue = queue_with(1);
+        let purge = queue.enqueue(
+            0,
+            PathBuf::from("/p0/.cache-sweep-deleted.1.1.node_modules"),
+            100,
+            None,
+            JobKind::Purge,
+        );
+
+        assert!(!queue.cancel(purge.id));
+        assert_eq!(queue.cancel_all_queued(), vec![0]);
+        assert_eq!(queue.jobs[1].status, JobStatus::Queued);
+        assert!(!purge.cancel.is_cancelled());
+    }
+
+    #[test]
+    fn test_stop_purges_signals_running_and_queued_purges() {
+        let mut queue = queue_with(1);
+        queue.jobs[0].status = JobStatus::Running;
+        let running = queue.enqueue(
+            0,
+            PathBuf::from("/p0/.cache-sweep-deleted.1.1.node_modules"),
+            100,
+            None,
+            JobKind::Purge,
+        );
+        let queued = queue.enqueue(
+            0,
+            PathBuf::from("/p0/.cache-sweep-deleted.1.2.node_modules"),
+            100,
+            None,
+            JobKind::Purge,
+        );
+        queue.get_mut(running.id).unwrap().status = JobStatus::Running;
+
+        assert_eq!(queue.stop_purges(), 2);
+
+        assert!(running.cancel.is_cancelled());
+        assert!(queued.cancel.is_cancelled());
+        assert_eq!(
+            queue.jobs[1].status,
+            JobStatus::Running,
+            "stops between files"
+        );
+        assert_eq!(queue.jobs[2].status, JobStatus::Cancelled);
+        assert!(
+            !queue.jobs[0].cancel.is_cancelled(),
+            "deletions aren't purges"
+        );
+        assert_eq!(queue.stop_purges(), 1, "still finishing");
+    }
+
+    #[test]
+    fn test_cancel_all_queued_skips_running() {
+        let mut queue = queue_with(3);
+        queue.jobs[0].status = JobStatus::Running;
+
+        let cancelled = queue.cancel_all_queued();
+
+        assert_eq!(cancelled, vec![1, 2]);
+        assert_eq!(queue.count(&JobStatus::Running), 1);
+        assert_eq!(queue.count(&JobStatus::Cancelled), 2);
+    }
+
+    #[test]
+    fn test_cancel_at_cursor() {
+        let mut queue = queue_with(2);
+        queue.move_cursor(1);
+
+        assert_eq!(queue.cancel_at_cursor(), Some(1));
+        assert_eq!(queue.jobs[1].status, JobStatus::Cancelled);
+        assert_eq!(queue.cancel_at_cursor(), None, "already cancelled");
+    }
+
+    #[test]
+    fn test_clear_finished_keeps_pending() {
+        let mut queue = queue_with(3);
+        queue.jobs[0].status = JobStatus::Done;
+        queue.jobs[1].status = JobStatus::Failed("boom".to_string());
+        queue.cursor = 2;
+
+        queue.clear_finished();
+
+        assert_eq!(queue.jobs.len(), 1);
+        assert_eq!(queue.cursor, 0);
+        assert!(queue.has_pending());
+    }
+
+    #[test]
+    fn test_job_for_index_returns_newest() {
+        let mut queue = queue_with(1);
+        queue.jobs[0].status = JobStatus::Failed("busy".to_string());
+        queue.enqueue(
+            0,
+            PathBuf::from("/p0/node_modules"),
+            100,
+            None,
+            JobKind::Delete,
+        );
+
+        assert_eq!(queue.job_for_index(0).unwrap().id, 2);
+    }
+
+    #[test]
+    fn test_move_cursor_clamps() {
+        let mut queue = queue_with(2);
+        queue.move_cursor(-1);
+        assert_eq!(queue.cursor, 0);
+        queue.move_cursor(5);
+        assert_eq!(queue.cursor, 1);
+    }
+}
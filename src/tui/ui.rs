@@ -1,3 +1,4 @@
+use std::fmt::Write as _;
 use std::time::{Duration, SystemTime};
 
 use bytesize::ByteSize;
@@ -11,7 +12,9 @@ use ratatui::{
 
 use super::app::{App, Mode, Panel};
 use super::panels;
+use super::queue::{DeleteJob, JobStatus};
 use super::widgets::DualProgressBar;
+use crate::delete::DeleteStrategy;
 
 /// Determines the style for a result item based on its state.
 #[allow(clippy::fn_params_excessive_bools)] // Bools map directly to item state flags
@@ -79,21 +82,56 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
     if app.mode == Mode::SensitiveBlocked {
         draw_sensitive_blocked_popup(frame, app);
     }
+
+    if app.mode == Mode::ActiveConfirm {
+        draw_active_confirm_popup(frame, app);
+    }
+
+    if app.mode == Mode::FreePrompt {
+        draw_free_prompt_popup(frame, app);
+    }
+
+    if app.mode == Mode::FreePlan {
+        draw_free_plan_popup(frame, app);
+    }
 }
 
 fn draw_header(frame: &mut Frame, app: &App, area: Rect, show_progress: bool) {
     let sort_label = app.sort_label();
 
-    let freed_label = if app.dry_run { "would free" } else { "freed" };
-    let dry_run_indicator = if app.dry_run { " [DRY RUN]" } else { "" };
+    let trash = app.delete_strategy == DeleteStrategy::Trash;
+    let freed_label = match (app.dry_run, trash) {
+        (true, _) => "would free",
+        (false, true) => "pending in trash",
+        (false, false) => "freed",
+    };
+    let dry_run_indicator = match (app.dry_run, trash) {
+        (true, true) => " [DRY RUN] [TRASH]",
+        (true, false) => " [DRY RUN]",
+        (false, true) => " [TRASH]",
+        (false, false) => "",
+    };
+
+    let pending = app
+        .delete_queue
+        .jobs
+        .iter()
+        .filter(|j| j.is_pending())
+        .count();
+    let deleting = if pending > 0 {
+        format!(" | deleting {pending}")
+    } else {
+        String::new()
+    };
 
     let prefix = format!(
-        " cache-sweep{} | {} results | {} potential | {} {} | sort:",
+        " cache-sweep{} | {} results | {} potential | {} {}{} | sort:",
         dry_run_indicator,
         app.filtered_indices.len(),
         ByteSize::b(app.active_tab_subtotal()),
         ByteSize::b(app.freed_size),
         freed_label,
+        deleting,
     );
 
     let base_style = Style::default()
@@ -103,11 +141,18 @@ fn draw_header(frame: &mut Frame, app: &App, area: Rect, show_progress: bool) {
         .fg(Color::Yellow)
         .add_modifier(Modifier::BOLD);
 
-    let title_line = Line::from(vec![
+    let mut title_line = Line::from(vec![
         Span::styled(prefix, base_style),
         Span::styled(sort_label, sort_style),
         Span::styled(" ", base_style),
     ]);
+    if !app.filter.is_empty() {
+        title_line.push_span(Span::styled("| filter: ", base_style));
+        title_line.push_span(Span::styled(
+            format!("{} ", app.filter.describe()),
+            sort_style,
+        ));
+    }
 
     if show_progress {
         // Split header into: title (2 lines) + gradient bar (2 lines) + bottom border (1 line)
@@ -246,6 +291,8 @@ fn draw_main(frame: &mut Frame, app: &App, area: Rect) {
         Panel::Results => draw_results_panel(frame, app, area),
         Panel::Info => panels::draw_info(frame, app, area),
         Panel::Analytics => panels::draw_analytics(frame, app, area),
+        Panel::Queue => panels::draw_queue(frame, app, area),
+        Panel::Errors => panels::draw_errors(frame, app, area),
     }
 }
 
@@ -261,11 +308,30 @@ fn draw_results_panel(frame: &mut Frame, app: &App, area: Rect) {
         .split(area);
 
     let title = match app.mode {
-        Mode::Search => format!(" Results (search: {}_) ", app.search_query),
-        Mode::MultiSelect | Mode::Confirm => {
-            format!(" Results ({} selected) ", app.selected_indices.len())
+        Mode::Search => {
+            let mut title = Line::from(format!(" Results (search: {}_) ", app.search_query));
+            // Parse errors show inline; the list keeps the last valid search meanwhile
+            if let Some(e) = &app.search_error {
+                title.push_span(Span::styled(
+                    format!("{e} "),
+                    Style::default().fg(Color::Red),
+                ));
+            }
+            title
         }
-        Mode::Normal | Mode::SensitiveBlocked => " Results - SPACE to delete ".to_string(),
+        Mode::MultiSelect | Mode::Confirm => Line::from(format!(
+            " Results ({} selected) ",
+            app.selected_indices.len()
+        )),
+        Mode::Normal if app.free_target.is_some() => Line::from(format!(
+            " Results - planning to free {} once sizes are in ",
+            ByteSize::b(app.free_target.unwrap_or(0))
+        )),
+        Mode::Normal
+        | Mode::SensitiveBlocked
+        | Mode::ActiveConfirm
+        | Mode::FreePrompt
+        | Mode::FreePlan => Line::from(" Results - SPACE to delete "),
     };
 
     // Calculate column positions based on area width
@@ -330,10 +396,23 @@ fn draw_confirm_popup(frame: &mut Frame, app: &App) {
         .filter_map(|r| r.scan_result.size)
         .sum();
 
+    let active_count = app.count_active_in_selection();
+    let active_note = if active_count > 0 {
+        format!("\n{active_count} active (recently modified) will be skipped")
+    } else {
+        String::new()
+    };
+
+    let verb = if app.delete_strategy == DeleteStrategy::Trash {
+        "Move to trash"
+    } else {
+        "Permanently delete"
+    };
     let text = format!(
-        "Permanently delete {} items ({})?\n\n[Y]es  /  [N]o",
+        "{verb} {} items ({})?{}\n\n[Y]es  /  [N]o",
         app.selected_indices.len(),
-        ByteSize::b(total_size)
+        ByteSize::b(total_size),
+        active_note
     );
 
     let block = Block::bordered().title(" Confirm ");
@@ -420,6 +499,162 @@ fn draw_sensitive_blocked_popup(frame: &mut Frame, app: &App) {
     frame.render_widget(paragraph, area);
 }
 
+fn draw_free_prompt_popup(frame: &mut Frame, app: &App) {
+    let area = popup_area(frame.area(), 40, 20);
+    frame.render_widget(Clear, area);
+
+    let mut text = vec![
+        Line::from("How much space should be freed?"),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("{}_", app.free_input),
+            Style::default().fg(Color::Cyan),
+        )),
+        Line::from(""),
+    ];
+    text.push(app.free_error.as_ref().map_or_else(
+        || {
+            Line::from(Span::styled(
+                "Stale folders that are cheap to rebuild go first.",
+                Style::default().fg(Color::DarkGray),
+            ))
+        },
+        |e| Line::from(Span::styled(e.clone(), Style::default().fg(Color::Red))),
+    ));
+
+    let block = Block::bordered().title(" Free Space ");
+    let paragraph = Paragraph::new(text).block(block).centered();
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_free_plan_popup(frame: &mut Frame, app: &App) {
+    let area = popup_area(frame.area(), 70, 60);
+    frame.render_widget(Clear, area);
+    let Some(plan) = &app.budget else {
+        return;
+    };
+
+    let summary = if plan.reached() {
+        Line::from(format!(
+            "Free {} by deleting {} folders ({} in total)",
+            ByteSize::b(plan.target),
+            plan.steps.len(),
+            ByteSize::b(plan.total())
+        ))
+    } else {
+        Line::from(Span::styled(
+            format!(
+                "Only {} of {} can be freed from this list (protected and active folders are never picked)",
+                ByteSize::b(plan.total()),
+                ByteSize::b(plan.target)
+            ),
+            Style::default().fg(Color::Yellow),
+        ))
+    };
+    let mut text = vec![
+        summary,
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("{:>10}  {:>10}  Path", "Size", "Total"),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+    ];
+
+    // Borders, summary, blank, header, blank and the key hint
+    let rows = usize::from(area.height.saturating_sub(7));
+    let mut previous = app
+        .budget_scroll
+        .checked_sub(1)
+        .and_then(|i| plan.steps.get(i))
+        .map_or(0, |&(_, total)| total);
+    for &(idx, total) in plan.steps.iter().skip(app.budget_scroll).take(rows) {
+        let path = app
+            .results
+            .get(idx)
+            .map(|item| item.scan_result.path.display().to_string())
+            .unwrap_or_default();
+        text.push(Line::from(format!(
+            "{:>10}  {:>10}  {path}",
+            ByteSize::b(total - previous).to_string(),
+            ByteSize::b(total).to_string()
+        )));
+        previous = total;
+    }
+    text.push(Line::from(""));
+    text.push(Line::from(Span::styled(
+        "[Y] Select these  /  [N] Cancel",
+        Style::default().fg(Color::Cyan),
+    )));
+
+    let block = Block::bordered()
+        .title(" Free Space Plan ")
+        .border_style(Style::default().fg(Color::Cyan));
+    frame.render_widget(Paragraph::new(text).block(block), area);
+}
+
+fn draw_active_confirm_popup(frame: &mut Frame, app: &App) {
+    let area = popup_area(frame.area(), 50, 30);
+    frame.render_widget(Clear, area);
+
+    let item = app
+        .pending_active_delete
+        .and_then(|idx| app.results.get(idx));
+    let changed = item
+        .and_then(super::app::ResultItem::last_activity)
+        .map_or_else(
+            || "recently".to_string(),
+            |time| {
+                let mins = SystemTime::now()
+                    .duration_since(time)
+                    .unwrap_or(Duration::ZERO)
+                    .as_secs()
+                    / 60;
+                if mins == 0 {
+                    "less than a minute ago".to_string()
+                } else {
+                    format!("{mins} min ago")
+                }
+            },
+        );
+
+    let text = vec![
+        Line::from(Span::styled(
+            "⚡ Folder Looks Active",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        Line::from(format!("Files inside changed {changed}.")),
+        Line::from("A build or dev server may still be writing to it,"),
+        Line::from(if app.pending_active_archive {
+            "and archiving it now may capture half-written files."
+        } else {
+            "and deleting it now can cause confusing failures."
+        }),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Use --active-window to tune or disable this check.",
+            Style::default().fg(Color::DarkGray),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            if app.pending_active_archive {
+                "[Y] Archive anyway  /  [N] Cancel"
+            } else {
+                "[Y] Delete anyway  /  [N] Cancel"
+            },
+            Style::default().fg(Color::Cyan),
+        )),
+    ];
+
+    let block = Block::bordered()
+        .title(" Active ")
+        .border_style(Style::default().fg(Color::Yellow));
+    let paragraph = Paragraph::new(text).block(block).centered();
+    frame.render_widget(paragraph, area);
+}
+
 /// Builds list items for the results view
 fn build_list_items(
     app: &App,
@@ -431,18 +666,26 @@ fn build_list_items(
     visible
         .iter()
         .enumerate()
-        .map(|(display_idx, (_real_idx, item))| {
+        .map(|(display_idx, (real_idx, item))| {
             let is_cursor = display_idx + app.scroll_offset == app.cursor;
-            build_result_item(app, item, is_cursor, age_width, size_width, path_width)
+            let is_active = app.is_active(*real_idx);
+            let job = app.delete_queue.job_for_index(*real_idx);
+            build_result_item(
+                app, item, job, is_cursor, is_active, age_width, size_width, path_width,
+            )
         })
         .collect()
 }
 
 /// Builds a single result item row
+#[allow(clippy::fn_params_excessive_bools)] // Bools map directly to item state flags
+#[allow(clippy::too_many_arguments)] // Row layout needs item state plus column widths
 fn build_result_item(
     app: &App,
     item: &super::app::ResultItem,
+    job: Option<&DeleteJob>,
     is_cursor: bool,
+    is_active: bool,
     age_width: usize,
     size_width: usize,
     path_width: usize,
@@ -470,16 +713,18 @@ fn build_result_item(
     // Status indicators
     let status = if item.is_deleted {
         if app.dry_run {
-            "[DRY RUN - NOT DELETED] "
+            "[DRY RUN - NOT DELETED] ".to_string()
         } else {
-            "[DELETED] "
+            "[DELETED] ".to_string()
         }
     } else if item.is_deleting {
-        "[DELETING] "
+        deleting_status(job)
+    } else if is_active {
+        "[ACTIVE] ".to_string()
     } else if item.risk.is_sensitive {
-        "⚠️ "
+        "⚠️ ".to_string()
     } else {
-        ""
+        String::new()
     };
 
     let selection_marker = if item.is_selected { "[x] " } else { "[ ] " };
@@ -503,20 +748,59 @@ fn build_result_item(
     ListItem::new(Line::from(Span::styled(line_content, style)))
 }
 
+/// "[QUEUED] " while waiting for a worker, "[DELETING 42%] " once files are going
+fn deleting_status(job: Option<&DeleteJob>) -> String {
+    match job {
+        Some(job) if job.status == JobStatus::Queued => "[QUEUED] ".to_string(),
+        Some(job) if job.progress.files > 0 => panels::percent(job.progress.bytes, job.size)
+            .map_or_else(
+                || "[DELETING] ".to_string(),
+                |pct| format!("[DELETING {pct}%] "),
+            ),
+        _ => "[DELETING] ".to_string(),
+    }
+}
+
 fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
     let has_tabs = app.target_groups.len() > 1;
+    let mut undo = if app.undo_stack.is_empty() {
+        String::new()
+    } else {
+        format!(" | u:undo ({})", app.undo_stack.len())
+    };
+    if !app.delete_queue.jobs.is_empty() {
+        undo.push_str(" | D:queue");
+    }
+    if !app.errors.is_empty() {
+        let _ = write!(undo, " | e:errors ({})", app.errors.len());
+    }
 
     let help_text = match app.mode {
+        _ if app.finishing => {
+            "Finishing deletions… | Ctrl+C/q:stop purging and quit (the next scan offers what's left)"
+                .to_string()
+        }
         Mode::Normal => match app.panel {
-            Panel::Info => "↑/↓:navigate | ←:back | o:open | q:quit".to_string(),
+            Panel::Info => "↑/↓:navigate | ←:back | A:archive | o:open | q:quit".to_string(),
             Panel::Analytics => "↑/↓:scroll | a/Esc:back | q:quit".to_string(),
-            _ if has_tabs => "Tab/⇧Tab:switch | ↑/↓:nav | /:search | s:sort | v:multi | SPACE:del | a:analytics | q:quit".to_string(),
-            Panel::Results => "↑/↓:nav | /:search | s:sort | v:multi | SPACE:del | a:analytics | q:quit".to_string(),
+            Panel::Queue => "↑/↓:nav | c:cancel | C:cancel all queued | x:clear finished | D/Esc:back | q:quit".to_string(),
+            Panel::Errors => "↑/↓:scroll | x:clear | e/Esc:back | q:quit".to_string(),
+            _ if has_tabs => format!("Tab/⇧Tab:switch | ↑/↓:nav | /:search | s:sort | v:multi | SPACE:del{undo} | a:analytics | q:quit"),
+            Panel::Results => format!("↑/↓:nav | /:search | s:sort | v:multi | SPACE:del{undo} | a:analytics | q:quit"),
         },
-        Mode::Search => "Type to filter | Enter:confirm | Esc:cancel".to_string(),
-        Mode::MultiSelect => "SPACE:toggle | a:all | Enter:delete selected | v/Esc:exit".to_string(),
+        Mode::Search => {
+            "Type text or e.g. size>1G age>90d profile:node !path:work/ | Enter:confirm | Esc:cancel"
+                .to_string()
+        }
+        Mode::MultiSelect => "SPACE:toggle | a:all | E:edit in $EDITOR | Enter:delete selected | v/Esc:exit".to_string(),
         Mode::Confirm => "Y:confirm | N/Esc:cancel".to_string(),
         Mode::SensitiveBlocked => "Enter/Esc:dismiss".to_string(),
+        Mode::ActiveConfirm if app.pending_active_archive => {
+            "Y:archive anyway | N/Esc:cancel".to_string()
+        }
+        Mode::ActiveConfirm => "Y:delete anyway | N/Esc:cancel".to_string(),
+        Mode::FreePrompt => "Type a size (e.g. 20GB) | Enter:plan | Esc:cancel".to_string(),
+        Mode::FreePlan => "↑/↓:scroll | Y/Enter:select these | N/Esc:cancel".to_string(),
     };
 
     let footer = Paragraph::new(help_text)
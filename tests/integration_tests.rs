@@ -1,7 +1,7 @@
 #![allow(clippy::unwrap_used, clippy::expect_used)]
 
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 fn cache_sweep_bin() -> PathBuf {
@@ -52,7 +52,7 @@ fn test_json_output_empty_dir() {
 
     // Parse JSON
     let json: serde_json::Value = serde_json::from_str(&stdout).expect("Invalid JSON");
-    assert_eq!(json["version"], 1);
+    assert_eq!(json["version"], 2);
     assert!(json["results"].as_array().unwrap().is_empty());
 }
 
@@ -89,6 +89,426 @@ fn test_json_finds_target() {
         .contains("node_modules"));
 }
 
+/// Checks `value` against the subset of JSON Schema used by `docs/json-output.schema.json`
+/// (`$ref`, `type`, `const`, `enum`, `required`, `properties`, `additionalProperties`,
+/// `items`, `minimum`), returning the first mismatch
+fn check_schema(
+    schema: &serde_json::Value,
+    root: &serde_json::Value,
+    value: &serde_json::Value,
+    at: &str,
+) -> Result<(), String> {
+    use serde_json::Value;
+
+    if let Some(reference) = schema["$ref"].as_str() {
+        let name = reference.strip_prefix("#/$defs/").expect("local $ref");
+        return check_schema(&root["$defs"][name], root, value, at);
+    }
+
+    if let Some(types) = schema.get("type") {
+        let types: Vec<&str> = match types {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => panic!("bad type in schema at {at}"),
+        };
+        let matches = |t: &str| match t {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "integer" => value.is_u64() || value.is_i64(),
+            "boolean" => value.is_boolean(),
+            "null" => value.is_null(),
+            _ => panic!("unsupported type {t}"),
+        };
+        if !types.iter().any(|t| matches(t)) {
+            return Err(format!("{at}: expected {types:?}, got {value}"));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if value != expected {
+            return Err(format!("{at}: expected {expected}, got {value}"));
+        }
+    }
+    if let Some(allowed) = schema["enum"].as_array() {
+        if !allowed.contains(value) {
+            return Err(format!("{at}: {value} not in {allowed:?}"));
+        }
+    }
+    if let (Some(min), Some(n)) = (schema["minimum"].as_i64(), value.as_i64()) {
+        if n < min {
+            return Err(format!("{at}: {n} is below {min}"));
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        for key in schema["required"].as_array().into_iter().flatten() {
+            if !object.contains_key(key.as_str().unwrap()) {
+                return Err(format!("{at}: missing {key}"));
+            }
+        }
+        for (key, field) in object {
+            match schema["properties"].get(key) {
+                Some(field_schema) => {
+                    check_schema(field_schema, root, field, &format!("{at}.{key}"))?;
+                }
+                None if schema["additionalProperties"] == false => {
+                    return Err(format!("{at}: unexpected field {key}"));
+                }
+                None => {}
+            }
+        }
+    }
+    if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+        for (i, item) in array.iter().enumerate() {
+            check_schema(items, root, item, &format!("{at}[{i}]"))?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_json_output_matches_schema() {
+    let schema: serde_json::Value =
+        serde_json::from_str(include_str!("../docs/json-output.schema.json"))
+            .expect("Invalid schema");
+
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let app = temp_dir.path().join("app");
+    fs::create_dir_all(app.join("node_modules").join("left-pad")).expect("Failed to create dirs");
+    fs::write(app.join("package.json"), r#"{"name": "my-app"}"#).expect("Failed to write file");
+    fs::write(
+        app.join("node_modules").join("left-pad").join("index.js"),
+        "x".repeat(10),
+    )
+    .expect("Failed to write file");
+    let cache = temp_dir.path().join("tool").join("__pycache__");
+    fs::create_dir_all(&cache).expect("Failed to create dirs");
+    fs::write(cache.join("a.pyc"), "x".repeat(500)).expect("Failed to write file");
+
+    let output = Command::new(cache_sweep_bin())
+        .arg("--json")
+        .arg("-d")
+        .arg(temp_dir.path())
+        .args(["-t", "node_modules,__pycache__", "-X", "-s", "size"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("Invalid JSON");
+
+    check_schema(&schema, &schema, &json, "$").unwrap();
+
+    // Sorted by size, largest first, with the new fields filled in
+    let results = json["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["target"], "__pycache__");
+    assert_eq!(results[0]["profiles"], serde_json::json!(["python"]));
+    assert_eq!(results[1]["target"], "node_modules");
+    assert_eq!(results[1]["fileCount"], 1);
+    assert_eq!(results[1]["project"]["name"], "my-app");
+    assert_eq!(
+        results[1]["project"]["root"],
+        app.to_string_lossy().as_ref()
+    );
+
+    let meta = &json["meta"];
+    assert_eq!(meta["sort"], "size");
+    assert_eq!(meta["totalSize"], 510);
+    assert_eq!(meta["errorCount"], 0);
+    assert_eq!(
+        meta["targets"],
+        serde_json::json!(["node_modules", "__pycache__"])
+    );
+    assert_eq!(meta["toolVersion"], env!("CARGO_PKG_VERSION"));
+
+    // The validator itself catches mismatches
+    let mut broken = json.clone();
+    broken["results"][0]["size"] = serde_json::json!("big");
+    assert!(check_schema(&schema, &schema, &broken, "$").is_err());
+    broken = json;
+    broken["meta"]["extra"] = serde_json::json!(1);
+    assert!(check_schema(&schema, &schema, &broken, "$").is_err());
+}
+
+#[test]
+fn test_csv_and_tsv_formats() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let odd = temp_dir
+        .path()
+        .join("a, \"quoted\" app")
+        .join("node_modules");
+    let plain = temp_dir.path().join("plain").join("node_modules");
+    fs::create_dir_all(&odd).expect("Failed to create dirs");
+    fs::create_dir_all(&plain).expect("Failed to create dirs");
+    fs::write(odd.join("index.js"), "x".repeat(300)).expect("Failed to write file");
+    fs::write(plain.join("index.js"), "x".repeat(100)).expect("Failed to write file");
+
+    let run = |format: &str, extra: &[&str]| {
+        let output = Command::new(cache_sweep_bin())
+            .args(["--format", format, "-d"])
+            .arg(temp_dir.path())
+            .args(["-t", "node_modules", "-X"])
+            .args(extra)
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    // Largest first, like --json; the comma and quotes are escaped
+    let csv = run("csv", &[]);
+    let lines: Vec<&str> = csv.split_terminator("\r\n").collect();
+    assert_eq!(
+        lines[0],
+        "path,target,profile,project,size,file_count,modified,risk"
+    );
+    assert_eq!(lines.len(), 3);
+    let odd_path = odd.to_string_lossy().replace('"', "\"\"");
+    assert!(lines[1].starts_with(&format!("\"{odd_path}\",node_modules,node,")));
+    assert!(lines[1].contains(",300,1,"));
+    assert!(lines[2].contains(",100,1,"));
+
+    // Filters apply just as with --json; TSV only quotes what needs it
+    let tsv = run("tsv", &["--max-size", "200B"]);
+    let lines: Vec<&str> = tsv.split_terminator("\r\n").collect();
+    assert_eq!(lines.len(), 2);
+    let fields: Vec<&str> = lines[1].split('\t').collect();
+    assert_eq!(fields[0], plain.to_string_lossy());
+    assert_eq!(fields[3], "plain");
+    assert_eq!(fields[4], "100");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_print0_and_paths_keep_raw_bytes() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let newline = temp_dir.path().join("two\nlines").join("node_modules");
+    let latin1 = temp_dir
+        .path()
+        .join(OsStr::from_bytes(b"caf\xe9"))
+        .join("node_modules");
+    let small = temp_dir.path().join("small").join("node_modules");
+    for (dir, bytes) in [(&newline, 500), (&latin1, 400), (&small, 1)] {
+        fs::create_dir_all(dir).expect("Failed to create dirs");
+        fs::write(dir.join("index.js"), vec![0u8; bytes]).expect("Failed to write file");
+    }
+
+    let run = |mode: &str| {
+        let output = Command::new(cache_sweep_bin())
+            .arg(mode)
+            .arg("-d")
+            .arg(temp_dir.path())
+            .args(["-t", "node_modules", "-X", "--min-size", "100B"])
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        output.stdout
+    };
+
+    // Largest first, the small folder filtered out, bytes untouched
+    let mut expected = Vec::new();
+    for path in [&newline, &latin1] {
+        expected.extend_from_slice(path.as_os_str().as_bytes());
+        expected.push(0);
+    }
+    assert_eq!(run("--print0"), expected);
+
+    // Same paths one per line (so the first name spans two lines)
+    let mut lines = expected.clone();
+    for byte in &mut lines {
+        if *byte == 0 {
+            *byte = b'\n';
+        }
+    }
+    assert_eq!(run("--paths"), lines);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_json_path_bytes_for_non_utf8_paths() {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let latin1 = temp_dir
+        .path()
+        .join(OsStr::from_bytes(b"caf\xe9"))
+        .join("node_modules");
+    let plain = temp_dir.path().join("plain").join("node_modules");
+    for dir in [&latin1, &plain] {
+        fs::create_dir_all(dir).expect("Failed to create dirs");
+    }
+
+    let run = |mode: &str| {
+        let output = Command::new(cache_sweep_bin())
+            .arg(mode)
+            .arg("-d")
+            .arg(temp_dir.path())
+            .args(["-t", "node_modules", "-X"])
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).expect("JSON output is UTF-8")
+    };
+
+    let json: serde_json::Value = serde_json::from_str(&run("--json")).unwrap();
+    let stream: Vec<serde_json::Value> = run("--json-stream")
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let results = json["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(stream.len(), 2);
+
+    for result in results.iter().chain(&stream) {
+        let path = result["path"].as_str().unwrap();
+        if path.ends_with("plain/node_modules") {
+            assert!(result.get("pathBytes").is_none());
+            assert!(result["project"].get("rootBytes").is_none());
+        } else {
+            // The text is lossy; the bytes give back the real path
+            assert_eq!(path, latin1.to_string_lossy());
+            let bytes = STANDARD
+                .decode(result["pathBytes"].as_str().unwrap())
+                .unwrap();
+            assert_eq!(bytes, latin1.as_os_str().as_bytes());
+        }
+    }
+
+    // The project root (the lossy folder itself) carries its bytes the same way
+    let project = results
+        .iter()
+        .find_map(|r| r["project"].get("rootBytes"))
+        .expect("rootBytes for the non-UTF-8 project");
+    let bytes = STANDARD.decode(project.as_str().unwrap()).unwrap();
+    assert_eq!(bytes, latin1.parent().unwrap().as_os_str().as_bytes());
+}
+
+#[test]
+fn test_prometheus_textfile_is_written_atomically() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let code = temp_dir.path().join("code");
+    let nm = code.join("web").join("node_modules");
+    fs::create_dir_all(&nm).expect("Failed to create dirs");
+    fs::write(nm.join("index.js"), vec![0u8; 700]).expect("Failed to write file");
+    let metrics = temp_dir.path().join("cache_sweep.prom");
+    fs::write(&metrics, "stale").expect("Failed to write file");
+
+    let output = Command::new(cache_sweep_bin())
+        .arg("-d")
+        .arg(&code)
+        .args(["-X", "--format", "prometheus", "-o"])
+        .arg(&metrics)
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let text = fs::read_to_string(&metrics).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert!(lines.contains(&r#"cache_sweep_profile_bytes{profile="node"} 700"#));
+    assert!(lines.contains(&r#"cache_sweep_target_folders{target="node_modules"} 1"#));
+    let root = format!(
+        "cache_sweep_root_bytes{{root=\"{}\"}} 700",
+        code.to_string_lossy()
+    );
+    assert!(lines.contains(&root.as_str()));
+    assert!(lines.contains(&"cache_sweep_scan_errors 0"));
+    // Only the final file is left behind
+    let files: Vec<_> = fs::read_dir(temp_dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    assert_eq!(files.len(), 2);
+}
+
+#[test]
+fn test_html_report_is_self_contained() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    for (project, target, bytes) in [
+        ("web", "node_modules", 400),
+        ("api", "node_modules", 200),
+        ("tool", "__pycache__", 50),
+    ] {
+        let dir = temp_dir.path().join("code").join(project).join(target);
+        fs::create_dir_all(&dir).expect("Failed to create dirs");
+        fs::write(dir.join("data"), vec![0u8; bytes]).expect("Failed to write file");
+    }
+    let report = temp_dir.path().join("report.html");
+
+    let output = Command::new(cache_sweep_bin())
+        .arg("-d")
+        .arg(temp_dir.path().join("code"))
+        .args(["-X", "--report"])
+        .arg(&report)
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("3 folders"));
+
+    let html = fs::read_to_string(&report).expect("Report not written");
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(!html.contains("http://") && !html.contains("https://"));
+
+    let start = html.find(r#"type="application/json">"#).unwrap() + 24;
+    let end = start + html[start..].find("</script>").unwrap();
+    let data: serde_json::Value = serde_json::from_str(&html[start..end]).expect("Invalid data");
+    assert_eq!(data["summary"]["count"], 3);
+    assert_eq!(data["summary"]["totalSize"], 650);
+    assert_eq!(data["profiles"][0]["name"], "node");
+    assert_eq!(data["profiles"][0]["totalSize"], 600);
+    assert_eq!(data["targetStats"][1]["name"], "__pycache__");
+    assert_eq!(data["results"].as_array().unwrap().len(), 3);
+
+    let conflict = Command::new(cache_sweep_bin())
+        .args(["--report", "x.html", "--json"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!conflict.status.success());
+}
+
+#[test]
+fn test_fail_thresholds_set_exit_code() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    for name in ["a", "b"] {
+        let dir = temp_dir.path().join(name).join("node_modules");
+        fs::create_dir_all(&dir).expect("Failed to create dirs");
+        fs::write(dir.join("index.js"), vec![0u8; 1000]).expect("Failed to write file");
+    }
+    let run = |args: &[&str]| {
+        Command::new(cache_sweep_bin())
+            .arg("-d")
+            .arg(temp_dir.path())
+            .args(["-t", "node_modules"])
+            .args(args)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    // Thresholds alone check quietly
+    let within = run(&["--fail-over", "5KB,python=1B", "--fail-count", "2"]);
+    assert!(within.status.success());
+    assert!(within.stdout.is_empty() && within.stderr.is_empty());
+
+    let over = run(&["--fail-over", "node=1500B", "--fail-count", "1"]);
+    assert_eq!(over.status.code(), Some(3));
+    assert!(over.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&over.stderr);
+    assert!(stderr.contains("2.0 KiB reclaimable in 2 folders"));
+    assert!(stderr.contains("node: 2.0 KiB reclaimable, over 1.5 KiB"));
+    assert!(stderr.contains("count: 2 folders, over 1"));
+
+    // Output modes still print their results
+    let json = run(&["--json", "--fail-over", "1KB"]);
+    assert_eq!(json.status.code(), Some(3));
+    let output: serde_json::Value = serde_json::from_slice(&json.stdout).expect("Invalid JSON");
+    assert_eq!(output["results"].as_array().unwrap().len(), 2);
+}
+
 #[test]
 fn test_exclude_works() {
     let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
@@ -121,6 +541,150 @@ fn test_exclude_works() {
     assert!(results[0]["path"].as_str().unwrap().contains("proj2"));
 }
 
+#[test]
+fn test_json_is_sorted_and_stream_emits_everything() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+
+    let mut expected = Vec::new();
+    for name in ["delta", "alpha", "echo", "charlie", "bravo"] {
+        let nm = temp_dir.path().join(name).join("node_modules");
+        fs::create_dir_all(&nm).expect("Failed to create dirs");
+        fs::write(nm.join("index.js"), name).expect("Failed to write file");
+        expected.push(nm.to_string_lossy().to_string());
+    }
+    expected.sort();
+
+    let run = |mode: &str| {
+        let output = Command::new(cache_sweep_bin())
+            .arg(mode)
+            .arg("-d")
+            .arg(temp_dir.path())
+            .args(["-t", "node_modules", "-X", "-s", "path"])
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    // Sizes finish in any order, but the document is always sorted
+    for _ in 0..3 {
+        let json: serde_json::Value = serde_json::from_str(&run("--json")).expect("Invalid JSON");
+        let paths: Vec<&str> = json["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["path"].as_str().unwrap())
+            .collect();
+        assert_eq!(paths, expected);
+    }
+
+    let mut streamed: Vec<String> = run("--json-stream")
+        .lines()
+        .map(|line| {
+            let json: serde_json::Value = serde_json::from_str(line).expect("Invalid JSON line");
+            assert!(json["size"].as_u64().unwrap() > 0);
+            json["path"].as_str().unwrap().to_string()
+        })
+        .collect();
+    streamed.sort();
+    assert_eq!(streamed, expected);
+}
+
+#[test]
+fn test_size_and_age_filters() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+
+    let small = temp_dir.path().join("small").join("node_modules");
+    let big = temp_dir.path().join("big").join("node_modules");
+    fs::create_dir_all(&small).expect("Failed to create dirs");
+    fs::create_dir_all(&big).expect("Failed to create dirs");
+    fs::write(small.join("a.js"), "x").expect("Failed to write file");
+    fs::write(big.join("a.js"), vec![0u8; 4096]).expect("Failed to write file");
+
+    let paths = |extra: &[&str], stream: bool| -> Vec<String> {
+        let output = Command::new(cache_sweep_bin())
+            .arg(if stream { "--json-stream" } else { "--json" })
+            .arg("-d")
+            .arg(temp_dir.path())
+            .args(["-t", "node_modules", "-X"])
+            .args(extra)
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let results: Vec<serde_json::Value> = if stream {
+            stdout
+                .lines()
+                .map(|line| serde_json::from_str(line).expect("Invalid JSON line"))
+                .collect()
+        } else {
+            let json: serde_json::Value = serde_json::from_str(&stdout).expect("Invalid JSON");
+            json["results"].as_array().unwrap().clone()
+        };
+        results
+            .iter()
+            .map(|r| r["path"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let big_only = paths(&["--min-size", "1KiB"], false);
+    assert_eq!(big_only.len(), 1);
+    assert!(big_only[0].contains("big"));
+
+    let small_only = paths(&["--max-size", "1KB"], true);
+    assert_eq!(small_only.len(), 1);
+    assert!(small_only[0].contains("small"));
+
+    // Everything was just written
+    assert!(paths(&["--older-than", "1d"], false).is_empty());
+    assert_eq!(paths(&["--newer-than", "1d"], true).len(), 2);
+
+    let output = Command::new(cache_sweep_bin())
+        .args(["--json", "--older-than", "soon"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid age"));
+}
+
+#[test]
+fn test_filter_query() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    for (project, target, bytes) in [
+        ("work-api", "node_modules", 4096),
+        ("home-app", "node_modules", 4096),
+        ("home-app", "__pycache__", 4096),
+        ("home-lib", "node_modules", 10),
+    ] {
+        let dir = temp_dir.path().join(project).join(target);
+        fs::create_dir_all(&dir).expect("Failed to create dirs");
+        fs::write(dir.join("a"), vec![0u8; bytes]).expect("Failed to write file");
+    }
+    let run = |query: &str| {
+        Command::new(cache_sweep_bin())
+            .arg("--paths")
+            .arg("-d")
+            .arg(temp_dir.path())
+            .args(["-s", "path", "--filter", query])
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    // Everything was just written, so it all counts as active
+    let output = run("risk:safe");
+    assert!(output.status.success() && output.stdout.is_empty());
+    let output = run("size>1KB profile:node !path:work- risk:active");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].ends_with("home-app/node_modules"));
+
+    let output = run("size>1KB colour:red");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown field \"colour\""));
+}
+
 #[test]
 fn test_multiple_profiles() {
     let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
@@ -277,3 +841,543 @@ fn test_json_stream_empty_dir() {
     // No output for empty results
     assert!(stdout.trim().is_empty());
 }
+
+#[test]
+fn test_project_config_keep_add_and_exclude() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+
+    // A kept project, and one that adds and excludes folders
+    let kept = temp_dir.path().join("kept");
+    let custom = temp_dir.path().join("custom");
+    fs::create_dir_all(kept.join("node_modules")).expect("Failed to create dirs");
+    fs::create_dir_all(custom.join("fixtures")).expect("Failed to create dirs");
+    fs::create_dir_all(custom.join("docs").join("node_modules")).expect("Failed to create dirs");
+    fs::create_dir_all(custom.join("node_modules")).expect("Failed to create dirs");
+    fs::write(kept.join(".cache-sweep-keep"), "").expect("Failed to write file");
+    fs::write(
+        custom.join(".cache-sweep.toml"),
+        "targets = [\"fixtures\"]\nexclude = [\"docs/node_modules\"]",
+    )
+    .expect("Failed to write file");
+
+    let output = Command::new(cache_sweep_bin())
+        .arg("--json")
+        .arg("-d")
+        .arg(temp_dir.path())
+        .arg("-t")
+        .arg("node_modules")
+        .arg("-X") // Show protected (kept projects are only listed with -X)
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("Invalid JSON");
+    let results = json["results"].as_array().unwrap();
+    let find = |suffix: &str| {
+        results
+            .iter()
+            .find(|r| r["path"].as_str().unwrap().ends_with(suffix))
+    };
+
+    assert_eq!(results.len(), 3);
+    let kept = find("kept/node_modules").expect("kept project still listed with -X");
+    assert_eq!(kept["riskAnalysis"]["isSensitive"], true);
+    assert!(kept["riskAnalysis"]["reason"]
+        .as_str()
+        .unwrap()
+        .contains(".cache-sweep-keep"));
+    assert!(find("custom/fixtures").is_some());
+    assert!(find("custom/node_modules").is_some());
+    assert!(find("docs/node_modules").is_none());
+}
+
+#[test]
+fn test_trash_subcommand_only_sees_own_items() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let data_home = temp_dir.path().join("data");
+    let state_home = temp_dir.path().join("state");
+
+    // Something another program put in the trash
+    let foreign = data_home.join("Trash").join("files").join("photos");
+    fs::create_dir_all(&foreign).expect("Failed to create dirs");
+
+    let run = |args: &[&str]| {
+        Command::new(cache_sweep_bin())
+            .args(args)
+            .env("XDG_DATA_HOME", &data_home)
+            .env("XDG_STATE_HOME", &state_home)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    let list = run(&["trash", "list"]);
+    assert!(list.status.success());
+    assert!(String::from_utf8_lossy(&list.stdout).contains("Nothing in the trash"));
+
+    let restore = run(&["trash", "restore", "photos"]);
+    assert!(!restore.status.success());
+    assert!(String::from_utf8_lossy(&restore.stderr).contains("not in the trash"));
+
+    let empty = run(&["trash", "empty"]);
+    assert!(empty.status.success());
+    assert!(
+        foreign.exists(),
+        "trash empty must not touch other programs' items"
+    );
+}
+
+#[test]
+fn test_history_lists_filters_and_sums_by_month() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let state_home = temp_dir.path().join("state");
+    let log_dir = state_home.join("cache-sweep");
+    fs::create_dir_all(&log_dir).expect("Failed to create dirs");
+
+    // Mid-month timestamps, so the month doesn't depend on the local time zone
+    let entry = |timestamp: i64, path: &str, freed: u64, outcome: &str| {
+        format!(
+            r#"{{"timestamp":{timestamp},"path":"{path}","size":{freed},"freed":{freed},"target":"node_modules","profiles":["node"],"risk":"safe","dryRun":false,"strategy":"delete","outcome":"{outcome}"}}"#
+        )
+    };
+    let log = [
+        entry(1_789_300_000, "/code/a/node_modules", 1000, "deleted"),
+        entry(1_791_900_000, "/code/b/node_modules", 2000, "deleted"),
+        entry(1_791_900_100, "/code/c/node_modules", 500, "failed"),
+    ]
+    .join("\n");
+    fs::write(log_dir.join("audit.jsonl"), log + "\n").expect("Failed to write log");
+
+    let run = |args: &[&str]| {
+        let output = Command::new(cache_sweep_bin())
+            .arg("history")
+            .args(args)
+            .env("XDG_STATE_HOME", &state_home)
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    let all = run(&[]);
+    assert!(all.contains("/code/a/node_modules"));
+    assert!(all.contains("2026-09          1"));
+    assert!(all.contains("2026-10          2"));
+
+    let failed = run(&["--failed"]);
+    assert!(failed.contains("/code/c/node_modules"));
+    assert!(!failed.contains("/code/b/node_modules"));
+
+    let september = run(&["--until", "2026-09-30"]);
+    assert!(september.contains("/code/a/node_modules"));
+    assert!(!september.contains("2026-10"));
+}
+
+#[test]
+fn test_history_path_finds_cleans_from_relative_scans() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let state_home = temp_dir.path().join("state");
+    let projects = temp_dir.path().join("projects");
+    fs::create_dir_all(projects.join("app/node_modules")).expect("Failed to create dirs");
+    fs::create_dir_all(projects.join("other/node_modules")).expect("Failed to create dirs");
+
+    let run = |dir: &Path, args: &[&str]| {
+        let output = Command::new(cache_sweep_bin())
+            .args(args)
+            .current_dir(dir)
+            .env("XDG_STATE_HOME", &state_home)
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    run(
+        &projects,
+        &[
+            "clean",
+            "-d",
+            ".",
+            "-t",
+            "node_modules",
+            "--active-window",
+            "0",
+            "--yes",
+        ],
+    );
+    let audit = fs::read_to_string(state_home.join("cache-sweep/audit.jsonl"))
+        .expect("Failed to read audit log");
+    assert_eq!(audit.lines().count(), 2);
+    assert!(!audit.contains("\"./"), "logged a relative path: {audit}");
+
+    // Relative and absolute filters both match, from wherever history runs
+    let relative = run(&projects, &["history", "--path", "app"]);
+    assert!(relative.contains("app/node_modules"), "{relative}");
+    assert!(!relative.contains("other/node_modules"));
+    let absolute = run(
+        temp_dir.path(),
+        &[
+            "history",
+            "--path",
+            projects.join("other").to_str().unwrap(),
+        ],
+    );
+    assert!(absolute.contains("other/node_modules"), "{absolute}");
+    assert!(!absolute.contains("app/node_modules"));
+}
+
+#[test]
+fn test_restore_lists_and_unpacks_archives() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let state_home = temp_dir.path().join("state");
+    let archives = temp_dir.path().join("archives");
+    fs::create_dir_all(state_home.join("cache-sweep")).expect("Failed to create dirs");
+    fs::create_dir_all(&archives).expect("Failed to create dirs");
+
+    // A tarball laid out the way cache-sweep writes them: the folder at the top
+    let source = temp_dir.path().join("source/.venv");
+    fs::create_dir_all(&source).expect("Failed to create dirs");
+    fs::write(source.join("pyvenv.cfg"), "home = /usr/bin\n").expect("Failed to write file");
+    let archive = archives.join("app-.venv-0000abcd.tar.gz");
+    let file = fs::File::create(&archive).expect("Failed to create archive");
+    let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+        file,
+        flate2::Compression::default(),
+    ));
+    builder
+        .append_dir_all(".venv", &source)
+        .expect("Failed to write archive");
+    builder
+        .into_inner()
+        .and_then(flate2::write::GzEncoder::finish)
+        .expect("Failed to finish archive");
+
+    let original = temp_dir.path().join("app/.venv");
+    let index = serde_json::json!({
+        "archive": archive,
+        "originalPath": original,
+        "archivedAt": 1_789_300_000,
+        "entries": 2,
+        "size": 16,
+        "compressedSize": fs::metadata(&archive).unwrap().len(),
+    });
+    fs::write(
+        state_home.join("cache-sweep/archives.jsonl"),
+        format!("{index}\n"),
+    )
+    .expect("Failed to write index");
+
+    let run = |args: &[&str]| {
+        Command::new(cache_sweep_bin())
+            .arg("restore")
+            .args(args)
+            .env("XDG_STATE_HOME", &state_home)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    let list = run(&[]);
+    assert!(list.status.success());
+    let stdout = String::from_utf8_lossy(&list.stdout);
+    assert!(stdout.contains(&original.display().to_string()));
+    assert!(stdout.contains("1 archives"));
+
+    let restored = run(&[original.to_str().unwrap()]);
+    assert!(restored.status.success());
+    assert_eq!(
+        fs::read_to_string(original.join("pyvenv.cfg")).unwrap(),
+        "home = /usr/bin\n"
+    );
+    assert!(!archive.exists());
+
+    let missing = run(&[original.to_str().unwrap()]);
+    assert!(!missing.status.success());
+}
+
+#[test]
+fn test_clean_requires_yes_and_never_touches_protected() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let projects = temp_dir.path().join("projects");
+    let app = projects.join("app/node_modules");
+    let kept = projects.join("kept/node_modules");
+    fs::create_dir_all(&app).expect("Failed to create dirs");
+    fs::create_dir_all(&kept).expect("Failed to create dirs");
+    fs::write(app.join("index.js"), "module.exports = 1;\n").expect("Failed to write file");
+    fs::write(projects.join("kept/.cache-sweep-keep"), "").expect("Failed to write file");
+
+    let run = |args: &[&str]| {
+        Command::new(cache_sweep_bin())
+            .arg("clean")
+            .arg("-d")
+            .arg(&projects)
+            .args(["-t", "node_modules", "-X", "--active-window", "0"])
+            .args(args)
+            .env("XDG_STATE_HOME", temp_dir.path().join("state"))
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    // Refuses outright without --yes
+    let refused = run(&[]);
+    assert!(!refused.status.success());
+    assert!(app.exists());
+
+    let preview = run(&["--dry-run"]);
+    assert!(preview.status.success());
+    let stdout = String::from_utf8_lossy(&preview.stdout);
+    assert!(stdout.contains("would delete"));
+    assert!(stdout.contains("Would delete 1 folders"));
+    assert!(app.exists());
+
+    let cleaned = run(&["--yes", "--json"]);
+    assert!(cleaned.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&cleaned.stdout).expect("Invalid JSON");
+    let results = report["results"].as_array().unwrap();
+    let outcome = |suffix: &str| {
+        results
+            .iter()
+            .find(|r| r["path"].as_str().unwrap().ends_with(suffix))
+            .map(|r| r["outcome"].as_str().unwrap().to_string())
+    };
+    assert_eq!(outcome("app/node_modules").as_deref(), Some("deleted"));
+    assert_eq!(outcome("kept/node_modules").as_deref(), Some("skipped"));
+    assert_eq!(report["meta"]["removed"], 1);
+    assert_eq!(report["meta"]["freed"], 20);
+    assert!(!app.exists());
+    assert!(kept.exists(), "protected folders are never deleted");
+
+    // The preview and the deletion went into the audit log; the skipped folder didn't
+    let audit = fs::read_to_string(temp_dir.path().join("state/cache-sweep/audit.jsonl"))
+        .expect("Failed to read audit log");
+    let entries: Vec<serde_json::Value> = audit
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Invalid audit entry"))
+        .collect();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["dryRun"], true);
+    assert_eq!(entries[1]["dryRun"], false);
+    assert_eq!(entries[1]["outcome"], "deleted");
+    assert!(entries
+        .iter()
+        .all(|e| e["path"].as_str().unwrap().ends_with("app/node_modules")));
+}
+
+#[cfg(unix)] // Telling whether the owning process is still running needs a Unix pid check
+#[test]
+fn test_scan_skips_undo_folders_and_offers_orphaned_ones() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let app = temp_dir.path().join("app");
+    let mut finished = Command::new("true").spawn().expect("Failed to spawn");
+    let dead = finished.id();
+    finished.wait().expect("Failed to wait");
+    // This test process stands in for a TUI that still owns its undo folder
+    let alive = std::process::id();
+    let owned = app.join(format!(".cache-sweep-deleted.{alive}.1.node_modules"));
+    let orphaned = app.join(format!(".cache-sweep-deleted.{dead}.2.node_modules"));
+    for dir in [&owned, &orphaned] {
+        fs::create_dir_all(dir.join("pkg/node_modules")).expect("Failed to create dirs");
+        fs::write(dir.join("pkg/node_modules/index.js"), "x").expect("Failed to write file");
+    }
+
+    let run = |command: &[&str]| {
+        let output = Command::new(cache_sweep_bin())
+            .args(command)
+            .arg("-d")
+            .arg(temp_dir.path())
+            .args(["-t", "node_modules", "-X", "--active-window", "0", "--json"])
+            .env("XDG_STATE_HOME", temp_dir.path().join("state"))
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        let report: serde_json::Value =
+            serde_json::from_slice(&output.stdout).expect("Invalid JSON");
+        report["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| PathBuf::from(r["path"].as_str().unwrap()))
+            .collect::<Vec<_>>()
+    };
+
+    // Only the orphaned folder itself: nothing nested inside either undo folder
+    assert_eq!(run(&[]), vec![orphaned.clone()]);
+
+    assert_eq!(run(&["clean", "--yes"]), vec![orphaned.clone()]);
+    assert!(!orphaned.exists());
+    assert!(owned.join("pkg/node_modules").exists());
+}
+
+#[test]
+fn test_top_level_flags_are_rejected_before_clean() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let app = temp_dir.path().join("app/node_modules");
+    fs::create_dir_all(&app).expect("Failed to create dirs");
+
+    // These used to be parsed and then dropped, so the clean went ahead for real
+    for flags in [&["--dry-run"][..], &["--trash"], &["--json"]] {
+        let output = Command::new(cache_sweep_bin())
+            .args(flags)
+            .arg("clean")
+            .arg("-d")
+            .arg(temp_dir.path())
+            .args(["-t", "node_modules", "--active-window", "0", "--yes"])
+            .env("XDG_STATE_HOME", temp_dir.path().join("state"))
+            .output()
+            .expect("Failed to execute command");
+        assert_eq!(output.status.code(), Some(2), "{flags:?}");
+        assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+        assert!(app.exists(), "{flags:?} deleted the folder");
+    }
+}
+
+#[cfg(unix)] // Setting a directory's mtime needs it opened as a file
+#[test]
+fn test_clean_free_deletes_only_what_is_needed() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let projects = temp_dir.path().join("projects");
+    let folder = |name: &str, bytes: usize, stale: bool| {
+        let dir = projects.join(name).join("node_modules");
+        fs::create_dir_all(&dir).expect("Failed to create dirs");
+        let file = dir.join("index.js");
+        fs::write(&file, vec![0u8; bytes]).expect("Failed to write file");
+        if stale {
+            for path in [&file, &dir] {
+                fs::File::open(path)
+                    .and_then(|f| f.set_modified(std::time::SystemTime::UNIX_EPOCH))
+                    .expect("Failed to set mtime");
+            }
+        }
+        dir
+    };
+    let (fresh, stale, also_stale) = (
+        folder("fresh", 3000, false),
+        folder("stale", 1000, true),
+        folder("also-stale", 200, true),
+    );
+
+    let output = Command::new(cache_sweep_bin())
+        .arg("clean")
+        .arg("-d")
+        .arg(&projects)
+        .args(["-t", "node_modules", "--active-window", "0"])
+        .args(["--free", "800B", "--dry-run", "--json"])
+        .env("XDG_STATE_HOME", temp_dir.path().join("state"))
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).expect("Invalid JSON");
+    let results = report["results"].as_array().unwrap();
+    // The stale folder alone is enough; the bigger but fresh one is left alone
+    assert_eq!(results.len(), 1);
+    assert!(results[0]["path"]
+        .as_str()
+        .unwrap()
+        .ends_with("/stale/node_modules"));
+    assert_eq!(results[0]["outcome"], "wouldDelete");
+    assert!(fresh.exists() && stale.exists() && also_stale.exists());
+}
+
+#[test]
+fn test_apply_rechecks_a_saved_plan() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let projects = temp_dir.path().join("projects");
+    let folder = |name: &str| {
+        let dir = projects.join(name).join("node_modules");
+        fs::create_dir_all(&dir).expect("Failed to create dirs");
+        fs::write(dir.join("index.js"), vec![0u8; 1000]).expect("Failed to write file");
+        dir
+    };
+    let (same, grown, gone, kept, unlisted) = (
+        folder("same"),
+        folder("grown"),
+        folder("gone"),
+        folder("kept"),
+        folder("unlisted"),
+    );
+
+    let scanned = Command::new(cache_sweep_bin())
+        .arg("--json")
+        .arg("-d")
+        .arg(&projects)
+        .args(["-t", "node_modules"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(scanned.status.success());
+
+    // Review: drop one folder from the plan; then the disk changes under the rest
+    let mut plan: serde_json::Value = serde_json::from_slice(&scanned.stdout).unwrap();
+    plan["results"]
+        .as_array_mut()
+        .unwrap()
+        .retain(|r| !r["path"].as_str().unwrap().contains("unlisted"));
+    let plan_file = temp_dir.path().join("plan.json");
+    fs::write(&plan_file, plan.to_string()).expect("Failed to write plan");
+    fs::write(grown.join("big.js"), vec![0u8; 5000]).expect("Failed to write file");
+    fs::remove_dir_all(&gone).expect("Failed to remove dir");
+    fs::write(projects.join("kept/.cache-sweep-keep"), "").expect("Failed to write file");
+
+    // The folders were just written, so they'd all look like active builds
+    let run = |args: &[&str]| {
+        Command::new(cache_sweep_bin())
+            .arg("apply")
+            .arg(&plan_file)
+            .args(["--active-window", "0"])
+            .args(args)
+            .env("XDG_STATE_HOME", temp_dir.path().join("state"))
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    assert!(!run(&[]).status.success(), "refuses without --yes");
+    // Top-level flags can't sneak in before the subcommand
+    for flag in ["--dry-run", "--trash"] {
+        let output = Command::new(cache_sweep_bin())
+            .args([flag, "apply"])
+            .arg(&plan_file)
+            .arg("--yes")
+            .env("XDG_STATE_HOME", temp_dir.path().join("state"))
+            .output()
+            .expect("Failed to execute command");
+        assert_eq!(output.status.code(), Some(2), "{flag}");
+        assert!(same.exists(), "{flag} deleted the folder");
+    }
+    let preview = run(&["--dry-run"]);
+    assert!(preview.status.success());
+    assert!(String::from_utf8_lossy(&preview.stdout).contains("Would delete 1 folders"));
+    assert!(same.exists());
+
+    let applied = run(&["--yes", "--json"]);
+    assert!(applied.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&applied.stdout).expect("Invalid JSON");
+    let reason = |suffix: &str| {
+        let result = report["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|r| r["path"].as_str().unwrap().ends_with(suffix))
+            .expect("result for every planned folder");
+        (
+            result["outcome"].as_str().unwrap().to_string(),
+            result["reason"].as_str().unwrap_or_default().to_string(),
+        )
+    };
+    assert_eq!(reason("same/node_modules").0, "deleted");
+    let (outcome, why) = reason("grown/node_modules");
+    assert!(
+        outcome == "skipped" && why.starts_with("size changed"),
+        "{why}"
+    );
+    let (outcome, why) = reason("gone/node_modules");
+    assert!(outcome == "skipped" && why.starts_with("missing"), "{why}");
+    let (outcome, why) = reason("kept/node_modules");
+    assert!(
+        outcome == "skipped" && why.starts_with("protected"),
+        "{why}"
+    );
+    assert_eq!(report["results"].as_array().unwrap().len(), 4);
+
+    assert!(!same.exists());
+    assert!(grown.exists() && kept.exists() && unlisted.exists());
+}